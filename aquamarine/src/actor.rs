@@ -21,6 +21,7 @@ use std::{
 
 use avm_server::CallResults;
 use futures::FutureExt;
+use tracing::{Instrument, Span};
 
 use fluence_libp2p::PeerId;
 use particle_execution::{ParticleFunctionStatic, ServiceFunction};
@@ -33,7 +34,7 @@ use crate::particle_functions::{Functions, SingleCallStat};
 use crate::{AquaRuntime, InterpretationStats};
 
 pub struct Actor<RT, F> {
-    /// Particle of that actor is expired after that deadline
+    /// Particle of that actor is expired after that deadline, possibly earlier than its TTL
     deadline: Deadline,
     future: Option<(usize, Fut<RT>)>,
     mailbox: VecDeque<Particle>,
@@ -46,6 +47,10 @@ pub struct Actor<RT, F> {
     /// Particles and call results will be processed in the security scope of this peer id
     /// It's either `host_peer_id` or owner-specific spell peer id
     current_peer_id: PeerId,
+    /// Span of the particle, all actor's work is recorded within it
+    span: Span,
+    /// Log data trace and call results if particle interpretation fails
+    log_failed_traces: bool,
 }
 
 impl<RT, F> Actor<RT, F>
@@ -53,9 +58,16 @@ where
     RT: AquaRuntime + ParticleExecutor<Particle = (Particle, CallResults), Future = Fut<RT>>,
     F: ParticleFunctionStatic,
 {
-    pub fn new(particle: &Particle, functions: Functions<F>, current_peer_id: PeerId) -> Self {
+    pub fn new(
+        particle: &Particle,
+        functions: Functions<F>,
+        current_peer_id: PeerId,
+        log_failed_traces: bool,
+        deadline: Deadline,
+        span: Span,
+    ) -> Self {
         Self {
-            deadline: Deadline::from(particle),
+            deadline,
             functions,
             future: None,
             mailbox: <_>::default(),
@@ -71,6 +83,8 @@ where
                 data: vec![],
             },
             current_peer_id,
+            span,
+            log_failed_traces,
         }
     }
 
@@ -108,6 +122,9 @@ where
     ) -> Poll<FutResult<(usize, RT), RoutingEffects, InterpretationStats>> {
         use Poll::Ready;
 
+        let span = self.span.clone();
+        let _guard = span.enter();
+
         self.waker = Some(cx.waker().clone());
 
         self.functions.poll(cx);
@@ -123,6 +140,7 @@ where
             let effects = RoutingEffects {
                 particle: r.effects.particle,
                 next_peers: r.effects.next_peers,
+                span: self.span.clone(),
             };
             return Poll::Ready(FutResult {
                 vm: (vm_id, r.vm),
@@ -139,6 +157,9 @@ where
     /// If actor is in the middle of executing previous particle, vm is returned
     /// If actor's mailbox is empty, vm is returned
     pub fn poll_next(&mut self, vm_id: usize, vm: RT, cx: &mut Context<'_>) -> ActorPoll<RT> {
+        let span = self.span.clone();
+        let _guard = span.enter();
+
         self.waker = Some(cx.waker().clone());
 
         self.functions.poll(cx);
@@ -170,7 +191,14 @@ where
         // Take ownership of vm to process particle
         self.future = Some((
             vm_id,
-            vm.execute((particle, calls), waker, self.current_peer_id),
+            vm.execute(
+                (particle, calls),
+                waker,
+                self.current_peer_id,
+                self.log_failed_traces,
+            )
+            .instrument(self.span.clone())
+            .boxed(),
         ));
 
         ActorPoll::Executing(stats)
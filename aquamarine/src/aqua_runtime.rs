@@ -66,11 +66,13 @@ impl AquaRuntime for AVM<DataStoreError> {
         waker: Waker,
     ) -> BoxFuture<'static, Result<Self, Self::Error>> {
         task::spawn_blocking(move || {
-            let data_store = Box::new(ParticleDataStore::new(
+            let mut data_store = ParticleDataStore::new(
                 config.particles_dir,
                 config.particles_vault_dir,
                 config.particles_anomaly_dir,
-            ));
+            );
+            data_store.keep_vaults = config.keep_particle_vaults;
+            let data_store = Box::new(data_store);
             let config = AVMConfig {
                 data_store,
                 air_wasm_path: config.air_interpreter,
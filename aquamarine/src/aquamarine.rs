@@ -19,7 +19,11 @@ use std::task::Poll;
 use std::time::Duration;
 
 use async_std::{task, task::JoinHandle};
-use futures::{channel::mpsc, SinkExt, StreamExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    SinkExt, StreamExt,
+};
+use tracing::Span;
 
 use fluence_libp2p::types::{BackPressuredInlet, BackPressuredOutlet, Outlet};
 use fluence_libp2p::PeerId;
@@ -30,10 +34,10 @@ use peer_metrics::{ParticleExecutorMetrics, VmPoolMetrics};
 
 use crate::aqua_runtime::AquaRuntime;
 use crate::command::Command;
-use crate::command::Command::{AddService, Ingest, RemoveService};
+use crate::command::Command::{AddService, Ingest, PoolStatus, RemoveService};
 use crate::error::AquamarineApiError;
 use crate::particle_effects::RoutingEffects;
-use crate::vm_pool::VmPool;
+use crate::vm_pool::{VmPool, VmPoolStatus};
 use crate::{Plumber, VmPoolConfig};
 
 pub type EffectsChannel = Outlet<Result<RoutingEffects, AquamarineApiError>>;
@@ -60,7 +64,14 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> AquamarineBackend<RT, F> {
         let sender = AquamarineApi::new(outlet, config.execution_timeout);
         let vm_pool = VmPool::new(config.pool_size, runtime_config, vm_pool_metrics);
         let host_peer_id = key_manager.get_host_peer_id();
-        let plumber = Plumber::new(vm_pool, builtins, plumber_metrics, key_manager);
+        let plumber = Plumber::new(
+            vm_pool,
+            builtins,
+            plumber_metrics,
+            key_manager,
+            config.log_failed_traces,
+            config.max_particle_ttl,
+        );
         let this = Self {
             inlet,
             plumber,
@@ -77,11 +88,16 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> AquamarineBackend<RT, F> {
         // check if there are new particles
         loop {
             match self.inlet.poll_next_unpin(cx) {
-                Poll::Ready(Some(Ingest { particle, function })) => {
+                Poll::Ready(Some(Ingest {
+                    particle,
+                    function,
+                    span,
+                })) => {
                     wake = true;
                     // set new particle to be executed
                     // every particle that comes from the connection pool first executed on the host peer id
-                    self.plumber.ingest(particle, function, self.host_peer_id);
+                    self.plumber
+                        .ingest(particle, function, self.host_peer_id, span);
                 }
                 Poll::Ready(Some(AddService {
                     service,
@@ -93,6 +109,11 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> AquamarineBackend<RT, F> {
                     self.plumber.remove_service(service)
                 }
 
+                Poll::Ready(Some(PoolStatus { outlet })) => {
+                    // receiver may have given up waiting, that's fine
+                    outlet.send(self.plumber.vm_pool_status()).ok();
+                }
+
                 Poll::Pending | Poll::Ready(None) => break,
             }
         }
@@ -144,9 +165,27 @@ impl AquamarineApi {
         self,
         particle: Particle,
         function: Option<ServiceFunction>,
+    ) -> impl Future<Output = Result<(), AquamarineApiError>> {
+        let span = particle.span();
+        self.execute_in_span(particle, function, span)
+    }
+
+    /// Send particle to the interpreters pool, its execution is recorded within `span`
+    pub fn execute_in_span(
+        self,
+        particle: Particle,
+        function: Option<ServiceFunction>,
+        span: Span,
     ) -> impl Future<Output = Result<(), AquamarineApiError>> {
         let particle_id = particle.id.clone();
-        self.send_command(Ingest { particle, function }, Some(particle_id))
+        self.send_command(
+            Ingest {
+                particle,
+                function,
+                span,
+            },
+            Some(particle_id),
+        )
     }
 
     pub fn add_service(
@@ -171,6 +210,19 @@ impl AquamarineApi {
         self.send_command(RemoveService { service }, None)
     }
 
+    /// Requests status of the AquaVM pool. Doesn't execute anything on the VMs
+    pub fn vm_pool_status(self) -> impl Future<Output = Result<VmPoolStatus, AquamarineApiError>> {
+        let (outlet, inlet) = oneshot::channel();
+        let sent = self.send_command(PoolStatus { outlet }, None);
+
+        async move {
+            sent.await?;
+            inlet
+                .await
+                .map_err(|_| AquamarineApiError::AquamarineDied { particle_id: None })
+        }
+    }
+
     fn send_command(
         self,
         command: Command,
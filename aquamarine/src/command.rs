@@ -14,14 +14,20 @@
  * limitations under the License.
  */
 
+use fluence_libp2p::types::OneshotOutlet;
 use particle_execution::ServiceFunction;
 use particle_protocol::Particle;
 use std::collections::HashMap;
+use tracing::Span;
+
+use crate::vm_pool::VmPoolStatus;
 
 pub enum Command {
     Ingest {
         particle: Particle,
         function: Option<ServiceFunction>,
+        /// Span of the particle, created once when the particle was ingested by the node
+        span: Span,
     },
     AddService {
         service: String,
@@ -31,4 +37,7 @@ pub enum Command {
     RemoveService {
         service: String,
     },
+    PoolStatus {
+        outlet: OneshotOutlet<VmPoolStatus>,
+    },
 }
@@ -26,6 +26,10 @@ pub struct VmPoolConfig {
     pub pool_size: usize,
     /// Timeout of a particle execution
     pub execution_timeout: Duration,
+    /// Log data trace and call results of particles whose interpretation failed
+    pub log_failed_traces: bool,
+    /// Particles are tracked no longer than that after their timestamp, regardless of their TTL
+    pub max_particle_ttl: Option<Duration>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +47,8 @@ pub struct VmConfig {
     pub particles_anomaly_dir: PathBuf,
     /// Maximum heap size in bytes available for the interpreter.
     pub max_heap_size: Option<u64>,
+    /// Don't remove particle vaults after particle expiration
+    pub keep_particle_vaults: bool,
 }
 
 impl VmPoolConfig {
@@ -50,6 +56,8 @@ impl VmPoolConfig {
         Self {
             pool_size,
             execution_timeout,
+            log_failed_traces: false,
+            max_particle_ttl: None,
         }
     }
 }
@@ -69,6 +77,7 @@ impl VmConfig {
             particles_anomaly_dir: config_utils::particles_anomaly_dir(&base_dir),
             air_interpreter,
             max_heap_size,
+            keep_particle_vaults: false,
         }
     }
 }
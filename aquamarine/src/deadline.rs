@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
 use particle_protocol::Particle;
 
 #[derive(Debug, Clone)]
@@ -32,6 +34,17 @@ impl Deadline {
         }
     }
 
+    /// Deadline of the particle, but no later than `max_ttl` after its timestamp.
+    /// Particle itself isn't changed, so its signature stays valid
+    pub fn capped(particle: &Particle, max_ttl: Option<Duration>) -> Self {
+        let mut deadline = Self::from(particle);
+        if let Some(max_ttl) = max_ttl {
+            let max_ttl = max_ttl.as_millis().try_into().unwrap_or(u32::MAX);
+            deadline.ttl = deadline.ttl.min(max_ttl);
+        }
+        deadline
+    }
+
     pub fn is_expired(&self, now_ms: u64) -> bool {
         self.timestamp
             .checked_add(self.ttl as u64)
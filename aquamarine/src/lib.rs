@@ -32,11 +32,13 @@ pub use avm_server::AVM;
 pub use avm_server::AVMConfig;
 
 pub use aqua_runtime::AquaRuntime;
+pub use command::Command;
 pub use config::{VmConfig, VmPoolConfig};
 pub use error::AquamarineApiError;
 pub use particle_data_store::{DataStoreError, ParticleDataStore};
 pub use particle_effects::{InterpretationStats, ParticleEffects, RoutingEffects};
 pub use plumber::Plumber;
+pub use vm_pool::VmPoolStatus;
 
 pub use crate::aquamarine::{AquamarineApi, AquamarineBackend};
 
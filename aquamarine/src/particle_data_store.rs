@@ -34,6 +34,8 @@ pub struct ParticleDataStore {
     pub particle_data_store: PathBuf,
     pub vault: ParticleVault,
     pub anomaly_data_store: PathBuf,
+    /// If true, particle vault is kept on cleanup
+    pub keep_vaults: bool,
 }
 
 impl ParticleDataStore {
@@ -46,6 +48,7 @@ impl ParticleDataStore {
             particle_data_store,
             vault: ParticleVault::new(vault_dir),
             anomaly_data_store,
+            keep_vaults: false,
         }
     }
 
@@ -99,10 +102,13 @@ impl DataStore for ParticleDataStore {
     }
 
     fn cleanup_data(&mut self, key: &str) -> Result<()> {
-        remove_file(&self.data_file(key)).map_err(CleanupData)?;
-        self.vault.cleanup(key)?;
+        let data = remove_file(&self.data_file(key)).map_err(CleanupData);
+        // vault is removed even if data file couldn't be
+        if !self.keep_vaults {
+            self.vault.cleanup(key)?;
+        }
 
-        Ok(())
+        data
     }
 
     fn detect_anomaly(&self, execution_time: Duration, memory_delta: usize) -> bool {
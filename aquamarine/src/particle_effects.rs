@@ -19,6 +19,7 @@ use particle_protocol::Particle;
 use std::time::Duration;
 
 use libp2p::PeerId;
+use tracing::Span;
 
 #[derive(Clone, Debug)]
 /// Effects produced by particle execution. Currently the only effect is that of sending particles.
@@ -47,6 +48,12 @@ pub struct InterpretationStats {
     pub interpretation_time: Duration,
     pub new_data_len: Option<usize>,
     pub success: bool,
+    /// Interpreter memory size after the interpretation, in bytes
+    pub memory_size: usize,
+    /// Number of call results passed to the interpreter
+    pub call_results: usize,
+    /// Whether the interpreter failed after reaching its max heap size
+    pub out_of_memory: bool,
 }
 
 /// Routing part of the [[ParticleEffects].
@@ -55,4 +62,6 @@ pub struct InterpretationStats {
 pub struct RoutingEffects {
     pub particle: Particle,
     pub next_peers: Vec<PeerId>,
+    /// Span of the particle, effects are executed within it
+    pub span: Span,
 }
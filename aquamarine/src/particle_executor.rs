@@ -34,7 +34,13 @@ pub(super) type Fut<RT> = BoxFuture<'static, FutResult<RT, ParticleEffects, Inte
 pub trait ParticleExecutor {
     type Future;
     type Particle;
-    fn execute(self, p: Self::Particle, waker: Waker, current_peer_id: PeerId) -> Self::Future;
+    fn execute(
+        self,
+        p: Self::Particle,
+        waker: Waker,
+        current_peer_id: PeerId,
+        log_failed_trace: bool,
+    ) -> Self::Future;
 }
 
 /// Result of a particle execution along a VM that has just executed the particle
@@ -51,7 +57,13 @@ impl<RT: AquaRuntime> ParticleExecutor for RT {
     type Future = Fut<Self>;
     type Particle = (Particle, CallResults);
 
-    fn execute(mut self, p: Self::Particle, waker: Waker, current_peer_id: PeerId) -> Self::Future {
+    fn execute(
+        mut self,
+        p: Self::Particle,
+        waker: Waker,
+        current_peer_id: PeerId,
+        log_failed_trace: bool,
+    ) -> Self::Future {
         task::spawn_blocking(move || {
             let now = Instant::now();
             let (p, calls) = p;
@@ -64,13 +76,22 @@ impl<RT: AquaRuntime> ParticleExecutor for RT {
                 timestamp: p.timestamp,
                 ttl: p.ttl
             };
+            // call results are consumed by the interpreter, keep them in case of an error
+            let failed_calls = log_failed_trace.then(|| calls.clone());
+            let call_results = calls.len();
             let result = self.call(p.script.clone(), p.data.clone(), particle, calls);
             let interpretation_time = now.elapsed();
             let new_data_len = result.as_ref().map(|e| e.data.len()).ok();
-            let stats = InterpretationStats { interpretation_time, new_data_len, success: result.is_ok() };
+            let success = result.is_ok();
+            let memory = self.memory_stats();
+            let out_of_memory = !success && memory.max_memory_size.map_or(false, |max| memory.memory_size >= max);
+            let stats = InterpretationStats { interpretation_time, new_data_len, success, memory_size: memory.memory_size, call_results, out_of_memory };
 
             if let Err(err) = &result {
-                log::warn!("Error executing particle {:#?}: {}", p, err)
+                log::warn!("Error executing particle {:#?}: {}", p, err);
+                if let Some(calls) = failed_calls {
+                    log::debug!(target: "execution", "Particle {} failed on trace: {}, call results: {:?}", p.id, String::from_utf8_lossy(&p.data), calls);
+                }
             } else {
                 let len = new_data_len.map(|l| l as i32).unwrap_or(-1);
                 log::trace!(target: "execution", "Particle {} interpreted in {} [{} bytes => {} bytes]", p.id, pretty(interpretation_time), p.data.len(), len);
@@ -88,3 +109,128 @@ impl<RT: AquaRuntime> ParticleExecutor for RT {
         .boxed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, Once};
+
+    use async_std::task;
+    use avm_server::{AVMMemoryStats, AVMOutcome, CallResults, ParticleParameters};
+    use fluence_libp2p::RandomPeerId;
+    use futures::future::BoxFuture;
+    use futures::task::noop_waker;
+    use futures::FutureExt;
+    use log::{Log, Metadata, Record};
+
+    use particle_protocol::Particle;
+
+    use crate::particle_executor::ParticleExecutor;
+    use crate::{AquaRuntime, ParticleEffects};
+
+    static LOGS: Mutex<Vec<String>> = Mutex::new(vec![]);
+    static INIT_LOGGER: Once = Once::new();
+
+    /// Collects messages logged to the `execution` target
+    struct ExecutionLogger;
+    impl Log for ExecutionLogger {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == "execution"
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            if self.enabled(record.metadata()) {
+                LOGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn execution_logs(particle_id: &str) -> Vec<String> {
+        let logs = LOGS.lock().unwrap();
+        logs.iter()
+            .filter(|l| l.contains(particle_id))
+            .cloned()
+            .collect()
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("interpreter failed")]
+    struct FailingVMError;
+
+    struct FailingVM;
+    impl AquaRuntime for FailingVM {
+        type Config = ();
+        type Error = FailingVMError;
+
+        fn create_runtime(
+            _config: Self::Config,
+            _waker: std::task::Waker,
+        ) -> BoxFuture<'static, Result<Self, Self::Error>> {
+            async { Ok(FailingVM) }.boxed()
+        }
+
+        fn into_effects(_outcome: Result<AVMOutcome, Self::Error>, p: Particle) -> ParticleEffects {
+            ParticleEffects::empty(p)
+        }
+
+        fn call(
+            &mut self,
+            _aqua: String,
+            _data: Vec<u8>,
+            _particle: ParticleParameters<'_>,
+            _call_results: CallResults,
+        ) -> Result<AVMOutcome, Self::Error> {
+            Err(FailingVMError)
+        }
+
+        fn cleanup(&mut self, _particle_id: &str) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn memory_stats(&self) -> AVMMemoryStats {
+            AVMMemoryStats {
+                memory_size: 0,
+                max_memory_size: None,
+            }
+        }
+    }
+
+    fn execute_failing(particle_id: &str, log_failed_trace: bool) -> Vec<String> {
+        INIT_LOGGER.call_once(|| {
+            log::set_logger(&ExecutionLogger).expect("set logger");
+            log::set_max_level(log::LevelFilter::Debug);
+        });
+
+        let particle = Particle {
+            id: particle_id.to_string(),
+            data: br#"{"trace":[]}"#.to_vec(),
+            ..<_>::default()
+        };
+        let fut = FailingVM.execute(
+            (particle, CallResults::default()),
+            noop_waker(),
+            RandomPeerId::random(),
+            log_failed_trace,
+        );
+        let result = task::block_on(fut);
+        assert!(!result.stats.success);
+
+        execution_logs(particle_id)
+    }
+
+    #[test]
+    fn failed_trace_is_logged() {
+        let logs = execute_failing("failed_trace_is_logged", true);
+
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains(r#"{"trace":[]}"#), "{}", logs[0]);
+    }
+
+    #[test]
+    fn failed_trace_is_not_logged_by_default() {
+        let logs = execute_failing("failed_trace_is_not_logged", false);
+
+        assert!(logs.is_empty(), "{:?}", logs);
+    }
+}
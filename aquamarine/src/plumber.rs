@@ -17,9 +17,11 @@
 use std::{
     collections::{HashMap, VecDeque},
     task::{Context, Poll},
+    time::Duration,
 };
 
 use futures::task::Waker;
+use tracing::Span;
 
 use fluence_libp2p::PeerId;
 use key_manager::KeyManager;
@@ -39,7 +41,7 @@ use crate::deadline::Deadline;
 use crate::error::AquamarineApiError;
 use crate::particle_effects::RoutingEffects;
 use crate::particle_functions::Functions;
-use crate::vm_pool::VmPool;
+use crate::vm_pool::{VmPool, VmPoolStatus};
 
 type ParticleId = String;
 pub struct Plumber<RT: AquaRuntime, F> {
@@ -50,6 +52,10 @@ pub struct Plumber<RT: AquaRuntime, F> {
     waker: Option<Waker>,
     metrics: Option<ParticleExecutorMetrics>,
     key_manager: KeyManager,
+    /// Log data trace and call results of particles whose interpretation failed
+    log_failed_traces: bool,
+    /// Actors don't live longer than that after particle's timestamp
+    max_particle_ttl: Option<Duration>,
 }
 
 impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
@@ -58,6 +64,8 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
         builtins: F,
         metrics: Option<ParticleExecutorMetrics>,
         key_manager: KeyManager,
+        log_failed_traces: bool,
+        max_particle_ttl: Option<Duration>,
     ) -> Self {
         Self {
             vm_pool,
@@ -67,6 +75,8 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
             waker: <_>::default(),
             metrics,
             key_manager,
+            log_failed_traces,
+            max_particle_ttl,
         }
     }
 
@@ -76,10 +86,13 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
         particle: Particle,
         function: Option<ServiceFunction>,
         scope_peer_id: PeerId,
+        span: Span,
     ) {
         self.wake();
 
-        let deadline = Deadline::from(&particle);
+        let _guard = span.enter();
+
+        let deadline = Deadline::capped(&particle, self.max_particle_ttl);
         if deadline.is_expired(now_ms()) {
             log::info!("Particle {} is expired, ignoring", particle.id);
             self.events
@@ -90,13 +103,21 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
         }
 
         let builtins = &self.builtins;
+        let log_failed_traces = self.log_failed_traces;
         let actor = self
             .actors
             .entry((particle.id.clone(), scope_peer_id))
             .or_insert_with(|| {
                 let params = ParticleParams::clone_from(&particle);
                 let functions = Functions::new(params, builtins.clone());
-                Actor::new(&particle, functions, scope_peer_id)
+                Actor::new(
+                    &particle,
+                    functions,
+                    scope_peer_id,
+                    log_failed_traces,
+                    deadline,
+                    span.clone(),
+                )
             });
 
         actor.ingest(particle);
@@ -118,6 +139,10 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
         self.builtins.remove(&service);
     }
 
+    pub fn vm_pool_status(&self) -> VmPoolStatus {
+        self.vm_pool.status()
+    }
+
     pub fn poll(
         &mut self,
         cx: &mut Context<'_>,
@@ -149,6 +174,7 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
                     remote_effects.push(RoutingEffects {
                         particle: result.effects.particle.clone(),
                         next_peers: remote_peers,
+                        span: result.effects.span.clone(),
                     })
                 }
 
@@ -156,6 +182,7 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
                     local_effects.push(RoutingEffects {
                         particle: result.effects.particle,
                         next_peers: local_peers,
+                        span: result.effects.span,
                     });
                 }
                 let (vm_id, vm) = result.vm;
@@ -222,6 +249,13 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
 
                 let time = stat.interpretation_time.as_secs_f64();
                 m.interpretation_time_sec.observe(time);
+                m.interpretation_memory_size
+                    .observe(stat.memory_size as f64);
+                m.interpretation_call_results
+                    .observe(stat.call_results as f64);
+                if stat.out_of_memory {
+                    m.interpretation_out_of_memory.inc();
+                }
 
                 m.total_actors_mailbox.set(mailbox_size as u64);
                 m.alive_actors.set(self.actors.len() as u64);
@@ -234,7 +268,12 @@ impl<RT: AquaRuntime, F: ParticleFunctionStatic> Plumber<RT, F> {
 
         for effect in local_effects {
             for local_peer in effect.next_peers {
-                self.ingest(effect.particle.clone(), None, local_peer);
+                self.ingest(
+                    effect.particle.clone(),
+                    None,
+                    local_peer,
+                    effect.span.clone(),
+                );
             }
         }
 
@@ -273,6 +312,7 @@ mod tests {
     use std::collections::HashMap;
     use std::convert::Infallible;
     use std::task::Waker;
+    use std::time::Duration;
     use std::{sync::Arc, task::Context};
 
     use avm_server::{AVMMemoryStats, AVMOutcome, CallResults, ParticleParameters};
@@ -281,6 +321,7 @@ mod tests {
     use futures::task::noop_waker_ref;
     use futures::FutureExt;
     use key_manager::KeyManager;
+    use tracing::Span;
 
     use particle_args::Args;
     use particle_execution::{ParticleFunction, ParticleParams, ServiceFunction};
@@ -372,11 +413,22 @@ mod tests {
     }
 
     fn plumber() -> Plumber<VMMock, Arc<MockF>> {
+        plumber_with_max_ttl(None)
+    }
+
+    fn plumber_with_max_ttl(max_particle_ttl: Option<Duration>) -> Plumber<VMMock, Arc<MockF>> {
         // Pool is of size 1 so it's easier to control tests
         let vm_pool = VmPool::new(1, (), None);
         let builtin_mock = Arc::new(MockF);
         let key_manager = KeyManager::new("keypair".into(), RandomPeerId::random());
-        Plumber::new(vm_pool, builtin_mock, None, key_manager)
+        Plumber::new(
+            vm_pool,
+            builtin_mock,
+            None,
+            key_manager,
+            false,
+            max_particle_ttl,
+        )
     }
 
     fn particle(ts: u64, ttl: u32) -> Particle {
@@ -403,7 +455,7 @@ mod tests {
         let deadline = Deadline::from(&particle);
         assert!(!deadline.is_expired(now_ms()));
 
-        plumber.ingest(particle, None, RandomPeerId::random());
+        plumber.ingest(particle, None, RandomPeerId::random(), Span::none());
 
         assert_eq!(plumber.actors.len(), 1);
         let mut cx = context();
@@ -436,7 +488,7 @@ mod tests {
         let deadline = Deadline::from(&particle);
         assert!(deadline.is_expired(now_ms()));
 
-        plumber.ingest(particle.clone(), None, RandomPeerId::random());
+        plumber.ingest(particle.clone(), None, RandomPeerId::random(), Span::none());
 
         assert_eq!(plumber.actors.len(), 0);
 
@@ -455,6 +507,33 @@ mod tests {
         }
         assert_eq!(plumber.actors.len(), 0);
     }
+
+    /// Checks that particle is tracked no longer than `max_particle_ttl`, while its TTL is kept
+    #[test]
+    fn max_particle_ttl() {
+        set_mock_time(real_time::now_ms());
+
+        let mut plumber = plumber_with_max_ttl(Some(Duration::from_secs(1)));
+        let old = particle(now_ms() - 2000, 60_000);
+        assert!(!Deadline::from(&old).is_expired(now_ms()));
+
+        plumber.ingest(old.clone(), None, RandomPeerId::random(), Span::none());
+        assert_eq!(plumber.actors.len(), 0);
+        match plumber.poll(&mut context()) {
+            std::task::Poll::Ready(Err(ParticleExpired { particle_id })) => {
+                assert_eq!(particle_id, old.id)
+            }
+            unexpected => panic!(
+                "Expected Poll::Ready(Err(AquamarineApiError::ParticleExpired)), got {:?}",
+                unexpected
+            ),
+        }
+
+        // particles within the limit are executed as usual
+        let fresh = particle(now_ms(), 60_000);
+        plumber.ingest(fresh, None, RandomPeerId::random(), Span::none());
+        assert_eq!(plumber.actors.len(), 1);
+    }
 }
 
 /// Code taken from https://blog.iany.me/2019/03/how-to-mock-time-in-rust-tests-and-cargo-gotchas-we-met/
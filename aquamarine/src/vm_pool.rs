@@ -22,6 +22,16 @@ use peer_metrics::VmPoolMetrics;
 
 use crate::aqua_runtime::AquaRuntime;
 
+/// Snapshot of the pool state, used by health checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VmPoolStatus {
+    pub pool_size: usize,
+    /// Number of successfully created VMs, either free or busy executing particles
+    pub created: usize,
+    /// Number of VMs available for execution right now
+    pub free: usize,
+}
+
 type RuntimeF<RT> = BoxFuture<'static, Result<RT, <RT as AquaRuntime>::Error>>;
 
 /// Pool that owns and manages aquamarine stepper VMs
@@ -38,6 +48,7 @@ pub struct VmPool<RT: AquaRuntime> {
     creating_runtimes: Option<Vec<RuntimeF<RT>>>,
     runtime_config: RT::Config,
     pool_size: usize,
+    created: usize,
     metrics: Option<VmPoolMetrics>,
 }
 
@@ -53,6 +64,7 @@ impl<RT: AquaRuntime> VmPool<RT> {
             creating_runtimes: None,
             runtime_config,
             pool_size,
+            created: 0,
             metrics,
         };
 
@@ -71,6 +83,14 @@ impl<RT: AquaRuntime> VmPool<RT> {
         self.runtimes.len()
     }
 
+    pub fn status(&self) -> VmPoolStatus {
+        VmPoolStatus {
+            pool_size: self.pool_size,
+            created: self.created,
+            free: self.runtimes.iter().filter(|vm| vm.is_some()).count(),
+        }
+    }
+
     /// Takes VM from pool
     pub fn get_vm(&mut self) -> Option<(usize, RT)> {
         let runtimes = self.runtimes.iter_mut();
@@ -144,7 +164,10 @@ impl<RT: AquaRuntime> VmPool<RT> {
 
                 // Put created vm to self.vms
                 match vm {
-                    Ok(vm) => vms[i] = Some(vm),
+                    Ok(vm) => {
+                        vms[i] = Some(vm);
+                        self.created += 1;
+                    }
                     Err(err) => log::error!("Failed to create vm: {:?}", err), // TODO: don't panic
                 }
 
@@ -59,6 +59,10 @@ pub enum Command {
         peer_id: PeerId,
         out: OneshotOutlet<Option<Contact>>,
     },
+    Ping {
+        peer_id: PeerId,
+        out: OneshotOutlet<Option<Duration>>,
+    },
 
     CountConnections {
         out: OneshotOutlet<usize>,
@@ -131,6 +135,15 @@ impl ConnectionPoolT for ConnectionPoolApi {
             .boxed()
     }
 
+    fn ping(&self, peer_id: PeerId, timeout: Duration) -> BoxFuture<'static, Option<Duration>> {
+        let fut = self.execute(|out| Command::Ping { peer_id, out });
+        // timeout is required because RTT may not be measured yet, and ping result could take a while
+        async_std::future::timeout(timeout, fut)
+            // convert timeout to None
+            .map(|r| r.ok().flatten())
+            .boxed()
+    }
+
     fn count_connections(&self) -> BoxFuture<'static, usize> {
         // timeout isn't needed because result is returned immediately
         self.execute(|out| Command::CountConnections { out })
@@ -17,12 +17,14 @@
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     task::{Context, Poll, Waker},
+    time::{Duration, Instant},
 };
 
+use async_std::stream::{interval, Interval};
 use futures::channel::mpsc;
 use futures::StreamExt;
 use libp2p::swarm::dial_opts::DialOpts;
-use libp2p::swarm::{dial_opts, DialError, IntoConnectionHandler};
+use libp2p::swarm::{dial_opts, CloseConnection, DialError, IntoConnectionHandler};
 use libp2p::{
     core::{connection::ConnectionId, ConnectedPoint, Multiaddr},
     swarm::{
@@ -63,6 +65,12 @@ struct Peer {
     dialing: HashSet<Multiaddr>,
     /// Channels to notify when any dial succeeds or peer is already connected
     dial_promises: Vec<OneshotOutlet<bool>>,
+    /// Round-trip time measured by the latest successful ping
+    ping_rtt: Option<Duration>,
+    /// Channels to notify when next ping result is available
+    ping_promises: Vec<OneshotOutlet<Option<Duration>>>,
+    /// Last time a connection was established or a particle was sent to or received from the peer
+    last_activity: Option<Instant>,
     // TODO: this layout of `dialing` and `dial_promises` doesn't allow to check specific addresses for reachability
     //       if check reachability for specific maddrs is ever required, one would need to maintain the following info:
     //       reachability_promises: HashMap<Multiaddr, Vec<OneshotOutlet<bool>>
@@ -84,6 +92,9 @@ impl Peer {
             discovered: Default::default(),
             dialing: Default::default(),
             dial_promises: vec![],
+            ping_rtt: None,
+            ping_promises: vec![],
+            last_activity: Some(Instant::now()),
         }
     }
 
@@ -96,6 +107,9 @@ impl Peer {
             discovered: Default::default(),
             dialing: addresses.into_iter().collect(),
             dial_promises: vec![outlet],
+            ping_rtt: None,
+            ping_promises: vec![],
+            last_activity: None,
         }
     }
 }
@@ -116,6 +130,17 @@ pub struct ConnectionPoolBehaviour {
     waker: Option<Waker>,
     pub(super) protocol_config: ProtocolConfig,
 
+    /// Connections without particles for that long are closed
+    idle_timeout: Option<Duration>,
+    /// Fires periodically to check for idle connections
+    idle_check: Option<Interval>,
+    /// Peers that are never closed for inactivity, e.g. Kademlia peers
+    protected_peers: HashSet<PeerId>,
+    /// Addresses that are never closed for inactivity, e.g. bootstrap nodes
+    protected_addresses: HashSet<Multiaddr>,
+    /// Whether the particle stream was full on the last attempt to send to it
+    stalled: bool,
+
     metrics: Option<ConnectionPoolMetrics>,
 }
 
@@ -128,6 +153,7 @@ impl ConnectionPoolBehaviour {
             Command::IsConnected { peer_id, out } => self.is_connected(peer_id, out),
             Command::GetContact { peer_id, out } => self.get_contact(peer_id, out),
             Command::Send { to, particle, out } => self.send(to, particle, out),
+            Command::Ping { peer_id, out } => self.ping(peer_id, out),
             Command::CountConnections { out } => self.count_connections(out),
             Command::LifecycleEvents { out } => self.add_subscriber(out),
         }
@@ -222,7 +248,8 @@ impl ConnectionPoolBehaviour {
             self.queue.push_back(particle);
             outlet.send(SendStatus::Ok).ok();
             self.wake();
-        } else if self.contacts.contains_key(&to.peer_id) {
+        } else if let Some(peer) = self.contacts.get_mut(&to.peer_id) {
+            peer.last_activity = Some(Instant::now());
             log::debug!(target: "network", "{}: Sending particle {} to {}", self.peer_id, particle.id, to.peer_id);
             // Send particle to remote peer
             self.push_event(NetworkBehaviourAction::NotifyHandler {
@@ -240,6 +267,34 @@ impl ConnectionPoolBehaviour {
         }
     }
 
+    /// Returns round-trip time to a connected peer
+    /// If RTT wasn't measured yet, waits for the next ping result
+    /// `None` means peer isn't connected or ping has failed
+    pub fn ping(&mut self, peer_id: PeerId, outlet: OneshotOutlet<Option<Duration>>) {
+        match self.contacts.get_mut(&peer_id) {
+            Some(peer) if !peer.connected.is_empty() => match peer.ping_rtt {
+                Some(rtt) => {
+                    outlet.send(Some(rtt)).ok();
+                }
+                None => peer.ping_promises.push(outlet),
+            },
+            _ => {
+                outlet.send(None).ok();
+            }
+        }
+    }
+
+    /// Remembers result of the ping protocol and notifies these waiting for it
+    /// `None` means ping has failed
+    pub fn add_ping_result(&mut self, peer_id: PeerId, rtt: Option<Duration>) {
+        if let Some(peer) = self.contacts.get_mut(&peer_id) {
+            peer.ping_rtt = rtt;
+            for out in std::mem::take(&mut peer.ping_promises) {
+                out.send(rtt).ok();
+            }
+        }
+    }
+
     /// Returns number of connected contacts
     pub fn count_connections(&mut self, outlet: OneshotOutlet<usize>) {
         outlet.send(self.contacts.len()).ok();
@@ -258,6 +313,97 @@ impl ConnectionPoolBehaviour {
             .extend(addresses);
     }
 
+    /// Connections to the peer won't be closed for inactivity.
+    /// Kademlia traffic isn't visible to the pool, so routing table peers must be protected
+    pub fn protect_peer(&mut self, peer_id: PeerId) {
+        self.protected_peers.insert(peer_id);
+    }
+
+    /// Connections to the address won't be closed for inactivity, e.g. to keep bootstrap nodes
+    pub fn protect_address(&mut self, addr: Multiaddr) {
+        self.protected_addresses.insert(addr);
+    }
+
+    /// Closes connections that had no particles for longer than `idle_timeout`
+    fn evict_idle(&mut self, now: Instant) {
+        let timeout = match self.idle_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+
+        let protected_peers = &self.protected_peers;
+        let protected_addresses = &self.protected_addresses;
+        let idle: Vec<_> = self
+            .contacts
+            .iter()
+            .filter(|(peer_id, peer)| {
+                !peer.connected.is_empty()
+                    && peer
+                        .last_activity
+                        .map_or(false, |t| now.saturating_duration_since(t) >= timeout)
+                    && !protected_peers.contains(peer_id)
+                    && peer.connected.is_disjoint(protected_addresses)
+            })
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+
+        for peer_id in idle {
+            self.push_event(NetworkBehaviourAction::CloseConnection {
+                peer_id,
+                connection: CloseConnection::All,
+            });
+            // emits LifecycleEvent::Disconnected
+            self.remove_contact(&peer_id, "idle timeout");
+        }
+    }
+
+    /// Moves particles from the queue to the particle stream while it has capacity
+    fn send_to_stream(&mut self, cx: &mut Context<'_>) {
+        loop {
+            // Check backpressure on the outlet
+            match self.outlet.poll_ready(cx) {
+                Poll::Ready(Ok(_)) => {
+                    // channel is ready to consume more particles, so send them
+                    self.stalled = false;
+                    if let Some(particle) = self.queue.pop_front() {
+                        let particle_id = particle.id.clone();
+                        // increment before sending, so the dispatcher never sees depth below zero
+                        self.meter(|m| m.particle_stream.depth.inc());
+                        if let Err(err) = self.outlet.start_send(particle) {
+                            self.meter(|m| m.particle_stream.depth.dec());
+                            log::error!("Failed to send particle to outlet: {}", err)
+                        } else {
+                            log::trace!(target: "execution", "Sent particle {} to execution", particle_id);
+                        }
+                    } else {
+                        break;
+                    }
+                }
+                Poll::Pending => {
+                    // if channel is full, then keep particles in the queue
+                    let len = self.queue.len();
+                    if len > 0 && !self.stalled {
+                        self.stalled = true;
+                        self.meter(|m| m.particle_stream.stalls.inc());
+                    }
+                    if len > 30 {
+                        log::warn!("Particle queue seems to have stalled; queue {}", len);
+                    } else {
+                        log::trace!(target: "network", "Connection pool outlet is pending; queue {}", len);
+                    }
+                    if self.outlet.is_closed() {
+                        log::error!("Particle outlet closed");
+                    }
+                    break;
+                }
+                Poll::Ready(Err(err)) => {
+                    log::warn!("ConnectionPool particle inlet has been dropped: {}", err);
+                    break;
+                }
+            }
+        }
+    }
+
     fn meter<U, F: Fn(&ConnectionPoolMetrics) -> U>(&self, f: F) {
         self.metrics.as_ref().map(f);
     }
@@ -268,10 +414,13 @@ impl ConnectionPoolBehaviour {
         buffer: usize,
         protocol_config: ProtocolConfig,
         peer_id: PeerId,
+        idle_timeout: Option<Duration>,
         metrics: Option<ConnectionPoolMetrics>,
     ) -> (Self, BackPressuredInlet<Particle>, ConnectionPoolApi) {
         let (outlet, inlet) = mpsc::channel(buffer);
         let (command_outlet, command_inlet) = mpsc::unbounded();
+        // zero timeout would close connections right away, treat it as disabled
+        let idle_timeout = idle_timeout.filter(|timeout| !timeout.is_zero());
         let api = ConnectionPoolApi {
             outlet: command_outlet,
             send_timeout: protocol_config.upgrade_timeout * 2,
@@ -288,6 +437,12 @@ impl ConnectionPoolBehaviour {
             events: <_>::default(),
             waker: None,
             protocol_config,
+            idle_timeout,
+            // check twice per timeout, so connections are closed within 1.5 * idle_timeout
+            idle_check: idle_timeout.map(|timeout| interval(timeout / 2)),
+            protected_peers: <_>::default(),
+            protected_addresses: <_>::default(),
+            stalled: false,
             metrics,
         };
 
@@ -308,6 +463,7 @@ impl ConnectionPoolBehaviour {
                 peer.dialing.remove(&maddr);
                 peer.discovered.remove(&maddr);
                 peer.connected.insert(maddr.clone());
+                peer.last_activity = Some(Instant::now());
 
                 let dial_promises = std::mem::take(&mut peer.dial_promises);
 
@@ -357,6 +513,11 @@ impl ConnectionPoolBehaviour {
                 out.send(false).ok();
             }
 
+            for out in contact.ping_promises {
+                // peer is gone, so there will be no ping results
+                out.send(None).ok();
+            }
+
             self.meter(|m| m.connected_peers.set(self.contacts.len() as u64));
         }
     }
@@ -565,6 +726,9 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
                     m.received_particles.inc();
                     m.particle_sizes.observe(particle.data.len() as f64);
                 });
+                if let Some(peer) = self.contacts.get_mut(&from) {
+                    peer.last_activity = Some(Instant::now());
+                }
                 self.queue.push_back(particle);
                 self.wake();
             }
@@ -577,48 +741,23 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
     fn poll(&mut self, cx: &mut Context<'_>, _: &mut impl PollParameters) -> Poll<SwarmEventType> {
         self.waker = Some(cx.waker().clone());
 
-        loop {
-            // Check backpressure on the outlet
-            match self.outlet.poll_ready(cx) {
-                Poll::Ready(Ok(_)) => {
-                    // channel is ready to consume more particles, so send them
-                    if let Some(particle) = self.queue.pop_front() {
-                        let particle_id = particle.id.clone();
-                        if let Err(err) = self.outlet.start_send(particle) {
-                            log::error!("Failed to send particle to outlet: {}", err)
-                        } else {
-                            log::trace!(target: "execution", "Sent particle {} to execution", particle_id);
-                        }
-                    } else {
-                        break;
-                    }
-                }
-                Poll::Pending => {
-                    // if channel is full, then keep particles in the queue
-                    let len = self.queue.len();
-                    if len > 30 {
-                        log::warn!("Particle queue seems to have stalled; queue {}", len);
-                    } else {
-                        log::trace!(target: "network", "Connection pool outlet is pending; queue {}", len);
-                    }
-                    if self.outlet.is_closed() {
-                        log::error!("Particle outlet closed");
-                    }
-                    break;
-                }
-                Poll::Ready(Err(err)) => {
-                    log::warn!("ConnectionPool particle inlet has been dropped: {}", err);
-                    break;
-                }
-            }
-        }
-
+        self.send_to_stream(cx);
         self.meter(|m| m.particle_queue_size.set(self.queue.len() as u64));
 
         while let Poll::Ready(Some(cmd)) = self.commands.poll_next_unpin(cx) {
             self.execute(cmd)
         }
 
+        let mut idle_check_fired = false;
+        if let Some(idle_check) = self.idle_check.as_mut() {
+            while let Poll::Ready(Some(_)) = idle_check.poll_next_unpin(cx) {
+                idle_check_fired = true;
+            }
+        }
+        if idle_check_fired {
+            self.evict_idle(Instant::now());
+        }
+
         if let Some(event) = self.events.pop_front() {
             return Poll::Ready(event);
         }
@@ -626,3 +765,82 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::task::Context;
+    use std::time::{Duration, Instant};
+
+    use futures::task::noop_waker_ref;
+    use libp2p::Multiaddr;
+    use prometheus_client::registry::Registry;
+
+    use fluence_libp2p::RandomPeerId;
+    use particle_protocol::{Particle, ProtocolConfig};
+    use peer_metrics::ConnectionPoolMetrics;
+
+    use crate::behaviour::{ConnectionPoolBehaviour, Peer};
+
+    #[test]
+    fn particle_stream_depth() {
+        let metrics = ConnectionPoolMetrics::new(&mut Registry::default());
+        let (mut pool, mut particle_stream, _) = ConnectionPoolBehaviour::new(
+            2,
+            ProtocolConfig::default(),
+            RandomPeerId::random(),
+            None,
+            Some(metrics.clone()),
+        );
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        for _ in 0..10 {
+            pool.queue.push_back(Particle::default());
+        }
+        pool.send_to_stream(&mut cx);
+
+        // channel holds `buffer` particles plus one per sender
+        assert_eq!(metrics.particle_stream.depth.get(), 3);
+        assert_eq!(metrics.particle_stream.stalls.get(), 1);
+        assert_eq!(pool.queue.len(), 7);
+
+        // stall is counted once, not on every poll
+        pool.send_to_stream(&mut cx);
+        assert_eq!(metrics.particle_stream.stalls.get(), 1);
+
+        // consumer frees a slot, so the pool sends one more particle and stalls again
+        particle_stream.try_next().unwrap();
+        pool.send_to_stream(&mut cx);
+        assert_eq!(metrics.particle_stream.depth.get(), 4);
+        assert_eq!(metrics.particle_stream.stalls.get(), 2);
+        assert_eq!(pool.queue.len(), 6);
+    }
+
+    #[test]
+    fn protected_connections_are_not_evicted() {
+        let (mut pool, _, _) = ConnectionPoolBehaviour::new(
+            2,
+            ProtocolConfig::default(),
+            RandomPeerId::random(),
+            Some(Duration::from_secs(1)),
+            None,
+        );
+
+        let addr = |port: u64| -> Multiaddr { format!("/memory/{port}").parse().unwrap() };
+        let (idle, kademlia, bootstrap) = (
+            RandomPeerId::random(),
+            RandomPeerId::random(),
+            RandomPeerId::random(),
+        );
+        pool.contacts.insert(idle, Peer::connected([addr(1)]));
+        pool.contacts.insert(kademlia, Peer::connected([addr(2)]));
+        pool.contacts.insert(bootstrap, Peer::connected([addr(3)]));
+        pool.protect_peer(kademlia);
+        pool.protect_address(addr(3));
+
+        pool.evict_idle(Instant::now() + Duration::from_secs(2));
+
+        assert!(!pool.contacts.contains_key(&idle));
+        assert!(pool.contacts.contains_key(&kademlia));
+        assert!(pool.contacts.contains_key(&bootstrap));
+    }
+}
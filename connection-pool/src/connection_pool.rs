@@ -15,6 +15,7 @@
  */
 
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
 
 use futures::{future::BoxFuture, stream::BoxStream};
 use libp2p::{core::Multiaddr, PeerId};
@@ -43,6 +44,9 @@ pub trait ConnectionPoolT {
     fn is_connected(&self, peer_id: PeerId) -> BoxFuture<'static, bool>;
     fn get_contact(&self, peer_id: PeerId) -> BoxFuture<'static, Option<Contact>>;
     fn send(&self, to: Contact, particle: Particle) -> BoxFuture<'static, SendStatus>;
+    /// Returns round-trip time to a connected peer, as measured by the ping protocol
+    /// `None` means peer isn't connected, ping failed or didn't complete within `timeout`
+    fn ping(&self, peer_id: PeerId, timeout: Duration) -> BoxFuture<'static, Option<Duration>>;
     fn count_connections(&self) -> BoxFuture<'static, usize>;
     fn lifecycle_events(&self) -> BoxStream<'static, LifecycleEvent>;
 }
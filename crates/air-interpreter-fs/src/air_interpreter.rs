@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use eyre::{Result, WrapErr};
+use eyre::{eyre, Result, WrapErr};
 use std::path::{Path, PathBuf};
 
 pub fn air_interpreter_path(base_dir: &Path) -> PathBuf {
@@ -31,3 +31,53 @@ pub fn write_default_air_interpreter(destination: &Path) -> Result<()> {
         "failed writing default INTERPRETER_WASM to {destination:?}"
     ))
 }
+
+/// Check that SHA256 of the interpreter at `path` equals hex-encoded `expected_sha256`
+pub fn check_air_interpreter_sha256(path: &Path, expected_sha256: &str) -> Result<()> {
+    use sha2::{Digest, Sha256};
+
+    let interpreter =
+        std::fs::read(path).wrap_err(format!("failed reading AIR interpreter from {path:?}"))?;
+    let actual = faster_hex::hex_string(&Sha256::digest(interpreter));
+    if !actual.eq_ignore_ascii_case(expected_sha256.trim()) {
+        return Err(eyre!(
+            "AIR interpreter {:?} has sha256 {}, but {} is expected by air_interpreter_sha256",
+            path,
+            actual,
+            expected_sha256
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha256 of b"interpreter"
+    const SHA256: &str = "9666d9e8899447735cb9897b77dbb121754fd4d503609758755bd3fdae3a4b22";
+
+    fn write_interpreter(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, b"interpreter").unwrap();
+        path
+    }
+
+    #[test]
+    fn matching_sha256() {
+        let path = write_interpreter("matching_sha256_aquamarine.wasm");
+
+        check_air_interpreter_sha256(&path, SHA256).expect("hash must match");
+        check_air_interpreter_sha256(&path, &SHA256.to_uppercase()).expect("case is ignored");
+    }
+
+    #[test]
+    fn mismatching_sha256() {
+        let path = write_interpreter("mismatching_sha256_aquamarine.wasm");
+        let wrong = SHA256.replace('9', "0");
+
+        let err = check_air_interpreter_sha256(&path, &wrong).expect_err("hash must not match");
+        assert!(err.to_string().contains(SHA256));
+    }
+}
@@ -28,4 +28,6 @@
 
 mod air_interpreter;
 
-pub use air_interpreter::{air_interpreter_path, write_default_air_interpreter};
+pub use air_interpreter::{
+    air_interpreter_path, check_air_interpreter_sha256, write_default_air_interpreter,
+};
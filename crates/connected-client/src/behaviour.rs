@@ -141,6 +141,17 @@ impl NetworkBehaviour for ClientBehaviour {
             return;
         }
 
+        let multiaddr = match cp {
+            ConnectedPoint::Dialer { address, .. } => address,
+            ConnectedPoint::Listener { send_back_addr, .. } => send_back_addr,
+        };
+        self.events.push_back(NetworkBehaviourAction::GenerateEvent(
+            ClientEvent::ConnectionClosed {
+                peer_id: *peer_id,
+                multiaddr: multiaddr.clone(),
+            },
+        ));
+
         match cp {
             ConnectedPoint::Dialer { address, .. } => {
                 log::warn!(
@@ -15,7 +15,12 @@
  */
 
 use core::ops::Deref;
-use std::{cell::LazyCell, collections::HashMap, ops::DerefMut, time::Duration};
+use std::{
+    cell::LazyCell,
+    collections::HashMap,
+    ops::DerefMut,
+    time::{Duration, Instant},
+};
 
 use async_std::task;
 use eyre::Result;
@@ -23,7 +28,7 @@ use eyre::{bail, eyre, WrapErr};
 use fluence_keypair::KeyPair;
 use libp2p::{core::Multiaddr, PeerId};
 use parking_lot::Mutex;
-use serde_json::Value as JValue;
+use serde_json::{json, Value as JValue};
 
 use fluence_libp2p::Transport;
 use local_vm::{make_particle, make_vm, read_args, DataStoreError};
@@ -31,6 +36,7 @@ use particle_protocol::Particle;
 use test_constants::{KAD_TIMEOUT, PARTICLE_TTL, SHORT_TIMEOUT, TIMEOUT, TRANSPORT_TIMEOUT};
 
 use crate::client::Client;
+use crate::error::RelayDisconnected;
 use crate::event::ClientEvent;
 
 #[allow(clippy::upper_case_acronyms)]
@@ -180,6 +186,27 @@ impl ConnectedClient {
         script: impl Into<String>,
         data: HashMap<&str, JValue>,
         generated: bool,
+    ) -> String {
+        let ttl = self.particle_ttl();
+        self.send_particle_inner(script, data, generated, ttl)
+    }
+
+    /// Send a particle with the given `ttl` instead of `self.particle_ttl`
+    pub fn send_particle_with_ttl(
+        &mut self,
+        script: impl Into<String>,
+        data: HashMap<&str, JValue>,
+        ttl: Duration,
+    ) -> String {
+        self.send_particle_inner(script, data, false, ttl)
+    }
+
+    fn send_particle_inner(
+        &mut self,
+        script: impl Into<String>,
+        data: HashMap<&str, JValue>,
+        generated: bool,
+        ttl: Duration,
     ) -> String {
         let data = data
             .into_iter()
@@ -192,7 +219,7 @@ impl ConnectedClient {
             self.node,
             &mut self.local_vm.lock(),
             generated,
-            self.particle_ttl(),
+            ttl,
         );
         let id = particle.id.clone();
         self.send(particle);
@@ -211,22 +238,36 @@ impl ConnectedClient {
     }
 
     pub fn receive(&mut self) -> Result<Particle> {
+        task::block_on(self.receive_async())
+    }
+
+    pub async fn receive_async(&mut self) -> Result<Particle> {
         let tout = self.timeout();
-        let result = task::block_on(timeout(tout, async {
+        let relay = self.node;
+        let result = timeout(tout, async {
             loop {
                 let result = self.client.receive_one().await;
-                if let Some(ClientEvent::Particle { particle, .. }) = result {
-                    break particle;
+                match result {
+                    Some(ClientEvent::Particle { particle, .. }) => break Ok(particle),
+                    Some(ClientEvent::ConnectionClosed { peer_id, .. }) if peer_id == relay => {
+                        break Err(RelayDisconnected { relay });
+                    }
+                    _ => {}
                 }
             }
-        }))
-        .wrap_err("receive particle")?;
+        })
+        .await
+        .wrap_err("receive particle")??;
 
         Ok(result)
     }
 
     pub fn receive_args(&mut self) -> Result<Vec<JValue>> {
-        let particle = self.receive().wrap_err("receive_args")?;
+        task::block_on(self.receive_args_async())
+    }
+
+    pub async fn receive_args_async(&mut self) -> Result<Vec<JValue>> {
+        let particle = self.receive_async().await.wrap_err("receive_args")?;
         let result = read_args(particle, self.peer_id, &mut self.local_vm.lock());
         match result {
             Some(result) => result.map_err(|args| eyre!("AIR caught an error: {:?}", args)),
@@ -236,22 +277,89 @@ impl ConnectedClient {
 
     /// Wait for a particle with specified `particle_id`, and read "op" "return" result from it
     pub fn wait_particle_args(&mut self, particle_id: impl AsRef<str>) -> Result<Vec<JValue>> {
-        let mut max = 100;
+        let timeout = self.timeout();
+        self.wait_particle_args_with_timeout(particle_id, timeout)
+    }
+
+    /// Same as `wait_particle_args`, but gives up once `timeout` has elapsed
+    pub fn wait_particle_args_with_timeout(
+        &mut self,
+        particle_id: impl AsRef<str>,
+        timeout: Duration,
+    ) -> Result<Vec<JValue>> {
+        let particle_id = particle_id.as_ref();
+        let deadline = Instant::now() + timeout;
         loop {
-            max -= 1;
-            if max <= 0 {
-                bail!("timed out waiting for particle {}", particle_id.as_ref());
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                bail!("timed out waiting for particle {}", particle_id);
             }
-            let particle = self.receive().ok();
-            if let Some(particle) = particle {
-                if particle.id == particle_id.as_ref() {
+            let event = task::block_on(self::timeout(remaining, self.client.receive_one()));
+            match event {
+                Ok(Some(ClientEvent::Particle { particle, .. })) if particle.id == particle_id => {
                     let result = read_args(particle, self.peer_id, &mut self.local_vm.lock());
                     if let Some(result) = result {
                         break result.map_err(|args| eyre!("AIR caught an error: {:?}", args));
                     }
                 }
+                Ok(Some(_)) => {}
+                Ok(None) => bail!("client stopped while waiting for particle {}", particle_id),
+                Err(_) => bail!("timed out waiting for particle {}", particle_id),
+            }
+        }
+    }
+
+    /// Send `script` once per node in `nodes`, passing the target node to the script as `node`,
+    /// and collect the responses in the order of `nodes`. Nodes that didn't respond before
+    /// `self.timeout` are reported as errors without failing the whole call.
+    pub fn send_to_all(
+        &mut self,
+        script: impl Into<String>,
+        data: HashMap<&str, JValue>,
+        nodes: &[PeerId],
+    ) -> Vec<Result<Vec<JValue>>> {
+        let script = script.into();
+        let ids: Vec<String> = nodes
+            .iter()
+            .map(|node| {
+                let mut data = data.clone();
+                data.insert("node", json!(node.to_string()));
+                self.send_particle(script.clone(), data)
+            })
+            .collect();
+
+        let mut results: HashMap<String, Result<Vec<JValue>>> = HashMap::new();
+        let deadline = Instant::now() + self.timeout();
+        while results.len() < ids.len() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            match task::block_on(timeout(remaining, self.client.receive_one())) {
+                Ok(Some(ClientEvent::Particle { particle, .. }))
+                    if ids.contains(&particle.id) && !results.contains_key(&particle.id) =>
+                {
+                    let id = particle.id.clone();
+                    let result = read_args(particle, self.peer_id, &mut self.local_vm.lock());
+                    if let Some(result) = result {
+                        let result =
+                            result.map_err(|args| eyre!("AIR caught an error: {:?}", args));
+                        results.insert(id, result);
+                    }
+                }
+                Ok(Some(_)) => {}
+                Ok(None) | Err(_) => break,
             }
         }
+
+        ids.into_iter()
+            .zip(nodes)
+            .map(|(id, node)| {
+                results.remove(&id).unwrap_or_else(|| {
+                    Err(eyre!("timed out waiting for particle {id} sent to {node}"))
+                })
+            })
+            .collect()
     }
 
     pub fn listen_for_n<O: Default, F: Fn(Result<Vec<JValue>, Vec<JValue>>) -> O>(
@@ -32,4 +32,9 @@ pub enum ClientEvent {
         peer_id: PeerId,
         multiaddr: Multiaddr,
     },
+    ConnectionClosed {
+        #[serde(with = "peerid_serializer")]
+        peer_id: PeerId,
+        multiaddr: Multiaddr,
+    },
 }
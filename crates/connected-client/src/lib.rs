@@ -32,9 +32,11 @@ mod client;
 mod command;
 mod connected_client;
 mod connection;
+mod error;
 mod event;
 
 pub use crate::connected_client::ConnectedClient;
 pub use command::ClientCommand;
 pub use connection::connect_real;
+pub use error::RelayDisconnected;
 pub use event::ClientEvent;
@@ -15,6 +15,7 @@
  */
 
 use std::convert::identity;
+use std::net::IpAddr;
 use std::path::Path;
 use std::{path::PathBuf, time::Duration};
 
@@ -63,6 +64,14 @@ pub struct CreatedSwarm {
     pub aquamarine_api: AquamarineApi,
 }
 
+impl CreatedSwarm {
+    /// Real directory of the vault for the given particle on this node's file system
+    pub fn particle_vault_dir(&self, particle_id: &str) -> PathBuf {
+        let avm_base_dir = server_config::avm_base_dir(&self.tmp_dir);
+        config_utils::particles_vault_dir(&avm_base_dir).join(particle_id)
+    }
+}
+
 pub fn make_swarms(n: usize) -> Vec<CreatedSwarm> {
     make_swarms_with_cfg(n, identity)
 }
@@ -229,6 +238,12 @@ pub struct SwarmConfig {
     pub builtins_dir: Option<PathBuf>,
     pub spell_base_dir: Option<PathBuf>,
     pub timer_resolution: Duration,
+    pub connection_idle_timeout: Option<Duration>,
+    /// Metrics and health endpoint are served on this port. Disabled if not set
+    pub metrics_port: Option<u16>,
+    /// Also listen on the counterpart of `listen_on` from the other IP version.
+    /// Requires `listen_on` to be a loopback TCP address
+    pub dual_stack: bool,
 }
 
 impl SwarmConfig {
@@ -248,10 +263,23 @@ impl SwarmConfig {
             builtins_dir: None,
             spell_base_dir: None,
             timer_resolution: default_script_storage_timer_resolution(),
+            connection_idle_timeout: None,
+            metrics_port: None,
+            dual_stack: false,
         }
     }
 }
 
+/// IP address and port of a TCP multiaddr, e.g. `/ip4/127.0.0.1/tcp/7777`
+fn tcp_listen_address(maddr: &Multiaddr) -> (IpAddr, u16) {
+    let mut protocols = maddr.iter();
+    match (protocols.next(), protocols.next()) {
+        (Some(Protocol::Ip4(ip)), Some(Protocol::Tcp(port))) => (ip.into(), port),
+        (Some(Protocol::Ip6(ip)), Some(Protocol::Tcp(port))) => (ip.into(), port),
+        _ => panic!("expected a TCP multiaddr, got {maddr}"),
+    }
+}
+
 pub struct BaseVmConfig {
     pub peer_id: PeerId,
     pub tmp_dir: PathBuf,
@@ -297,7 +325,7 @@ pub fn create_swarm_with_runtime<RT: AquaRuntime>(
     let tmp_dir = config.tmp_dir.as_ref().unwrap();
     create_dir(tmp_dir).expect("create tmp dir");
 
-    let node_config = json!({
+    let mut node_config = json!({
         "base_dir": tmp_dir.to_string_lossy(),
         "root_key_pair": {
             "format": format,
@@ -313,6 +341,12 @@ pub fn create_swarm_with_runtime<RT: AquaRuntime>(
         "external_multiaddresses": [config.listen_on],
         "spell_base_dir": Some(config.spell_base_dir.clone().unwrap_or(to_abs_path(PathBuf::from("spell")))),
     });
+    if config.dual_stack {
+        let (listen_ip, tcp_port) = tcp_listen_address(&config.listen_on);
+        node_config["listen_ip"] = json!(listen_ip);
+        node_config["tcp_port"] = json!(tcp_port);
+        node_config["dual_stack"] = json!(true);
+    }
 
     let node_config: UnresolvedConfig =
         UnresolvedConfig::deserialize(node_config).expect("created_swarm: deserialize config");
@@ -320,7 +354,10 @@ pub fn create_swarm_with_runtime<RT: AquaRuntime>(
     let mut resolved = node_config.resolve().expect("failed to resolve config");
     create_dir(&resolved.dir_config.builtins_base_dir).expect("create builtins dir");
 
-    resolved.node_config.transport_config.transport = Transport::Memory;
+    // `SwarmConfig::new` picks the in-memory transport for memory addresses, so most swarms
+    // stay in memory. Swarms listening on TCP need the network transport: the in-memory
+    // one can't listen on such addresses, so these swarms failed to start before
+    resolved.node_config.transport_config.transport = config.transport;
     resolved.node_config.transport_config.socket_timeout = TRANSPORT_TIMEOUT;
     resolved.node_config.protocol_config =
         ProtocolConfig::new(TRANSPORT_TIMEOUT, KEEP_ALIVE_TIMEOUT, TRANSPORT_TIMEOUT);
@@ -329,7 +366,10 @@ pub fn create_swarm_with_runtime<RT: AquaRuntime>(
     resolved.node_config.bootstrap_config = BootstrapConfig::zero();
     resolved.node_config.bootstrap_frequency = 1;
 
-    resolved.metrics_config.metrics_enabled = false;
+    resolved.metrics_config.metrics_enabled = config.metrics_port.is_some();
+    if let Some(metrics_port) = config.metrics_port {
+        resolved.metrics_config.metrics_port = metrics_port;
+    }
 
     resolved.node_config.allow_local_addresses = true;
 
@@ -337,6 +377,7 @@ pub fn create_swarm_with_runtime<RT: AquaRuntime>(
     resolved.node_config.particle_execution_timeout = EXECUTION_TIMEOUT;
 
     resolved.node_config.script_storage_timer_resolution = config.timer_resolution;
+    resolved.node_config.connection_idle_timeout = config.connection_idle_timeout;
 
     let management_kp = fluence_keypair::KeyPair::generate_ed25519();
     let management_peer_id = libp2p::identity::Keypair::from(management_kp.clone())
@@ -350,8 +391,19 @@ pub fn create_swarm_with_runtime<RT: AquaRuntime>(
         listen_on: config.listen_on.clone(),
         manager: management_peer_id,
     });
+    let listen_on = if config.dual_stack {
+        let listen_config = &resolved.node_config.listen_config;
+        listen_config
+            .listen_ips()
+            .into_iter()
+            .map(|ip| Multiaddr::from(ip).with(Protocol::Tcp(listen_config.tcp_port)))
+            .collect()
+    } else {
+        vec![config.listen_on.clone()]
+    };
+
     let mut node = Node::new(resolved, vm_config, "some version").expect("create node");
-    node.listen(vec![config.listen_on.clone()]).expect("listen");
+    node.listen(listen_on).expect("listen");
 
     (
         node.key_manager.get_host_peer_id(),
@@ -14,7 +14,8 @@
  * limitations under the License.
  */
 
-// blocks until either SIGINT(Ctrl+C) or SIGTERM signals received
+// blocks until either SIGINT(Ctrl+C) or SIGTERM signals received.
+// Once it has returned, a repeated signal terminates the process immediately
 pub fn block_until_ctrlc() {
     let (ctrlc_outlet, ctrlc_inlet) = futures::channel::oneshot::channel();
     let ctrlc_outlet = std::cell::RefCell::new(Some(ctrlc_outlet));
@@ -23,6 +24,10 @@ pub fn block_until_ctrlc() {
         println!("ctrlc fired!");
         if let Some(outlet) = ctrlc_outlet.borrow_mut().take() {
             outlet.send(()).expect("sending shutdown signal failed");
+        } else {
+            // graceful shutdown is already in progress, a repeated signal forces exit
+            println!("forcing exit");
+            std::process::exit(1);
         }
     })
     .expect("Error while setting ctrlc handler");
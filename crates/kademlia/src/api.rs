@@ -15,6 +15,7 @@
  */
 
 use std::convert::identity;
+use std::time::Duration;
 
 use futures::{channel::oneshot, future::BoxFuture, FutureExt};
 use libp2p::{core::Multiaddr, PeerId};
@@ -33,6 +34,11 @@ pub trait KademliaApiT {
     fn local_lookup(&self, peer: PeerId) -> Future<Result<Vec<Multiaddr>>>;
     fn discover_peer(&self, peer: PeerId) -> Future<Result<Vec<Multiaddr>>>;
     fn neighborhood(&self, key: Multihash, count: usize) -> Future<Result<Vec<PeerId>>>;
+    fn put_record(&self, key: Vec<u8>, value: Vec<u8>, ttl: Option<Duration>)
+        -> Future<Result<()>>;
+    fn get_record(&self, key: Vec<u8>) -> Future<Result<Option<Vec<u8>>>>;
+    fn start_providing(&self, key: Vec<u8>) -> Future<Result<()>>;
+    fn get_providers(&self, key: Vec<u8>) -> Future<Result<Vec<PeerId>>>;
 }
 
 // marked `pub` to be available in benchmarks
@@ -57,6 +63,25 @@ pub enum Command {
         count: usize,
         out: OneshotOutlet<Result<Vec<PeerId>>>,
     },
+    PutRecord {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        /// If not set, Kademlia default record TTL is used
+        ttl: Option<Duration>,
+        out: OneshotOutlet<Result<()>>,
+    },
+    GetRecord {
+        key: Vec<u8>,
+        out: OneshotOutlet<Result<Option<Vec<u8>>>>,
+    },
+    StartProviding {
+        key: Vec<u8>,
+        out: OneshotOutlet<Result<()>>,
+    },
+    GetProviders {
+        key: Vec<u8>,
+        out: OneshotOutlet<Result<Vec<PeerId>>>,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -111,4 +136,30 @@ impl KademliaApiT for KademliaApi {
     fn neighborhood(&self, key: Multihash, count: usize) -> Future<Result<Vec<PeerId>>> {
         self.execute(|out| Command::Neighborhood { key, count, out })
     }
+
+    fn put_record(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> Future<Result<()>> {
+        self.execute(|out| Command::PutRecord {
+            key,
+            value,
+            ttl,
+            out,
+        })
+    }
+
+    fn get_record(&self, key: Vec<u8>) -> Future<Result<Option<Vec<u8>>>> {
+        self.execute(|out| Command::GetRecord { key, out })
+    }
+
+    fn start_providing(&self, key: Vec<u8>) -> Future<Result<()>> {
+        self.execute(|out| Command::StartProviding { key, out })
+    }
+
+    fn get_providers(&self, key: Vec<u8>) -> Future<Result<Vec<PeerId>>> {
+        self.execute(|out| Command::GetProviders { key, out })
+    }
 }
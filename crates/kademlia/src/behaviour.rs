@@ -38,9 +38,11 @@ use libp2p::swarm::{
 use libp2p::{
     core::Multiaddr,
     kad::{
-        self, store::MemoryStore, BootstrapError, BootstrapOk, BootstrapResult,
-        GetClosestPeersError, GetClosestPeersOk, GetClosestPeersResult, KademliaEvent, QueryId,
-        QueryResult,
+        self, store::MemoryStore, AddProviderError, AddProviderResult, BootstrapError, BootstrapOk,
+        BootstrapResult, GetClosestPeersError, GetClosestPeersOk, GetClosestPeersResult,
+        GetProvidersError, GetProvidersOk, GetProvidersResult, GetRecordError, GetRecordOk,
+        GetRecordResult, KademliaEvent, PutRecordError, PutRecordResult, QueryId, QueryResult,
+        Quorum, Record,
     },
     swarm::NetworkBehaviour,
     PeerId,
@@ -51,6 +53,7 @@ use multihash::Multihash;
 use control_macro::get_return;
 use fluence_libp2p::types::{Inlet, OneshotOutlet};
 use particle_protocol::Contact;
+use peer_metrics::{KademliaMetrics, KademliaQueryOutcome};
 
 use crate::error::{KademliaError, Result};
 use crate::{Command, KademliaApi};
@@ -74,6 +77,10 @@ pub enum PendingQuery {
     Peer(PeerId),
     Neighborhood(OneshotOutlet<Result<Vec<PeerId>>>),
     Unit(OneshotOutlet<Result<()>>),
+    PutRecord(OneshotOutlet<Result<()>>),
+    GetRecord(OneshotOutlet<Result<Option<Vec<u8>>>>),
+    StartProviding(OneshotOutlet<Result<()>>),
+    GetProviders(OneshotOutlet<Result<Vec<PeerId>>>),
 }
 
 #[derive(Debug)]
@@ -117,10 +124,15 @@ pub struct Kademlia {
     // Timer to track timed out requests, and return errors ASAP
     timer: Delay,
     metrics: Option<Metrics>,
+    query_metrics: Option<KademliaMetrics>,
 }
 
 impl Kademlia {
-    pub fn new(config: KademliaConfig, metrics: Option<Metrics>) -> (Self, KademliaApi) {
+    pub fn new(
+        config: KademliaConfig,
+        metrics: Option<Metrics>,
+        query_metrics: Option<KademliaMetrics>,
+    ) -> (Self, KademliaApi) {
         let timer = Delay::new(config.query_timeout);
 
         let store = MemoryStore::new(config.peer_id);
@@ -139,6 +151,7 @@ impl Kademlia {
             waker: None,
             timer,
             metrics,
+            query_metrics,
         };
 
         (behaviour, api)
@@ -151,6 +164,15 @@ impl Kademlia {
             Command::LocalLookup { peer, out } => self.local_lookup(&peer, out),
             Command::DiscoverPeer { peer, out } => self.discover_peer(peer, out),
             Command::Neighborhood { key, count, out } => self.neighborhood(key, count, out),
+            Command::PutRecord {
+                key,
+                value,
+                ttl,
+                out,
+            } => self.put_record(key, value, ttl, out),
+            Command::GetRecord { key, out } => self.get_record(key, out),
+            Command::StartProviding { key, out } => self.start_providing(key, out),
+            Command::GetProviders { key, out } => self.get_providers(key, out),
         }
     }
 
@@ -173,8 +195,7 @@ impl Kademlia {
 
     pub fn bootstrap(&mut self, outlet: OneshotOutlet<Result<()>>) {
         if let Ok(query_id) = self.kademlia.bootstrap() {
-            self.queries.insert(query_id, PendingQuery::Unit(outlet));
-            self.wake();
+            self.track_query(query_id, PendingQuery::Unit(outlet));
         } else {
             outlet.send(Err(KademliaError::NoKnownPeers)).ok();
         }
@@ -205,8 +226,7 @@ impl Kademlia {
         // Run discovery only if there's no discovery already running
         if !discovering {
             let query_id = self.kademlia.get_closest_peers(peer);
-            self.queries.insert(query_id, PendingQuery::Peer(peer));
-            self.wake();
+            self.track_query(query_id, PendingQuery::Peer(peer));
         }
     }
 
@@ -224,19 +244,70 @@ impl Kademlia {
         self.wake();
     }
 
+    /// Stores record locally and replicates it to `replication_factor` closest peers.
+    /// Succeeds if at least one remote peer has stored the record
+    pub fn put_record(
+        &mut self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        outlet: OneshotOutlet<Result<()>>,
+    ) {
+        let mut record = Record::new(key, value);
+        record.expires = ttl.map(|ttl| Instant::now() + ttl);
+        match self.kademlia.put_record(record, Quorum::One) {
+            Ok(query_id) => {
+                self.track_query(query_id, PendingQuery::PutRecord(outlet));
+            }
+            Err(err) => {
+                outlet.send(Err(KademliaError::RecordStoreFailed(err))).ok();
+            }
+        }
+    }
+
+    /// Looks up record in the local store first, then in the network
+    pub fn get_record(&mut self, key: Vec<u8>, outlet: OneshotOutlet<Result<Option<Vec<u8>>>>) {
+        let query_id = self.kademlia.get_record(key.into(), Quorum::One);
+        self.track_query(query_id, PendingQuery::GetRecord(outlet));
+    }
+
+    /// Announces local peer as a provider of `key` to the closest peers
+    pub fn start_providing(&mut self, key: Vec<u8>, outlet: OneshotOutlet<Result<()>>) {
+        match self.kademlia.start_providing(key.into()) {
+            Ok(query_id) => {
+                self.track_query(query_id, PendingQuery::StartProviding(outlet));
+            }
+            Err(err) => {
+                outlet.send(Err(KademliaError::RecordStoreFailed(err))).ok();
+            }
+        }
+    }
+
+    /// Collects providers of `key` from the local store and the network
+    pub fn get_providers(&mut self, key: Vec<u8>, outlet: OneshotOutlet<Result<Vec<PeerId>>>) {
+        let query_id = self.kademlia.get_providers(key.into());
+        self.track_query(query_id, PendingQuery::GetProviders(outlet));
+    }
+
     pub fn remote_neighborhood(
         &mut self,
         key: Multihash,
         outlet: OneshotOutlet<Result<Vec<PeerId>>>,
     ) {
         let query_id = self.kademlia.get_closest_peers(key);
-        self.queries
-            .insert(query_id, PendingQuery::Neighborhood(outlet));
-        self.wake();
+        self.track_query(query_id, PendingQuery::Neighborhood(outlet));
     }
 }
 
 impl Kademlia {
+    fn track_query(&mut self, query_id: QueryId, query: PendingQuery) {
+        if let Some(metrics) = &self.query_metrics {
+            metrics.query_started.inc();
+        }
+        self.queries.insert(query_id, query);
+        self.wake();
+    }
+
     fn peer_discovered(&mut self, peer: PeerId, addresses: Vec<Multiaddr>) {
         log::trace!(
             target: "network",
@@ -279,6 +350,67 @@ impl Kademlia {
             PendingQuery::Unit(outlet) => {
                 outlet.send(Ok(())).ok();
             }
+            // record and provider queries finish with their own results
+            PendingQuery::PutRecord(_)
+            | PendingQuery::GetRecord(_)
+            | PendingQuery::StartProviding(_)
+            | PendingQuery::GetProviders(_) => {}
+        }
+    }
+
+    fn put_record_finished(&mut self, id: QueryId, result: PutRecordResult) {
+        if let Some(PendingQuery::PutRecord(outlet)) = self.queries.remove(&id) {
+            let result = match result {
+                Ok(_) => Ok(()),
+                Err(PutRecordError::QuorumFailed { .. }) => Err(KademliaError::RecordNotReplicated),
+                Err(PutRecordError::Timeout { .. }) => Err(KademliaError::QueryTimedOut),
+            };
+            outlet.send(result).ok();
+        }
+    }
+
+    fn get_record_finished(&mut self, id: QueryId, result: GetRecordResult) {
+        use GetRecordError::{QuorumFailed, Timeout};
+
+        if let Some(PendingQuery::GetRecord(outlet)) = self.queries.remove(&id) {
+            let result = match result {
+                Ok(GetRecordOk { records, .. }) => {
+                    Ok(records.into_iter().next().map(|r| r.record.value))
+                }
+                Err(QuorumFailed { records, .. } | Timeout { records, .. })
+                    if !records.is_empty() =>
+                {
+                    Ok(records.into_iter().next().map(|r| r.record.value))
+                }
+                Err(Timeout { .. }) => Err(KademliaError::QueryTimedOut),
+                Err(_) => Ok(None),
+            };
+            outlet.send(result).ok();
+        }
+    }
+
+    fn start_providing_finished(&mut self, id: QueryId, result: AddProviderResult) {
+        if let Some(PendingQuery::StartProviding(outlet)) = self.queries.remove(&id) {
+            let result = match result {
+                Ok(_) => Ok(()),
+                Err(AddProviderError::Timeout { .. }) => Err(KademliaError::QueryTimedOut),
+            };
+            outlet.send(result).ok();
+        }
+    }
+
+    fn get_providers_finished(&mut self, id: QueryId, result: GetProvidersResult) {
+        use GetProvidersError::Timeout;
+
+        if let Some(PendingQuery::GetProviders(outlet)) = self.queries.remove(&id) {
+            let result = match result {
+                Ok(GetProvidersOk { providers, .. }) => Ok(providers.into_iter().collect()),
+                Err(Timeout { providers, .. }) if !providers.is_empty() => {
+                    Ok(providers.into_iter().collect())
+                }
+                Err(Timeout { .. }) => Err(KademliaError::QueryTimedOut),
+            };
+            outlet.send(result).ok();
         }
     }
 
@@ -388,11 +520,30 @@ impl Kademlia {
         }
 
         match event {
-            KademliaEvent::OutboundQueryCompleted { id, result, .. } => match result {
-                QueryResult::GetClosestPeers(result) => self.closest_finished(id, result),
-                QueryResult::Bootstrap(result) => self.bootstrap_finished(id, result),
-                _ => {}
-            },
+            KademliaEvent::OutboundQueryCompleted { id, result, stats } => {
+                // queries started by libp2p itself, e.g. record republishing, aren't counted
+                let tracked = self.queries.contains_key(&id);
+                let outcome = query_outcome(&result);
+
+                match result {
+                    QueryResult::GetClosestPeers(result) => self.closest_finished(id, result),
+                    QueryResult::Bootstrap(result) => self.bootstrap_finished(id, result),
+                    QueryResult::PutRecord(result) => self.put_record_finished(id, result),
+                    QueryResult::GetRecord(result) => self.get_record_finished(id, result),
+                    QueryResult::StartProviding(result) => {
+                        self.start_providing_finished(id, result)
+                    }
+                    QueryResult::GetProviders(result) => self.get_providers_finished(id, result),
+                    _ => {}
+                }
+
+                // bootstrap completes once per bucket, so count the query only when it's done
+                if tracked && !self.queries.contains_key(&id) {
+                    if let Some(metrics) = &self.query_metrics {
+                        metrics.query_finished(outcome, stats.duration());
+                    }
+                }
+            }
             KademliaEvent::UnroutablePeer { .. } => {}
             KademliaEvent::RoutingUpdated {
                 peer, addresses, ..
@@ -406,6 +557,29 @@ impl Kademlia {
     }
 }
 
+fn query_outcome(result: &QueryResult) -> KademliaQueryOutcome {
+    use KademliaQueryOutcome::*;
+
+    match result {
+        QueryResult::Bootstrap(Err(_))
+        | QueryResult::GetClosestPeers(Err(_))
+        | QueryResult::GetProviders(Err(_))
+        | QueryResult::StartProviding(Err(_))
+        | QueryResult::RepublishProvider(Err(_))
+        | QueryResult::GetRecord(Err(GetRecordError::Timeout { .. }))
+        | QueryResult::PutRecord(Err(PutRecordError::Timeout { .. }))
+        | QueryResult::RepublishRecord(Err(PutRecordError::Timeout { .. })) => TimedOut,
+        // API reports it as NoPeersFound
+        QueryResult::GetClosestPeers(Ok(GetClosestPeersOk { peers, .. })) if peers.is_empty() => {
+            Failed
+        }
+        QueryResult::GetRecord(Err(_))
+        | QueryResult::PutRecord(Err(_))
+        | QueryResult::RepublishRecord(Err(_)) => Failed,
+        _ => Succeeded,
+    }
+}
+
 /// Calculate whether some entity has reached its timeout.
 /// `now` - current time
 /// `timestamp` - starting point
@@ -581,6 +755,8 @@ mod tests {
 
     use fluence_libp2p::random_multiaddr::create_memory_maddr;
     use fluence_libp2p::{build_memory_transport, RandomPeerId};
+    use peer_metrics::KademliaMetrics;
+    use prometheus_client::registry::Registry;
 
     use crate::{KademliaConfig, KademliaError};
 
@@ -599,11 +775,15 @@ mod tests {
     }
 
     fn make_node() -> (Swarm<Kademlia>, Multiaddr) {
+        make_node_with_metrics(None)
+    }
+
+    fn make_node_with_metrics(metrics: Option<KademliaMetrics>) -> (Swarm<Kademlia>, Multiaddr) {
         let kp = Keypair::generate_ed25519();
         let public_key = kp.public();
         let peer_id = PeerId::from(public_key);
         let config = kad_config(peer_id);
-        let (kad, _) = Kademlia::new(config, None);
+        let (kad, _) = Kademlia::new(config, None, metrics);
         let timeout = Duration::from_secs(20);
 
         let mut swarm = Swarm::new(build_memory_transport(kp, timeout), kad, peer_id);
@@ -729,4 +909,35 @@ mod tests {
             .unwrap();
         assert!(matches!(banned, Err(KademliaError::PeerBanned)));
     }
+
+    #[test]
+    fn query_metrics() {
+        let metrics = KademliaMetrics::new(&mut Registry::default());
+        let (mut node, _) = make_node_with_metrics(Some(metrics.clone()));
+
+        let (out, inlet) = oneshot::channel();
+        node.behaviour_mut()
+            .remote_neighborhood(RandomPeerId::random().into(), out);
+        assert_eq!(metrics.query_started.get(), 1);
+        assert_eq!(metrics.query_succeeded.get(), 0);
+
+        let result = task::block_on(async move {
+            let t = task::spawn(futures::future::poll_fn(move |ctx| {
+                while node.poll_next_unpin(ctx).is_ready() {}
+                ctx.waker().wake_by_ref();
+                Poll::Pending as Poll<()>
+            }));
+
+            let result = inlet.await;
+            t.cancel().await;
+            result
+        });
+
+        // node knows no peers, so query finishes right away with an empty result
+        assert!(matches!(result, Ok(Err(KademliaError::NoPeersFound))));
+        assert_eq!(metrics.query_started.get(), 1);
+        assert_eq!(metrics.query_succeeded.get(), 0);
+        assert_eq!(metrics.query_failed.get(), 1);
+        assert_eq!(metrics.query_timed_out.get(), 0);
+    }
 }
@@ -32,4 +32,8 @@ pub enum KademliaError {
     NoKnownPeers,
     #[error("KademliaError::PeerBanned")]
     PeerBanned,
+    #[error("KademliaError::RecordStoreFailed: {0}")]
+    RecordStoreFailed(#[source] libp2p::kad::record::store::Error),
+    #[error("KademliaError::RecordNotReplicated")]
+    RecordNotReplicated,
 }
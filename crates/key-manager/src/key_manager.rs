@@ -14,14 +14,16 @@
  * limitations under the License.
  */
 
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
 use fluence_keypair::{KeyFormat, KeyPair};
 use libp2p::PeerId;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::error::PersistedKeypairError;
+use crate::error::PersistedKeypairError::{CannotExtractRSASecretKey, WriteErrorPersistedKeypair};
 use crate::persistence::{load_persisted_keypairs, persist_keypair, PersistedKeypair};
 use parking_lot::RwLock;
 
@@ -80,6 +82,11 @@ impl KeyManager {
         self.scope_peer_ids.read().contains_key(&remote_peer_id)
     }
 
+    /// Returns scope peer id associated with `remote_peer_id`, doesn't generate a new one
+    pub fn find_scope_peer_id(&self, remote_peer_id: PeerId) -> Option<PeerId> {
+        self.scope_peer_ids.read().get(&remote_peer_id).cloned()
+    }
+
     pub fn is_scope_peer_id(&self, scope_peer_id: PeerId) -> bool {
         self.scope_keypairs.read().contains_key(&scope_peer_id)
     }
@@ -105,6 +112,13 @@ impl KeyManager {
         }
     }
 
+    /// Peer ids of all managed worker keypairs, including the ones restored from `keypairs_dir`
+    pub fn list_keypairs(&self) -> Vec<PeerId> {
+        let mut peer_ids: Vec<_> = self.scope_keypairs.read().keys().cloned().collect();
+        peer_ids.sort();
+        peer_ids
+    }
+
     pub fn get_scope_keypair(&self, scope_peer_id: PeerId) -> eyre::Result<KeyPair> {
         self.scope_keypairs
             .read()
@@ -135,4 +149,109 @@ impl KeyManager {
 
         Ok(())
     }
+
+    /// Generates a new root keypair of `key_format` and writes its base64 secret key
+    /// to `root_key_path`, the file the node loads its root keypair from.
+    /// Previous key is kept next to it, with `.old` appended to the file name.
+    ///
+    /// The running node keeps its current identity, the new one is used after restart.
+    /// Since host peer id changes, all peers will have to re-establish their connections,
+    /// and addresses with the old peer id, e.g. in bootstrap lists, must be updated.
+    ///
+    /// Returns old and new host peer ids.
+    pub fn rotate_root_keypair(
+        &self,
+        root_key_path: &Path,
+        key_format: KeyFormat,
+    ) -> Result<(PeerId, PeerId), PersistedKeypairError> {
+        let keypair = KeyPair::generate(key_format);
+        let secret_key = keypair.secret().map_err(|_| CannotExtractRSASecretKey)?;
+
+        // write to a temporary file first, so the key is never left half-written
+        let with_suffix = |suffix: &str| {
+            let mut path = root_key_path.as_os_str().to_owned();
+            path.push(suffix);
+            PathBuf::from(path)
+        };
+        let tmp_path = with_suffix(".new");
+        let old_path = with_suffix(".old");
+        let result: std::io::Result<()> = try {
+            std::fs::write(&tmp_path, base64.encode(secret_key))?;
+            if root_key_path.exists() {
+                std::fs::copy(root_key_path, &old_path)?;
+            }
+            std::fs::rename(&tmp_path, root_key_path)?;
+        };
+        result.map_err(|err| WriteErrorPersistedKeypair {
+            path: root_key_path.to_path_buf(),
+            err,
+        })?;
+
+        let new_peer_id = keypair.get_peer_id();
+        log::info!(
+            "Root keypair rotated: {} -> {}, restart the node to use the new one",
+            self.host_peer_id,
+            new_peer_id
+        );
+
+        Ok((self.host_peer_id, new_peer_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD as base64, Engine};
+    use fluence_keypair::{KeyFormat, KeyPair};
+
+    use fluence_libp2p::RandomPeerId;
+    use fs_utils::make_tmp_dir;
+
+    use crate::KeyManager;
+
+    #[test]
+    fn list_keypairs() {
+        let dir = make_tmp_dir();
+        let key_manager = KeyManager::new(dir.clone(), RandomPeerId::random());
+        assert!(key_manager.list_keypairs().is_empty());
+
+        let first = key_manager
+            .get_scope_peer_id(RandomPeerId::random())
+            .unwrap();
+        let second = key_manager
+            .get_scope_peer_id(RandomPeerId::random())
+            .unwrap();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(key_manager.list_keypairs(), expected);
+
+        // keypairs are restored from disk
+        let restored = KeyManager::new(dir, RandomPeerId::random());
+        assert_eq!(restored.list_keypairs(), expected);
+    }
+
+    #[test]
+    fn rotate_root_keypair() {
+        let dir = make_tmp_dir();
+        let root_key_path = dir.join("secret_key.ed25519");
+        let old_keypair = KeyPair::generate_ed25519();
+        let old_secret = base64.encode(old_keypair.secret().unwrap());
+        std::fs::write(&root_key_path, &old_secret).unwrap();
+
+        let key_manager = KeyManager::new(dir.join("keypairs"), old_keypair.get_peer_id());
+        let (old_peer_id, new_peer_id) = key_manager
+            .rotate_root_keypair(&root_key_path, KeyFormat::Ed25519)
+            .expect("rotate root keypair");
+        assert_eq!(old_peer_id, old_keypair.get_peer_id());
+        assert_ne!(old_peer_id, new_peer_id);
+
+        // new key is persisted in the same format the node loads it from
+        let secret = std::fs::read_to_string(&root_key_path).unwrap();
+        let secret = base64.decode(secret).unwrap();
+        let loaded = KeyPair::from_secret_key(secret, KeyFormat::Ed25519).unwrap();
+        assert_eq!(loaded.get_peer_id(), new_peer_id);
+
+        // previous key is kept
+        let backup = std::fs::read_to_string(dir.join("secret_key.ed25519.old")).unwrap();
+        assert_eq!(backup, old_secret);
+    }
 }
@@ -30,3 +30,10 @@ pub fn create_tcp_maddr() -> Multiaddr {
     maddr.push(Protocol::Tcp(port));
     maddr
 }
+
+pub fn create_tcp_ipv6_maddr() -> Multiaddr {
+    let port: u16 = 1000 + rand::thread_rng().gen_range(1..3000);
+    let mut maddr: Multiaddr = Protocol::Ip6("::1".parse().unwrap()).into();
+    maddr.push(Protocol::Tcp(port));
+    maddr
+}
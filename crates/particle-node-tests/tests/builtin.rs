@@ -21,6 +21,10 @@ use std::collections::HashMap;
 use std::str::FromStr;
 use std::time::Duration;
 
+use base64::{
+    engine::general_purpose::{STANDARD as base64, URL_SAFE},
+    Engine,
+};
 use eyre::{Report, WrapErr};
 use fluence_keypair::{KeyPair, Signature};
 use itertools::Itertools;
@@ -33,7 +37,7 @@ use serde_json::{json, Value as JValue};
 
 use connected_client::ConnectedClient;
 use created_swarm::{
-    make_swarms, make_swarms_with_builtins, make_swarms_with_keypair,
+    make_swarms, make_swarms_with_builtins, make_swarms_with_cfg, make_swarms_with_keypair,
     make_swarms_with_transport_and_mocked_vm,
 };
 use fluence_libp2p::RandomPeerId;
@@ -47,8 +51,13 @@ use test_utils::create_service;
 
 #[derive(Deserialize, Debug)]
 struct NodeInfo {
-    #[allow(dead_code)]
     pub external_addresses: Vec<Multiaddr>,
+    pub uptime_sec: u64,
+    pub connected_peers: usize,
+    pub builtins: Vec<(String, String)>,
+    pub git_commit: String,
+    pub build_timestamp: String,
+    pub rustc_version: String,
 }
 
 #[test]
@@ -73,8 +82,169 @@ fn identify() {
     );
 
     let info = client.receive_args().wrap_err("receive args").unwrap();
-    let _: NodeInfo = serde_json::from_value(info[0].clone())
+    let info: NodeInfo = serde_json::from_value(info[0].clone())
         .unwrap_or_else(|_| panic!("deserialize {:?}", info[0]));
+    // swarm was started during this test
+    assert!(info.uptime_sec < 600);
+    // at least the client is connected
+    assert!(info.connected_peers >= 1);
+    // build info falls back to "unknown", but is never missing
+    assert!(!info.git_commit.is_empty());
+    assert!(!info.build_timestamp.is_empty());
+    assert!(info.rustc_version.starts_with("rustc") || info.rustc_version == "unknown");
+
+    let has_builtin = |service: &str, function: &str| {
+        info.builtins
+            .iter()
+            .any(|(s, f)| s == service && f == function)
+    };
+    assert!(has_builtin("op", "noop"));
+    assert!(has_builtin("peer", "identify"));
+    assert!(has_builtin("srv", "create"));
+    assert!(!has_builtin("op", "no_such_function"));
+}
+
+#[test]
+fn set_external_address() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .wrap_err("connect client")
+    .unwrap();
+
+    let mut external_addresses = |remove: bool| -> Vec<Multiaddr> {
+        client.send_particle(
+            r#"
+            (seq
+                (seq
+                    (call relay ("peer" "set_external_address") [maddr remove])
+                    (call relay ("peer" "identify") [] info)
+                )
+                (call client ("op" "return") [info])
+            )
+            "#,
+            hashmap! {
+                "relay" => json!(client.node.to_string()),
+                "client" => json!(client.peer_id.to_string()),
+                "maddr" => json!("/ip4/8.8.8.8/tcp/7777"),
+                "remove" => json!(remove),
+            },
+        );
+
+        let info = client.receive_args().wrap_err("receive args").unwrap();
+        let info: NodeInfo = serde_json::from_value(info[0].clone())
+            .unwrap_or_else(|_| panic!("deserialize {:?}", info[0]));
+        info.external_addresses
+    };
+
+    let maddr: Multiaddr = "/ip4/8.8.8.8/tcp/7777".parse().unwrap();
+    assert!(external_addresses(false).contains(&maddr));
+    assert!(!external_addresses(true).contains(&maddr));
+}
+
+#[test]
+fn idle_connection_is_closed() {
+    let swarms = make_swarms_with_cfg(1, |mut cfg| {
+        cfg.connection_idle_timeout = Some(Duration::from_secs(1));
+        cfg
+    });
+
+    let idle = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect idle client")
+        .unwrap();
+    let mut active = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect active client")
+        .unwrap();
+    let active_peer_id = active.peer_id;
+
+    let mut is_connected = |peer_id: PeerId| -> bool {
+        active.send_particle(
+            r#"
+            (seq
+                (call relay ("peer" "is_connected") [peer_id] connected)
+                (call client ("op" "return") [connected])
+            )
+            "#,
+            hashmap! {
+                "relay" => json!(active.node.to_string()),
+                "client" => json!(active.peer_id.to_string()),
+                "peer_id" => json!(peer_id.to_string()),
+            },
+        );
+
+        let args = active.receive_args().wrap_err("receive args").unwrap();
+        args[0].as_bool().expect("is_connected must return bool")
+    };
+
+    assert!(is_connected(idle.peer_id));
+
+    // active client keeps its connection busy for several idle timeouts
+    for _ in 0..10 {
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(is_connected(active_peer_id));
+    }
+
+    assert!(!is_connected(idle.peer_id));
+}
+
+#[test]
+fn ping() {
+    let swarms = make_swarms(2);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("peer" "ping") [peer 10000] rtt)
+            (call client ("op" "return") [rtt])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+            "peer" => json!(swarms[1].peer_id.to_string()),
+        },
+    );
+
+    let rtt = client.receive_args().wrap_err("receive args").unwrap();
+    let rtt = rtt[0].as_u64();
+    assert!(rtt.is_some(), "expected non-negative latency, got {rtt:?}");
+}
+
+#[test]
+fn ping_unknown_peer() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    client.send_particle(
+        r#"
+        (seq
+            (xor
+                (call relay ("peer" "ping") [peer 100] rtt)
+                (ap %last_error%.$.message error)
+            )
+            (call client ("op" "return") [error])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+            "peer" => json!(RandomPeerId::random().to_string()),
+        },
+    );
+
+    let error = client.receive_args().wrap_err("receive args").unwrap();
+    let error = error[0].as_str().unwrap();
+    assert!(error.contains("isn't connected"), "{error}");
 }
 
 #[ignore]
@@ -260,6 +430,96 @@ fn remove_service_by_alias() {
     }
 }
 
+#[test]
+fn restart_service() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .wrap_err("connect client")
+    .unwrap();
+
+    let tetraplets_service = create_service(
+        &mut client,
+        "tetraplets",
+        load_module("tests/tetraplets/artifacts", "tetraplets").expect("load module"),
+    );
+
+    client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call relay ("srv" "add_alias") [alias service])
+                (call relay ("srv" "restart") [alias])
+            )
+            (seq
+                (seq
+                    (call relay ("srv" "resolve_alias") [alias] service_id)
+                    (call relay ("srv" "list_aliases") [service] aliases)
+                )
+                (seq
+                    (call relay (alias "not") [true] not_true)
+                    (call %init_peer_id% ("op" "return") [service_id aliases not_true])
+                )
+            )
+        )
+    "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "service" => json!(tetraplets_service.id),
+            "alias" => json!("some_alias".to_string()),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    assert_eq!(
+        result,
+        vec![
+            json!(tetraplets_service.id),
+            json!(["some_alias"]),
+            json!(false)
+        ]
+    );
+}
+
+#[test]
+fn restart_service_forbidden() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let tetraplets_service = create_service(
+        &mut client,
+        "tetraplets",
+        load_module("tests/tetraplets/artifacts", "tetraplets").expect("load module"),
+    );
+
+    client.send_particle(
+        r#"
+        (xor
+            (call relay ("srv" "restart") [service])
+            (call %init_peer_id% ("op" "return") [%last_error%.$.message])
+        )
+    "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "service" => json!(tetraplets_service.id),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    let error = result[0].as_str().unwrap();
+    assert!(
+        error.contains("only management peer id can restart services"),
+        "{}",
+        error
+    );
+}
+
 #[test]
 fn non_owner_remove_service() {
     let swarms = make_swarms(1);
@@ -314,6 +574,116 @@ fn non_owner_remove_service() {
     }
 }
 
+#[test]
+fn remove_unused_blueprint() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .wrap_err("connect client")
+    .unwrap();
+
+    let module = load_module("tests/tetraplets/artifacts", "tetraplets").expect("load module");
+
+    client.send_particle(
+        r#"
+        (seq
+            (seq
+                (seq
+                    (call relay ("dist" "default_module_config") [module_name] module_config)
+                    (call relay ("dist" "add_module") [module_bytes module_config] module)
+                )
+                (seq
+                    (call relay ("dist" "make_blueprint") [module_name dependencies] blueprint)
+                    (call relay ("dist" "add_blueprint") [blueprint] blueprint_id)
+                )
+            )
+            (seq
+                (seq
+                    (call relay ("dist" "list_blueprints") [] list_before)
+                    (call relay ("dist" "remove_blueprint") [blueprint_id])
+                )
+                (seq
+                    (call relay ("dist" "list_blueprints") [] list_after)
+                    (call %init_peer_id% ("op" "return") [list_before list_after])
+                )
+            )
+        )
+    "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "module_name" => json!("tetraplets"),
+            "module_bytes" => json!(base64.encode(module)),
+            "dependencies" => json!(["tetraplets"]),
+        },
+    );
+
+    use serde_json::Value::Array;
+
+    if let [Array(before), Array(after)] = client.receive_args().unwrap().as_slice() {
+        assert_eq!(before.len(), 1);
+        assert_eq!(after.len(), 0);
+    } else {
+        panic!("incorrect args: expected two arrays")
+    }
+}
+
+#[test]
+fn remove_used_blueprint() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .wrap_err("connect client")
+    .unwrap();
+
+    let tetraplets_service = create_service(
+        &mut client,
+        "tetraplets",
+        load_module("tests/tetraplets/artifacts", "tetraplets").expect("load module"),
+    );
+
+    client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call relay ("dist" "list_blueprints") [] list_before)
+                (xor
+                    (call relay ("dist" "remove_blueprint") [list_before.$.[0].id!])
+                    (ap %last_error%.$.message error)
+                )
+            )
+            (seq
+                (call relay ("dist" "list_blueprints") [] list_after)
+                (call %init_peer_id% ("op" "return") [list_before list_after error])
+            )
+        )
+    "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+        },
+    );
+
+    use serde_json::Value::{Array, String};
+
+    let args = client.receive_args().unwrap();
+    if let [Array(before), Array(after), String(error)] = args.as_slice() {
+        assert_eq!(before.len(), 1);
+        assert_eq!(after.len(), 1);
+        assert!(error.contains("can't be removed"));
+        assert!(error.contains(&tetraplets_service.id));
+    } else {
+        panic!(
+            "incorrect args: expected two arrays and an error, got: {:?}",
+            args
+        )
+    }
+}
+
 #[test]
 fn resolve_alias() {
     let swarms = make_swarms(1);
@@ -434,6 +804,119 @@ fn resolve_alias_removed() {
     );
 }
 
+#[test]
+fn service_last_error() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let tetraplets_service = create_service(
+        &mut client,
+        "tetraplets",
+        load_module("tests/tetraplets/artifacts", "tetraplets").expect("load module"),
+    );
+
+    client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call relay ("srv" "last_error") [service] before)
+                (xor
+                    (call relay (service "no_such_function") [])
+                    (null)
+                )
+            )
+            (seq
+                (call relay ("srv" "last_error") [service] after)
+                (xor
+                    (call relay ("srv" "last_error") ["no_such_service"])
+                    (call %init_peer_id% ("op" "return") [before after %last_error%.$.message])
+                )
+            )
+        )
+    "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "service" => json!(tetraplets_service.id),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    let [before, after, unknown_service] = result.as_slice() else {
+        panic!("expected 3 values, got {:?}", result);
+    };
+    assert_eq!(before, &JValue::Null);
+    assert_eq!(after["function_name"], "no_such_function");
+    assert!(!after["message"].as_str().unwrap().is_empty());
+    assert!(after["timestamp"].as_u64().unwrap() > 0);
+    assert!(
+        unknown_service
+            .as_str()
+            .unwrap()
+            .contains("Service with id 'no_such_service' not found"),
+        "{}",
+        unknown_service
+    );
+}
+
+#[test]
+fn init_peer_id() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("peer" "init_peer_id") [] result)
+            (call client ("op" "return") [result])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    assert_eq!(result, vec![json!(client.peer_id.to_string())]);
+}
+
+#[test]
+fn particle_id_and_timestamp() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let sent_at = now_ms() as u64;
+    let particle_id = client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call relay ("peer" "particle_id") [] id)
+                (call relay ("peer" "particle_timestamp_ms") [] timestamp)
+            )
+            (call client ("op" "return") [id timestamp])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    assert_eq!(result[0], json!(particle_id));
+    let timestamp = result[1].as_u64().unwrap();
+    assert!(timestamp >= sent_at && timestamp <= now_ms() as u64);
+}
+
 #[test]
 fn timestamp_ms() {
     let swarms = make_swarms(1);
@@ -487,54 +970,238 @@ fn timestamp_sec() {
 }
 
 #[test]
-fn base58_string_builtins() {
+fn base58_string_builtins() {
+    let script = r#"
+    (seq
+        (call relay ("op" "string_to_b58") [string] b58_string_out)
+        (seq
+            (call relay ("op" "string_from_b58") [b58_string] string_out)
+            (call relay ("op" "string_from_b58") [b58_string_out] identity_string)
+        )
+    )
+    "#;
+
+    let string = "hello, this is a string! ДОБРЫЙ ВЕЧЕР КАК СЛЫШНО";
+    let b58_string = bs58::encode(string).into_string();
+    let args = hashmap! {
+        "string" => json!(string),
+        "b58_string" => json!(b58_string),
+    };
+
+    let result = exec_script(script, args, "b58_string_out string_out identity_string", 1).unwrap();
+    assert_eq!(result[0], JValue::String(b58_string));
+    assert_eq!(result[1], JValue::String(string.into()));
+    assert_eq!(result[2], JValue::String(string.into()));
+}
+
+#[test]
+fn base58_bytes_builtins() {
+    let script = r#"
+    (seq
+        (call relay ("op" "bytes_to_b58") [bytes] b58_string_out)
+        (seq
+            (call relay ("op" "bytes_from_b58") [b58_string] bytes_out)
+            (call relay ("op" "bytes_from_b58") [b58_string_out] identity_bytes)
+        )
+    )
+    "#;
+
+    let bytes: Vec<_> = (1..32).map(|i| (200 + i) as u8).collect();
+    let b58_string = bs58::encode(&bytes).into_string();
+    let args = hashmap! {
+        "b58_string" => json!(b58_string),
+        "bytes" => json!(bytes),
+    };
+
+    let result = exec_script(script, args, "b58_string_out bytes_out identity_bytes", 1).unwrap();
+    // let array = into_array(result).expect("must be an array");
+    assert_eq!(result[0], json!(b58_string));
+    assert_eq!(result[1], json!(bytes));
+    assert_eq!(result[2], json!(bytes));
+}
+
+#[test]
+fn base64_string_builtins() {
+    let script = r#"
+    (seq
+        (call relay ("op" "string_to_b64") [string] b64_string_out)
+        (seq
+            (call relay ("op" "string_from_b64") [b64_string] string_out)
+            (call relay ("op" "string_from_b64") [b64_string_out] identity_string)
+        )
+    )
+    "#;
+
+    let string = "hello, this is a string! ДОБРЫЙ ВЕЧЕР КАК СЛЫШНО";
+    let b64_string = base64.encode(string);
+    let args = hashmap! {
+        "string" => json!(string),
+        "b64_string" => json!(b64_string),
+    };
+
+    let result = exec_script(script, args, "b64_string_out string_out identity_string", 1).unwrap();
+    assert_eq!(result[0], JValue::String(b64_string));
+    assert_eq!(result[1], JValue::String(string.into()));
+    assert_eq!(result[2], JValue::String(string.into()));
+}
+
+#[test]
+fn base64_bytes_builtins() {
+    let script = r#"
+    (seq
+        (seq
+            (call relay ("op" "bytes_to_b64") [bytes] b64_string_out)
+            (call relay ("op" "bytes_to_b64") [bytes true] url_safe_out)
+        )
+        (seq
+            (seq
+                (call relay ("op" "bytes_from_b64") [b64_string] bytes_out)
+                (call relay ("op" "bytes_from_b64") [b64_string_out] identity_bytes)
+            )
+            (call relay ("op" "bytes_from_b64") [url_safe_out true] url_safe_identity_bytes)
+        )
+    )
+    "#;
+
+    let bytes: Vec<_> = (1..32).map(|i| (200 + i) as u8).collect();
+    let b64_string = base64.encode(&bytes);
+    let url_safe_string = URL_SAFE.encode(&bytes);
+    // make sure alphabets differ on these bytes
+    assert_ne!(b64_string, url_safe_string);
+    let args = hashmap! {
+        "b64_string" => json!(b64_string),
+        "bytes" => json!(bytes),
+    };
+
+    let result = exec_script(
+        script,
+        args,
+        "b64_string_out url_safe_out bytes_out identity_bytes url_safe_identity_bytes",
+        1,
+    )
+    .unwrap();
+    assert_eq!(result[0], json!(b64_string));
+    assert_eq!(result[1], json!(url_safe_string));
+    assert_eq!(result[2], json!(bytes));
+    assert_eq!(result[3], json!(bytes));
+    assert_eq!(result[4], json!(bytes));
+}
+
+#[test]
+fn hex_bytes_builtins() {
     let script = r#"
     (seq
-        (call relay ("op" "string_to_b58") [string] b58_string_out)
+        (call relay ("op" "bytes_to_hex") [bytes] hex_string_out)
         (seq
-            (call relay ("op" "string_from_b58") [b58_string] string_out)
-            (call relay ("op" "string_from_b58") [b58_string_out] identity_string)
+            (call relay ("op" "bytes_from_hex") [hex_string_upper] bytes_out)
+            (call relay ("op" "bytes_from_hex") [hex_string_out] identity_bytes)
         )
     )
     "#;
 
-    let string = "hello, this is a string! ДОБРЫЙ ВЕЧЕР КАК СЛЫШНО";
-    let b58_string = bs58::encode(string).into_string();
+    let bytes: Vec<_> = (1..32).map(|i| (200 + i) as u8).collect();
+    let hex_string: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
     let args = hashmap! {
-        "string" => json!(string),
-        "b58_string" => json!(b58_string),
+        "hex_string_upper" => json!(hex_string.to_uppercase()),
+        "bytes" => json!(bytes),
     };
 
-    let result = exec_script(script, args, "b58_string_out string_out identity_string", 1).unwrap();
-    assert_eq!(result[0], JValue::String(b58_string));
-    assert_eq!(result[1], JValue::String(string.into()));
-    assert_eq!(result[2], JValue::String(string.into()));
+    let result = exec_script(script, args, "hex_string_out bytes_out identity_bytes", 1).unwrap();
+    assert_eq!(result[0], json!(hex_string));
+    assert_eq!(result[1], json!(bytes));
+    assert_eq!(result[2], json!(bytes));
 }
 
 #[test]
-fn base58_bytes_builtins() {
+fn hex_odd_length() {
+    let script = r#"
+    (xor
+        (call relay ("op" "bytes_from_hex") [hex_string])
+        (ap %last_error%.$.message error)
+    )
+    "#;
+
+    let args = hashmap! {
+        "hex_string" => json!("abc"),
+    };
+
+    let result = exec_script(script, args, "error", 1).unwrap();
+    let error = result[0].as_str().unwrap();
+    assert!(
+        error.contains("hex string must have even length, got 3"),
+        "{error}"
+    );
+}
+
+#[test]
+fn random_bytes() {
+    let script = r#"
+    (seq
+        (call relay ("op" "random_bytes") [32] first)
+        (call relay ("op" "random_bytes") [32] second)
+    )
+    "#;
+
+    let result = exec_script(script, <_>::default(), "first second", 1).unwrap();
+    let first: Vec<u8> = serde_json::from_value(result[0].clone()).unwrap();
+    let second: Vec<u8> = serde_json::from_value(result[1].clone()).unwrap();
+    assert_eq!(first.len(), 32);
+    assert_eq!(second.len(), 32);
+    assert_ne!(first, second);
+
+    let empty = unary("op", "random_bytes", 0).unwrap();
+    assert_eq!(empty, json!([]));
+
+    let err = unary("op", "random_bytes", 1025).err().unwrap();
+    assert!(
+        format!("{err:?}").contains("must be between 0 and 1024, was 1025"),
+        "{err:?}"
+    );
+    let err = unary("op", "random_bytes", -1).err().unwrap();
+    assert!(
+        format!("{err:?}").contains("must be between 0 and 1024, was -1"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn uuid() {
     let script = r#"
     (seq
-        (call relay ("op" "bytes_to_b58") [bytes] b58_string_out)
         (seq
-            (call relay ("op" "bytes_from_b58") [b58_string] bytes_out)
-            (call relay ("op" "bytes_from_b58") [b58_string_out] identity_bytes)
+            (call relay ("op" "uuid") [] random_1)
+            (call relay ("op" "uuid") [] random_2)
+        )
+        (seq
+            (call relay ("op" "uuid") [seed] seeded_1)
+            (call relay ("op" "uuid") [seed] seeded_2)
         )
     )
     "#;
 
-    let bytes: Vec<_> = (1..32).map(|i| (200 + i) as u8).collect();
-    let b58_string = bs58::encode(&bytes).into_string();
     let args = hashmap! {
-        "b58_string" => json!(b58_string),
-        "bytes" => json!(bytes),
+        "seed" => json!([1, 2, 3, 4]),
     };
+    let result = exec_script(script, args, "random_1 random_2 seeded_1 seeded_2", 1).unwrap();
+    let uuids: Vec<&str> = result.iter().map(|v| v.as_str().unwrap()).collect();
 
-    let result = exec_script(script, args, "b58_string_out bytes_out identity_bytes", 1).unwrap();
-    // let array = into_array(result).expect("must be an array");
-    assert_eq!(result[0], json!(b58_string));
-    assert_eq!(result[1], json!(bytes));
-    assert_eq!(result[2], json!(bytes));
+    // xxxxxxxx-xxxx-Vxxx-xxxx-xxxxxxxxxxxx where V is the version
+    let check_format = |uuid: &str, version: char| {
+        let groups: Vec<_> = uuid.split('-').map(|g| g.len()).collect();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12], "{uuid}");
+        assert!(
+            uuid.chars().all(|c| c == '-' || c.is_ascii_hexdigit()),
+            "{uuid}"
+        );
+        assert_eq!(uuid.chars().nth(14), Some(version), "{uuid}");
+    };
+
+    check_format(uuids[0], '4');
+    check_format(uuids[1], '4');
+    assert_ne!(uuids[0], uuids[1]);
+
+    check_format(uuids[2], '5');
+    assert_eq!(uuids[2], uuids[3]);
 }
 
 #[test]
@@ -631,6 +1298,117 @@ fn neighborhood() {
     assert!(error.contains("Invalid multihash"));
 }
 
+#[test]
+fn closest_connected() {
+    let script = r#"
+    (seq
+        (seq
+            (call relay ("op" "string_to_b58") ["key"] key)
+            (call relay ("kad" "closest_connected") [key 5] closest)
+        )
+        (seq
+            (fold closest peer
+                (seq
+                    (call relay ("peer" "is_connected") [peer.$.peer_id] $connected)
+                    (next peer)
+                )
+            )
+            (canon relay $connected #connected)
+        )
+    )
+    "#;
+
+    let mut result = exec_script(script, <_>::default(), "closest #connected", 3).unwrap();
+    let closest = into_array(result[0].take()).expect("closest is an array");
+    let connected = into_array(result[1].take()).expect("connected is an array");
+
+    assert!(!closest.is_empty());
+    assert!(closest.len() <= 5);
+    for peer in closest {
+        let peer_id = peer["peer_id"].as_str().expect("peer_id is string");
+        PeerId::from_str(peer_id).expect("peer_id is valid");
+        let addresses = into_array(peer["addresses"].clone()).expect("addresses is an array");
+        assert!(!addresses.is_empty());
+    }
+    assert!(connected.into_iter().all(|c| c == json!(true)));
+}
+
+#[test]
+fn dht_records() {
+    let swarms = make_swarms(3);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call relay ("kad" "put_record") ["key" value 60])
+                (xor
+                    (call relay ("kad" "put_record") ["big" big])
+                    (ap %last_error%.$.message error)
+                )
+            )
+            (seq
+                (seq
+                    (call other ("kad" "get_record") ["key"] stored)
+                    (call other ("kad" "get_record") ["missing"] missing)
+                )
+                (seq
+                    (call relay ("op" "noop") [])
+                    (call client ("op" "return") [stored missing error])
+                )
+            )
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+            "other" => json!(swarms[1].peer_id.to_string()),
+            "value" => json!({ "answer": 42 }),
+            "big" => json!("x".repeat(100 * 1024)),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    assert_eq!(result[0], json!({ "answer": 42 }));
+    assert_eq!(result[1], JValue::Null);
+    let error = result[2].as_str().expect("error is string");
+    assert!(error.contains("max allowed is 65536 bytes"), "{error}");
+}
+
+#[test]
+fn provider_records() {
+    let swarms = make_swarms(3);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call provider ("kad" "start_providing") ["blueprint"])
+                (call relay ("kad" "get_providers") ["blueprint"] providers)
+            )
+            (call client ("op" "return") [providers])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+            "provider" => json!(swarms[1].peer_id.to_string()),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    let providers = into_array(result[0].clone()).expect("providers is an array");
+    assert_eq!(providers, vec![json!(swarms[1].peer_id.to_base58())]);
+}
+
 #[test]
 fn kad_merge() {
     let target = RandomPeerId::random();
@@ -785,6 +1563,119 @@ fn array_length() {
     ])
 }
 
+#[test]
+fn array_zip() {
+    let result = exec_script(
+        r#"
+        (seq
+            (seq
+                (call relay ("op" "array_zip") [abc numbers] equal)
+                (call relay ("op" "array_zip") [abc pair] truncated)
+            )
+            (seq
+                (xor
+                    (call relay ("op" "array_zip") [abc pair true])
+                    (ap %last_error%.$.message strict_error)
+                )
+                (xor
+                    (call relay ("op" "array_zip") [abc "hola"])
+                    (ap %last_error%.$.message type_error)
+                )
+            )
+        )
+        "#,
+        hashmap! {
+            "abc" => json!(["a", "b", "c"]),
+            "numbers" => json!([1, 2, 3]),
+            "pair" => json!([1, 2]),
+        },
+        "equal truncated strict_error type_error",
+        1,
+    )
+    .unwrap();
+
+    assert_eq!(result, vec![
+        json!([["a", 1], ["b", 2], ["c", 3]]),
+        json!([["a", 1], ["b", 2]]),
+        json!("Local service error, ret_code is 1, error message is '\"op array_zip arrays must have equal length in strict mode: 3 and 2 found\"'"),
+        json!("Local service error, ret_code is 1, error message is '\"both arguments of op array_zip must be arrays\"'"),
+    ])
+}
+
+#[test]
+fn array_chunk() {
+    let result = exec_script(
+        r#"
+        (seq
+            (seq
+                (call relay ("op" "array_chunk") [four 2] exact)
+                (call relay ("op" "array_chunk") [four 3] remainder)
+            )
+            (seq
+                (call relay ("op" "array_chunk") [empty 2] empty_chunks)
+                (xor
+                    (call relay ("op" "array_chunk") [four 0])
+                    (ap %last_error%.$.message size_error)
+                )
+            )
+        )
+        "#,
+        hashmap! {
+            "four" => json!([1, 2, 3, 4]),
+            "empty" => json!([]),
+        },
+        "exact remainder empty_chunks size_error",
+        1,
+    )
+    .unwrap();
+
+    assert_eq!(result, vec![
+        json!([[1, 2], [3, 4]]),
+        json!([[1, 2, 3], [4]]),
+        json!([]),
+        json!("Local service error, ret_code is 1, error message is '\"op array_chunk's size must be positive, was 0\"'"),
+    ])
+}
+
+#[test]
+fn array_pluck() {
+    let result = exec_script(
+        r#"
+        (seq
+            (seq
+                (call relay ("op" "array_pluck") [peers "id"] ids)
+                (call relay ("op" "array_pluck") [mixed "id"] skipped)
+            )
+            (seq
+                (xor
+                    (call relay ("op" "array_pluck") [peers "name" true])
+                    (ap %last_error%.$.message missing_error)
+                )
+                (xor
+                    (call relay ("op" "array_pluck") [scalars "id" true])
+                    (ap %last_error%.$.message type_error)
+                )
+            )
+        )
+        "#,
+        hashmap! {
+            "peers" => json!([{ "id": "a", "name": "first" }, { "id": "b" }]),
+            "mixed" => json!([{ "id": "a" }, { "name": "no id" }, 42, { "id": { "nested": true } }]),
+            "scalars" => json!([{ "id": "a" }, 42]),
+        },
+        "ids skipped missing_error type_error",
+        1,
+    )
+    .unwrap();
+
+    assert_eq!(result, vec![
+        json!(["a", "b"]),
+        json!(["a", { "nested": true }]),
+        json!("Local service error, ret_code is 1, error message is '\"op array_pluck: element #1 has no key 'name'\"'"),
+        json!("Local service error, ret_code is 1, error message is '\"op array_pluck: element #1 is not an object\"'"),
+    ])
+}
+
 #[test]
 fn array_slice() {
     let result = exec_script(
@@ -843,8 +1734,7 @@ fn array_slice() {
         1,
     );
     assert!(result.is_err());
-    assert!(format!("{result:?}")
-        .contains("invalid number of parameters. need array, start index and end index"));
+    assert!(format!("{result:?}").contains("expected 3 arguments, got 2"));
 
     let result = exec_script(
         r#"(call relay ("array" "slice") [ data eidx sidx ] result)"#,
@@ -1071,6 +1961,117 @@ fn math_cmp() {
     assert!(format!("{:?}", binary("math", "div", 2, 0).err().unwrap()).contains("overflow"));
 }
 
+#[test]
+fn math_gcd_lcm() {
+    assert_eq!(binary("math", "gcd", 12, 18).unwrap(), json!(6));
+    assert_eq!(binary("math", "gcd", -12, 18).unwrap(), json!(6));
+    assert_eq!(binary("math", "gcd", 7, 0).unwrap(), json!(7));
+    assert_eq!(binary("math", "gcd", 0, 0).unwrap(), json!(0));
+
+    assert_eq!(binary("math", "lcm", 4, 6).unwrap(), json!(12));
+    assert_eq!(binary("math", "lcm", -4, 6).unwrap(), json!(12));
+    assert_eq!(binary("math", "lcm", 0, 6).unwrap(), json!(0));
+    assert_eq!(binary("math", "lcm", 0, 0).unwrap(), json!(0));
+
+    // overflow
+    assert!(
+        format!("{:?}", binary("math", "gcd", i64::MIN, 0).err().unwrap()).contains("overflow")
+    );
+    assert!(format!(
+        "{:?}",
+        binary("math", "lcm", i64::MAX, i64::MAX - 1).err().unwrap()
+    )
+    .contains("overflow"));
+}
+
+#[test]
+fn cmp_eq() {
+    let eq = |x: JValue, y: JValue| {
+        let eq = binary("cmp", "eq", x.clone(), y.clone()).unwrap();
+        let neq = binary("cmp", "neq", x, y).unwrap();
+        assert_eq!(eq, json!(!neq.as_bool().unwrap()));
+        eq.as_bool().unwrap()
+    };
+
+    // strings
+    assert!(eq(json!("abc"), json!("abc")));
+    assert!(!eq(json!("abc"), json!("abd")));
+    // numbers
+    assert!(eq(json!(42), json!(42)));
+    assert!(!eq(json!(42), json!(-42)));
+    assert!(eq(json!(0.5), json!(0.5)));
+    // booleans
+    assert!(eq(json!(true), json!(true)));
+    assert!(!eq(json!(true), json!(false)));
+    // nulls
+    assert!(eq(json!(null), json!(null)));
+    assert!(!eq(json!(null), json!(0)));
+    // different types
+    assert!(!eq(json!("1"), json!(1)));
+    // nested objects, key order doesn't matter
+    assert!(eq(
+        json!({"a": 1, "b": {"c": [1, 2], "d": null}}),
+        json!({"b": {"d": null, "c": [1, 2]}, "a": 1})
+    ));
+    assert!(!eq(
+        json!({"a": 1, "b": {"c": [1, 2]}}),
+        json!({"a": 1, "b": {"c": [1, 3]}})
+    ));
+    // array order matters
+    assert!(!eq(json!([1, 2]), json!([2, 1])));
+}
+
+#[test]
+fn bits() {
+    assert_eq!(
+        binary("bits", "and", 0b1100, 0b1010).unwrap(),
+        json!(0b1000)
+    );
+    assert_eq!(binary("bits", "or", 0b1100, 0b1010).unwrap(), json!(0b1110));
+    assert_eq!(
+        binary("bits", "xor", 0b1100, 0b1010).unwrap(),
+        json!(0b0110)
+    );
+    assert_eq!(unary("bits", "not", 0).unwrap(), json!(-1));
+    assert_eq!(unary("bits", "not", -1).unwrap(), json!(0));
+
+    assert_eq!(binary("bits", "shl", 1, 4).unwrap(), json!(16));
+    assert_eq!(binary("bits", "shl", 1, 63).unwrap(), json!(i64::MIN));
+    assert_eq!(binary("bits", "shr", 16, 4).unwrap(), json!(1));
+    // shift is arithmetic, sign is preserved
+    assert_eq!(binary("bits", "shr", -16, 2).unwrap(), json!(-4));
+
+    // oversized shifts
+    let err = binary("bits", "shl", 1, 64).err().unwrap();
+    assert!(
+        format!("{err:?}").contains("can't shift by 64 bits"),
+        "{err:?}"
+    );
+    let err = binary("bits", "shr", 1, 100).err().unwrap();
+    assert!(
+        format!("{err:?}").contains("can't shift by 100 bits"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn math_clamp() {
+    // in range
+    assert_eq!(ternary("math", "clamp", 5, 0, 10).unwrap(), json!(5));
+    // below
+    assert_eq!(ternary("math", "clamp", -5, 0, 10).unwrap(), json!(0));
+    // above
+    assert_eq!(ternary("math", "clamp", 15, 0, 10).unwrap(), json!(10));
+    // bounds are inclusive
+    assert_eq!(ternary("math", "clamp", 10, 10, 10).unwrap(), json!(10));
+
+    let err = ternary("math", "clamp", 5, 10, 0).err().unwrap();
+    assert!(
+        format!("{err:?}").contains("clamp lower bound 10 is greater than upper bound 0"),
+        "{err:?}"
+    );
+}
+
 #[test]
 fn array_ops() {
     assert_eq!(unary("array", "sum", vec![1, 2, 3]).unwrap(), json!(6));
@@ -1564,6 +2565,29 @@ fn binary(
     result.map(|mut r| r[0].take())
 }
 
+fn ternary(
+    service: &str,
+    func: &str,
+    x: impl Into<JValue>,
+    y: impl Into<JValue>,
+    z: impl Into<JValue>,
+) -> Result<JValue, Report> {
+    let result = exec_script(
+        r#"(call relay (service func) [x y z] result)"#,
+        hashmap! {
+            "service" => service.into(),
+            "func" => func.into(),
+            "x" => x.into(),
+            "y" => y.into(),
+            "z" => z.into()
+        },
+        "result",
+        1,
+    );
+
+    result.map(|mut r| r[0].take())
+}
+
 fn unary(service: &str, func: &str, x: impl Into<JValue>) -> Result<JValue, Report> {
     let result = exec_script(
         r#"(call relay (service func) [x] result)"#,
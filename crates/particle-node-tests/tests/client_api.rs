@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_std::task::block_on;
 use futures::channel::oneshot::channel;
@@ -23,11 +23,13 @@ use futures::FutureExt;
 use maplit::hashmap;
 use serde_json::json;
 
+use connected_client::{ConnectedClient, RelayDisconnected};
 use created_swarm::make_swarms;
+use fluence_libp2p::RandomPeerId;
 use now_millis::now_ms;
 use particle_execution::FunctionOutcome;
 use particle_protocol::Particle;
-use test_constants::PARTICLE_TTL;
+use test_constants::{KAD_TIMEOUT, PARTICLE_TTL};
 use test_utils::timeout;
 use uuid_utils::uuid;
 
@@ -106,3 +108,72 @@ fn call_custom_service() {
 
     println!("result: {result:?}");
 }
+
+#[test]
+fn wait_particle_args_timeout() {
+    let swarms = make_swarms(1);
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone()).unwrap();
+
+    let wait = Duration::from_secs(2);
+    let start = Instant::now();
+    let err = client
+        .wait_particle_args_with_timeout("nonexistent-particle", wait)
+        .expect_err("no particle was sent, so waiting must fail");
+
+    assert!(start.elapsed() >= wait);
+    assert!(err.to_string().contains("nonexistent-particle"));
+}
+
+#[test]
+fn send_to_all() {
+    let swarms = make_swarms(3);
+    std::thread::sleep(KAD_TIMEOUT);
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone()).unwrap();
+    client.timeout = Duration::from_secs(10);
+
+    let script = r#"
+        (seq
+            (seq
+                (call relay ("op" "noop") [])
+                (call node ("op" "identity") [node] result)
+            )
+            (seq
+                (call relay ("op" "noop") [])
+                (call client ("op" "return") [result])
+            )
+        )
+    "#;
+    let data = hashmap! {
+        "client" => json!(client.peer_id.to_string()),
+        "relay" => json!(client.node.to_string()),
+    };
+    let unreachable = RandomPeerId::random();
+    let mut nodes: Vec<_> = swarms.iter().map(|s| s.peer_id).collect();
+    nodes.push(unreachable);
+
+    let results = client.send_to_all(script, data, &nodes);
+
+    assert_eq!(results.len(), nodes.len());
+    for (node, result) in nodes.iter().zip(results.iter()).take(swarms.len()) {
+        let result = result.as_ref().expect("node must respond");
+        assert_eq!(result[0], json!(node.to_string()));
+    }
+    assert!(results[swarms.len()].is_err());
+}
+
+#[test]
+fn relay_disconnected() {
+    let swarms = make_swarms(1);
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone()).unwrap();
+
+    let start = Instant::now();
+    let swarm = swarms.into_iter().next().unwrap();
+    swarm.outlet.send(()).unwrap();
+
+    let err = client
+        .receive()
+        .expect_err("relay is stopped, so no particle can be received");
+
+    assert!(err.downcast_ref::<RelayDisconnected>().is_some());
+    assert!(start.elapsed() < client.timeout());
+}
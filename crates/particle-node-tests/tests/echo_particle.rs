@@ -18,10 +18,12 @@ use connected_client::ConnectedClient;
 use created_swarm::make_swarms;
 use test_constants::KAD_TIMEOUT;
 
+use async_std::task::block_on;
 use eyre::WrapErr;
 use maplit::hashmap;
 use serde_json::json;
 use std::thread::sleep;
+use std::time::Duration;
 
 #[test]
 fn echo_particle() {
@@ -47,3 +49,70 @@ fn echo_particle() {
     let response = client.receive_args().wrap_err("receive").unwrap();
     assert_eq!(data["name"], response[0]);
 }
+
+#[test]
+fn echo_particle_concurrent_clients() {
+    let swarms = make_swarms(2);
+    sleep(KAD_TIMEOUT);
+    let mut first = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect first client")
+        .unwrap();
+    let mut second = ConnectedClient::connect_to(swarms[1].multiaddr.clone())
+        .wrap_err("connect second client")
+        .unwrap();
+
+    let script = r#"
+        (seq
+            (call relay ("op" "noop") [])
+            (call client ("return" "") [name])
+        )"#;
+    for (client, name) in [(&mut first, "first"), (&mut second, "second")] {
+        let data = hashmap! {
+            "name" => json!(name),
+            "client" => json!(client.peer_id.to_string()),
+            "relay" => json!(client.node.to_string()),
+        };
+        client.send_particle(script, data);
+    }
+
+    let (first_response, second_response) =
+        block_on(async { futures::join!(first.receive_args_async(), second.receive_args_async()) });
+    assert_eq!(first_response.unwrap()[0], json!("first"));
+    assert_eq!(second_response.unwrap()[0], json!("second"));
+}
+
+#[test]
+fn echo_particle_with_ttl() {
+    let swarms = make_swarms(1);
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+    let default_ttl = client.particle_ttl();
+
+    let script = r#"
+        (seq
+            (call relay ("op" "noop") [])
+            (call client ("return" "") [name])
+        )"#;
+    let data = hashmap! {
+        "name" => json!("folex"),
+        "client" => json!(client.peer_id.to_string()),
+        "relay" => json!(client.node.to_string()),
+    };
+
+    // particle expires before reaching the node, so it must be dropped
+    let expired_id = client.send_particle_with_ttl(script, data.clone(), Duration::from_millis(1));
+    let err = client
+        .wait_particle_args_with_timeout(&expired_id, client.short_timeout())
+        .expect_err("expired particle must not be executed");
+    assert!(
+        err.to_string()
+            .contains(&format!("timed out waiting for particle {expired_id}")),
+        "{err:?}"
+    );
+    assert_eq!(client.particle_ttl(), default_ttl);
+
+    client.send_particle_with_ttl(script, data.clone(), default_ttl * 2);
+    let response = client.receive_args().wrap_err("receive").unwrap();
+    assert_eq!(data["name"], response[0]);
+}
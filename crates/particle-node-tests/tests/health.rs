@@ -0,0 +1,164 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::sleep;
+use std::time::Duration;
+
+use eyre::WrapErr;
+use maplit::hashmap;
+use serde_json::{json, Value};
+
+use connected_client::ConnectedClient;
+use created_swarm::make_swarms_with_cfg;
+
+fn free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind to a free port");
+    listener.local_addr().unwrap().port()
+}
+
+/// Returns status code and body of `GET path`
+fn http_get(port: u16, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("connect to metrics endpoint");
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").expect("http response");
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .expect("status code");
+
+    (status, body.to_string())
+}
+
+/// Polls `path` until it returns 200, asserting it returns 503 meanwhile. Returns response body
+fn wait_for_ok(port: u16, path: &str) -> Value {
+    // endpoint and node components are started in background
+    for _ in 0..50 {
+        if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+            let (status, body) = http_get(port, path);
+            if status == 200 {
+                return serde_json::from_str(&body).unwrap();
+            }
+            assert_eq!(status, 503, "unexpected {path} status, body: {body}");
+        }
+        sleep(Duration::from_millis(100));
+    }
+
+    panic!("{path} didn't return 200 in time")
+}
+
+/// Returns value of the metric sample `name` from the Prometheus text exposition
+fn metric_value(metrics: &str, name: &str) -> f64 {
+    metrics
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("metric {name} not found in:\n{metrics}"))
+        .parse()
+        .expect("metric value")
+}
+
+#[test]
+fn health_endpoint() {
+    let metrics_port = free_port();
+    let swarms = make_swarms_with_cfg(1, |mut cfg| {
+        cfg.metrics_port = Some(metrics_port);
+        cfg
+    });
+
+    let _client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let health = wait_for_ok(metrics_port, "/health");
+    assert_eq!(health["healthy"], true);
+    assert_eq!(health["event_loop_responsive"], true);
+    assert_eq!(health["vm_pool"]["size"], 1);
+    assert_eq!(health["vm_pool"]["created"], 1);
+    // at least the client is connected
+    assert!(health["connections"].as_u64().unwrap() >= 1);
+}
+
+#[test]
+fn ready_endpoint() {
+    let mut metrics_ports = vec![];
+    let _swarms = make_swarms_with_cfg(2, |mut cfg| {
+        let port = free_port();
+        metrics_ports.push(port);
+        cfg.metrics_port = Some(port);
+        cfg
+    });
+
+    // both nodes bootstrap from each other
+    for port in metrics_ports {
+        let ready = wait_for_ok(port, "/ready");
+        assert_eq!(ready["ready"], true);
+        assert_eq!(ready["builtins_deployed"], true);
+        assert_eq!(ready["bootstrapped"], true);
+    }
+}
+
+#[test]
+fn interpretation_stats_metrics() {
+    let metrics_port = free_port();
+    let swarms = make_swarms_with_cfg(1, |mut cfg| {
+        cfg.metrics_port = Some(metrics_port);
+        cfg
+    });
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("op" "identity") ["hello"] result)
+            (call client ("op" "return") [result])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+        },
+    );
+    client.receive_args().wrap_err("receive args").unwrap();
+
+    wait_for_ok(metrics_port, "/health");
+    let (status, metrics) = http_get(metrics_port, "/metrics");
+    assert_eq!(status, 200);
+
+    let memory = metric_value(&metrics, "particle_executor_interpretation_memory_size_sum");
+    assert!(memory > 0.0, "interpreter memory size is {memory}");
+    // op.identity result is passed to the second interpretation
+    let calls = metric_value(
+        &metrics,
+        "particle_executor_interpretation_call_results_sum",
+    );
+    assert!(calls >= 1.0, "number of call results is {calls}");
+    let oom = metric_value(
+        &metrics,
+        "particle_executor_interpretation_out_of_memory_total",
+    );
+    assert_eq!(oom, 0.0);
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::convert::identity;
+use std::net::Ipv6Addr;
+
+use eyre::WrapErr;
+use libp2p::core::multiaddr::Protocol;
+use libp2p::core::Multiaddr;
+use maplit::hashmap;
+use serde_json::json;
+
+use connected_client::ConnectedClient;
+use created_swarm::{create_swarm, make_swarms_with, SwarmConfig};
+use fluence_libp2p::random_multiaddr::{create_tcp_ipv6_maddr, create_tcp_maddr};
+
+/// Checks that the client is served by the node it's connected to
+fn assert_identity(client: &mut ConnectedClient) {
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("op" "identity") ["hello"] result)
+            (call client ("op" "return") [result])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+        },
+    );
+
+    let result = client.receive_args().wrap_err("receive args").unwrap();
+    assert_eq!(result, vec![json!("hello")]);
+}
+
+#[test]
+fn ipv6_listen() {
+    let swarms = make_swarms_with(
+        1,
+        |bs, maddr| create_swarm(SwarmConfig::new(bs, maddr)),
+        create_tcp_ipv6_maddr,
+        identity,
+        true,
+    );
+    assert!(matches!(
+        swarms[0].multiaddr.iter().next(),
+        Some(Protocol::Ip6(_))
+    ));
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client over ipv6")
+        .unwrap();
+
+    assert_identity(&mut client);
+}
+
+#[test]
+fn dual_stack_listen() {
+    let swarms = make_swarms_with(
+        1,
+        |bs, maddr| {
+            create_swarm(SwarmConfig {
+                dual_stack: true,
+                ..SwarmConfig::new(bs, maddr)
+            })
+        },
+        create_tcp_maddr,
+        identity,
+        true,
+    );
+
+    let ipv4_addr = swarms[0].multiaddr.clone();
+    let port = match ipv4_addr.iter().nth(1) {
+        Some(Protocol::Tcp(port)) => port,
+        _ => panic!("expected a TCP multiaddr, got {ipv4_addr}"),
+    };
+    let ipv6_addr = Multiaddr::from(Ipv6Addr::LOCALHOST).with(Protocol::Tcp(port));
+
+    let mut ipv4_client = ConnectedClient::connect_to(ipv4_addr)
+        .wrap_err("connect client over ipv4")
+        .unwrap();
+    assert_identity(&mut ipv4_client);
+
+    let mut ipv6_client = ConnectedClient::connect_to(ipv6_addr)
+        .wrap_err("connect client over ipv6")
+        .unwrap();
+    assert_identity(&mut ipv6_client);
+}
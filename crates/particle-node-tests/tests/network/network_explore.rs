@@ -383,7 +383,7 @@ fn explore_services_fixed() {
                         .expect("no error");
                     received.push(args);
                 }
-                ClientEvent::NewConnection { .. } => {}
+                ClientEvent::NewConnection { .. } | ClientEvent::ConnectionClosed { .. } => {}
             }
         }
 
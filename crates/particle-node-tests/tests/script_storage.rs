@@ -696,3 +696,122 @@ fn add_script_from_vault_wrong_vault() {
         assert!(error_msg.starts_with(expected_error_prefix));
     }
 }
+
+#[test]
+fn update_script() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let script = f!(r#"
+        (call "{client.peer_id}" ("op" "return") ["hello"])
+    "#);
+
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("script" "add") [script "3600"] id)
+            (call client ("op" "return") [id])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+            "script" => json!(script),
+        },
+    );
+    let args = client.receive_args().wrap_err("receive args").unwrap();
+    let script_id = args.into_iter().next().unwrap();
+
+    let update_id = client.send_particle(
+        r#"
+        (seq
+            (seq
+                (call relay ("script" "update") [id "7200"] updated)
+                (call relay ("script" "update") ["unknown-id" "7200"] unknown)
+            )
+            (call client ("op" "return") [updated unknown])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+            "id" => json!(script_id),
+        },
+    );
+    let updated = client.wait_particle_args(update_id).unwrap();
+    assert_eq!(updated, vec![json!(true), json!(false)]);
+
+    let list_id = client.send_particle(
+        r#"
+        (seq
+            (call relay ("script" "list") [] list)
+            (call client ("op" "return") [list])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+        },
+    );
+    let list = client.wait_particle_args(list_id).unwrap();
+    let scripts = list[0].as_array().expect("list of scripts");
+    assert_eq!(scripts.len(), 1);
+    assert_eq!(scripts[0]["id"], script_id);
+    assert_eq!(scripts[0]["owner"], json!(client.peer_id.to_string()));
+    assert_eq!(
+        scripts[0]["interval"],
+        json!(format_duration(Duration::from_secs(7200)).to_string())
+    );
+}
+
+#[test]
+fn script_max_failures() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    // with the default of 3 max failures, the script would live for at least 2 hours
+    client.send_particle(
+        r#"
+        (call relay ("script" "add") [script "3600" "0" "1"])
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "script" => json!("INVALID SCRIPT"),
+        },
+    );
+
+    let timeout = Duration::from_secs(5);
+    let deadline = now() + timeout;
+
+    // wait for script to fail once and disappear from the list
+    while now() < deadline {
+        let list_id = client.send_particle(
+            r#"
+            (seq
+                (call relay ("script" "list") [] list)
+                (call client ("op" "return") [list])
+            )
+            "#,
+            hashmap! {
+                "relay" => json!(client.node.to_string()),
+                "client" => json!(client.peer_id.to_string()),
+            },
+        );
+        let list = client.wait_particle_args(list_id).unwrap();
+        if list == vec![JValue::Array(vec![])] {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    panic!(
+        "script wasn't deleted after a single failure in {}",
+        format_duration(timeout)
+    );
+}
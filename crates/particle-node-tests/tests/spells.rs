@@ -482,6 +482,90 @@ fn spell_install_fail_end_sec_before_start() {
     }
 }
 
+#[test]
+fn spell_install_fail_invalid_cron() {
+    let swarms = make_swarms(1);
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let script = r#"(call %init_peer_id% ("peer" "identify") [] x)"#;
+    let empty: HashMap<String, String> = HashMap::new();
+
+    let data = hashmap! {
+        "script" => json!(script.to_string()),
+        "config" => json!(TriggerConfig::default()),
+        "cron" => json!("0 0 30 2 *"),
+        "client" => json!(client.peer_id.to_string()),
+        "relay" => json!(client.node.to_string()),
+        "data" => json!(json!(empty).to_string()),
+    };
+    client.send_particle(
+        r#"
+        (xor
+            (call relay ("spell" "install") [script data config cron] spell_id)
+            (call client ("return" "") [%last_error%.$.message])
+        )"#,
+        data,
+    );
+
+    let response = client.receive_args().wrap_err("receive").unwrap();
+    let error_msg = response[0].as_str().unwrap();
+    let msg = "Local service error, ret_code is 1, error message is '\"Error: invalid config: cron expression '0 0 30 2 *' is invalid: schedule never fires";
+    assert!(error_msg.starts_with(msg), "unexpected error: {error_msg}");
+
+    // the spell must not be installed
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("spell" "list") [] list)
+            (call client ("return" "") [list])
+        )"#,
+        hashmap! {
+            "client" => json!(client.peer_id.to_string()),
+            "relay" => json!(client.node.to_string()),
+        },
+    );
+    let response = client.receive_args().wrap_err("receive").unwrap();
+    assert_eq!(response[0], json!([]));
+}
+
+#[test]
+fn spell_install_cron() {
+    let swarms = make_swarms(1);
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let script = r#"(call %init_peer_id% ("peer" "identify") [] x)"#;
+    let empty: HashMap<String, String> = HashMap::new();
+
+    let data = hashmap! {
+        "script" => json!(script.to_string()),
+        "config" => json!(TriggerConfig::default()),
+        "cron" => json!("*/5 * * * *"),
+        "client" => json!(client.peer_id.to_string()),
+        "relay" => json!(client.node.to_string()),
+        "data" => json!(json!(empty).to_string()),
+    };
+    client.send_particle(
+        r#"
+        (seq
+            (call relay ("spell" "install") [script data config cron] spell_id)
+            (seq
+                (call relay (spell_id "get_string") ["trigger_cron"] cron)
+                (call client ("return" "") [cron])
+            )
+        )"#,
+        data,
+    );
+
+    // cron expression is kept by the spell to reschedule it after restart
+    let response = client.receive_args().wrap_err("receive").unwrap();
+    assert!(response[0]["success"].as_bool().unwrap());
+    assert_eq!(response[0]["str"], json!("*/5 * * * *"));
+}
+
 #[test]
 fn spell_store_trigger_config() {
     let swarms = make_swarms(1);
@@ -579,6 +663,66 @@ fn spell_remove() {
     }
 }
 
+#[test]
+fn spell_list_detailed() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let script = r#"(call %init_peer_id% ("peer" "identify") [] x)"#;
+    let mut config = TriggerConfig::default();
+    config.clock.period_sec = 2;
+    config.clock.start_sec = 1;
+    let (spell_id, _) = create_spell(&mut client, script, config.clone(), hashmap! {});
+
+    let list_spells = |client: &mut ConnectedClient| -> Vec<JValue> {
+        let data = hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "client" => json!(client.peer_id.to_string()),
+        };
+        client.send_particle(
+            r#"
+            (seq
+                (call relay ("spell" "list_detailed") [] list)
+                (call client ("return" "") [list])
+            )"#,
+            data,
+        );
+        let mut response = client.receive_args().wrap_err("receive").unwrap();
+        match response.pop() {
+            Some(JValue::Array(spells)) => spells,
+            other => panic!("expected array of spells, got {other:?}"),
+        }
+    };
+
+    let spells = list_spells(&mut client);
+    assert_eq!(spells.len(), 1);
+    assert_eq!(spells[0]["spell_id"], json!(spell_id));
+    assert_eq!(spells[0]["script"], json!(script));
+    let listed_config: TriggerConfig =
+        serde_json::from_value(spells[0]["trigger_config"].clone()).unwrap();
+    assert_eq!(listed_config, config);
+
+    // other peers don't see the spell
+    let mut other = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect other client")
+        .unwrap();
+    assert!(list_spells(&mut other).is_empty());
+
+    // management peer sees all spells
+    let mut manager = ConnectedClient::connect_with_keypair(
+        swarms[0].multiaddr.clone(),
+        Some(swarms[0].management_keypair.clone()),
+    )
+    .wrap_err("connect manager")
+    .unwrap();
+    let spells = list_spells(&mut manager);
+    assert_eq!(spells.len(), 1);
+    assert_eq!(spells[0]["spell_id"], json!(spell_id));
+}
+
 #[test]
 fn spell_remove_spell_as_service() {
     let swarms = make_swarms(1);
@@ -25,6 +25,7 @@ mod network {
     pub use join::join_stream;
 
     mod join;
+    mod listen;
     mod loop_topology;
     mod network_explore;
 }
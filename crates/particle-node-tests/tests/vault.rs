@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
 use base64::{engine::general_purpose::STANDARD as base64, Engine};
 use eyre::WrapErr;
 use maplit::hashmap;
@@ -214,3 +216,72 @@ fn load_blueprint_from_vault() {
         panic!("#incorrect args: expected a single string, got {:?}", args);
     }
 }
+
+#[test]
+fn read_vault_from_test() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let file_share = create_file_share(&mut client);
+
+    let particle_id = client.send_particle(
+        r#"
+        (seq
+            (call relay (file_share "create_vault_file") [input_content] filename)
+            (call %init_peer_id% ("op" "return") [filename])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "file_share" => json!(file_share.id),
+            "input_content" => json!("Hello!")
+        },
+    );
+
+    let filename = client.receive_args().unwrap();
+    let filename = filename[0].as_str().expect("filename must be a string");
+
+    let path = swarms[0].particle_vault_dir(&particle_id).join(filename);
+    let contents = std::fs::read_to_string(path).expect("read vault file");
+    assert_eq!(contents, "Hello!");
+}
+
+#[test]
+fn vault_removed_after_expiration() {
+    let swarms = make_swarms(1);
+
+    let mut client = ConnectedClient::connect_to(swarms[0].multiaddr.clone())
+        .wrap_err("connect client")
+        .unwrap();
+
+    let file_share = create_file_share(&mut client);
+
+    let ttl = Duration::from_secs(5);
+    let particle_id = client.send_particle_with_ttl(
+        r#"
+        (seq
+            (call relay (file_share "create_vault_file") [input_content] filename)
+            (call %init_peer_id% ("op" "return") [filename])
+        )
+        "#,
+        hashmap! {
+            "relay" => json!(client.node.to_string()),
+            "file_share" => json!(file_share.id),
+            "input_content" => json!("Hello!")
+        },
+        ttl,
+    );
+    client.receive_args().unwrap();
+    let vault = swarms[0].particle_vault_dir(&particle_id);
+    assert!(vault.is_dir());
+
+    std::thread::sleep(ttl);
+    // expired particles are reaped when aquamarine processes particles
+    client.send_particle(r#"(call %init_peer_id% ("op" "return") [])"#, hashmap! {});
+    client.receive_args().unwrap();
+
+    assert!(!vault.exists(), "vault {vault:?} must be removed");
+}
@@ -3,16 +3,20 @@ use prometheus_client::metrics::gauge::Gauge;
 use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
 
+use crate::ParticleStreamMetrics;
+
 #[derive(Clone)]
 pub struct ConnectionPoolMetrics {
     pub received_particles: Counter,
     pub particle_sizes: Histogram,
     pub connected_peers: Gauge,
     pub particle_queue_size: Gauge,
+    pub particle_stream: ParticleStreamMetrics,
 }
 
 impl ConnectionPoolMetrics {
     pub fn new(registry: &mut Registry) -> Self {
+        let particle_stream = ParticleStreamMetrics::new(registry);
         let sub_registry = registry.sub_registry_with_prefix("connection_pool");
 
         let received_particles = Counter::default();
@@ -49,6 +53,7 @@ impl ConnectionPoolMetrics {
             particle_sizes,
             connected_peers,
             particle_queue_size,
+            particle_stream,
         }
     }
 }
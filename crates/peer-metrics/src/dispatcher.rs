@@ -4,6 +4,12 @@ use prometheus_client::registry::Registry;
 #[derive(Clone)]
 pub struct DispatcherMetrics {
     pub expired_particles: Counter,
+    pub rate_limited_particles: Counter,
+    pub rate_limit_delayed_particles: Counter,
+    pub invalid_signature_particles: Counter,
+    pub ttl_clamped_particles: Counter,
+    pub ttl_rejected_particles: Counter,
+    pub duplicate_particles: Counter,
 }
 
 impl DispatcherMetrics {
@@ -30,6 +36,56 @@ impl DispatcherMetrics {
             Box::new(expired_particles.clone()),
         );
 
-        DispatcherMetrics { expired_particles }
+        let rate_limited_particles = Counter::default();
+        sub_registry.register(
+            "particles_rate_limited",
+            "Number of particles dropped because their init peer exceeded the rate limit, and they would expire before being processed",
+            Box::new(rate_limited_particles.clone()),
+        );
+
+        let rate_limit_delayed_particles = Counter::default();
+        sub_registry.register(
+            "particles_rate_limit_delayed",
+            "Number of particles delayed because their init peer exceeded the rate limit",
+            Box::new(rate_limit_delayed_particles.clone()),
+        );
+
+        let invalid_signature_particles = Counter::default();
+        sub_registry.register(
+            "particles_signature_invalid",
+            "Number of particles dropped because of missing or invalid signature",
+            Box::new(invalid_signature_particles.clone()),
+        );
+
+        let ttl_clamped_particles = Counter::default();
+        sub_registry.register(
+            "particles_ttl_clamped",
+            "Number of particles with TTL clamped to the node's maximum",
+            Box::new(ttl_clamped_particles.clone()),
+        );
+
+        let ttl_rejected_particles = Counter::default();
+        sub_registry.register(
+            "particles_ttl_rejected",
+            "Number of particles dropped because their TTL exceeded the node's maximum",
+            Box::new(ttl_rejected_particles.clone()),
+        );
+
+        let duplicate_particles = Counter::default();
+        sub_registry.register(
+            "particles_duplicate",
+            "Number of particles dropped because the same particle was received recently",
+            Box::new(duplicate_particles.clone()),
+        );
+
+        DispatcherMetrics {
+            expired_particles,
+            rate_limited_particles,
+            rate_limit_delayed_particles,
+            invalid_signature_particles,
+            ttl_clamped_particles,
+            ttl_rejected_particles,
+            duplicate_particles,
+        }
     }
 }
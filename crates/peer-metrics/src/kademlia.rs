@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::registry::Registry;
+
+use crate::{execution_time_buckets, register};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KademliaQueryOutcome {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+#[derive(Clone)]
+pub struct KademliaMetrics {
+    pub query_started: Counter,
+    pub query_succeeded: Counter,
+    pub query_failed: Counter,
+    pub query_timed_out: Counter,
+    pub query_duration_sec: Histogram,
+}
+
+impl KademliaMetrics {
+    pub fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("kademlia");
+
+        let query_started = register(
+            sub_registry,
+            Counter::default(),
+            "query_started",
+            "Number of started Kademlia queries",
+        );
+
+        let query_succeeded = register(
+            sub_registry,
+            Counter::default(),
+            "query_succeeded",
+            "Number of successfully finished Kademlia queries",
+        );
+
+        let query_failed = register(
+            sub_registry,
+            Counter::default(),
+            "query_failed",
+            "Number of failed Kademlia queries",
+        );
+
+        let query_timed_out = register(
+            sub_registry,
+            Counter::default(),
+            "query_timed_out",
+            "Number of timed out Kademlia queries",
+        );
+
+        let query_duration_sec = register(
+            sub_registry,
+            Histogram::new(execution_time_buckets()),
+            "query_duration_sec",
+            "Duration of finished Kademlia queries",
+        );
+
+        Self {
+            query_started,
+            query_succeeded,
+            query_failed,
+            query_timed_out,
+            query_duration_sec,
+        }
+    }
+
+    pub fn query_finished(&self, outcome: KademliaQueryOutcome, duration: Option<Duration>) {
+        match outcome {
+            KademliaQueryOutcome::Succeeded => self.query_succeeded.inc(),
+            KademliaQueryOutcome::Failed => self.query_failed.inc(),
+            KademliaQueryOutcome::TimedOut => self.query_timed_out.inc(),
+        };
+        if let Some(duration) = duration {
+            self.query_duration_sec.observe(duration.as_secs_f64());
+        }
+    }
+}
@@ -1,8 +1,10 @@
 mod connection_pool;
 mod connectivity;
 mod dispatcher;
+mod kademlia;
 mod network_protocol;
 mod particle_executor;
+mod particle_stream;
 mod services_metrics;
 mod vm_pool;
 
@@ -10,12 +12,14 @@ pub use connection_pool::ConnectionPoolMetrics;
 pub use connectivity::ConnectivityMetrics;
 pub use connectivity::Resolution;
 pub use dispatcher::DispatcherMetrics;
+pub use kademlia::{KademliaMetrics, KademliaQueryOutcome};
 pub use particle_executor::{FunctionKind, ParticleExecutorMetrics};
+pub use particle_stream::ParticleStreamMetrics;
 use prometheus_client::encoding::text::SendSyncEncodeMetric;
 use prometheus_client::registry::Registry;
 pub use services_metrics::{
-    ServiceCallStats, ServiceMemoryStat, ServiceType, ServicesMetrics, ServicesMetricsBackend,
-    ServicesMetricsBuiltin, ServicesMetricsExternal,
+    ServiceCallStats, ServiceMemoryStat, ServiceType, ServiceTypeLabel, ServicesMetrics,
+    ServicesMetricsBackend, ServicesMetricsBuiltin, ServicesMetricsExternal,
 };
 pub use vm_pool::VmPoolMetrics;
 
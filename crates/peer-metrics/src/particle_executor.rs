@@ -2,11 +2,11 @@ use prometheus_client::encoding::text::Encode;
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
 use prometheus_client::metrics::gauge::Gauge;
-use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
 use std::time::Duration;
 
-use crate::execution_time_buckets;
+use crate::{execution_time_buckets, mem_buckets};
 
 #[derive(Copy, Clone, Debug, Encode, Hash, Eq, PartialEq)]
 pub enum FunctionKind {
@@ -26,6 +26,9 @@ pub struct ParticleExecutorMetrics {
     pub interpretation_time_sec: Histogram,
     pub interpretation_successes: Counter,
     pub interpretation_failures: Counter,
+    pub interpretation_memory_size: Histogram,
+    pub interpretation_call_results: Histogram,
+    pub interpretation_out_of_memory: Counter,
     pub total_actors_mailbox: Gauge,
     pub alive_actors: Gauge,
     service_call_time_sec: Family<FunctionKindLabel, Histogram>,
@@ -58,6 +61,28 @@ impl ParticleExecutorMetrics {
             Box::new(interpretation_failures.clone()),
         );
 
+        let interpretation_memory_size = Histogram::new(mem_buckets());
+        sub_registry.register(
+            "interpretation_memory_size",
+            "Distribution of interpreter memory size after an interpretation",
+            Box::new(interpretation_memory_size.clone()),
+        );
+
+        // 1, 2, 4, ..., 512
+        let interpretation_call_results = Histogram::new(exponential_buckets(1.0, 2.0, 10));
+        sub_registry.register(
+            "interpretation_call_results",
+            "Distribution of the number of call results passed to a single interpretation",
+            Box::new(interpretation_call_results.clone()),
+        );
+
+        let interpretation_out_of_memory = Counter::default();
+        sub_registry.register(
+            "interpretation_out_of_memory",
+            "Number of interpretations failed after the interpreter reached its max heap size",
+            Box::new(interpretation_out_of_memory.clone()),
+        );
+
         let total_actors_mailbox = Gauge::default();
         sub_registry.register(
             "total_actors_mailbox",
@@ -95,6 +120,9 @@ impl ParticleExecutorMetrics {
             interpretation_time_sec,
             interpretation_successes,
             interpretation_failures,
+            interpretation_memory_size,
+            interpretation_call_results,
+            interpretation_out_of_memory,
             total_actors_mailbox,
             alive_actors,
             service_call_time_sec,
@@ -0,0 +1,33 @@
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::registry::Registry;
+
+/// Metrics of the back-pressured stream of particles from connection pool to dispatcher
+#[derive(Clone)]
+pub struct ParticleStreamMetrics {
+    /// Incremented by the connection pool, decremented by the dispatcher
+    pub depth: Gauge,
+    pub stalls: Counter,
+}
+
+impl ParticleStreamMetrics {
+    pub fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("particle_stream");
+
+        let depth = Gauge::default();
+        sub_registry.register(
+            "depth",
+            "Number of particles sent to the particle stream, but not yet taken by the dispatcher",
+            Box::new(depth.clone()),
+        );
+
+        let stalls = Counter::default();
+        sub_registry.register(
+            "stalls",
+            "Number of times connection pool had to wait because the particle stream was full",
+            Box::new(stalls.clone()),
+        );
+
+        Self { depth, stalls }
+    }
+}
@@ -17,6 +17,8 @@ use crate::{execution_time_buckets, mem_buckets_4gib, mem_buckets_8gib, register
 pub enum ServiceType {
     Builtin,
     Service(Option<String>),
+    /// Custom service registered in builtins, labeled by its service id
+    Custom(String),
 }
 
 impl Encode for ServiceType {
@@ -25,6 +27,7 @@ impl Encode for ServiceType {
             ServiceType::Builtin => "builtin",
             ServiceType::Service(Some(x)) => x,
             ServiceType::Service(_) => "service",
+            ServiceType::Custom(service_id) => service_id,
         };
 
         writer.write_all(label.as_bytes())?;
@@ -72,10 +75,14 @@ pub struct ServicesMetricsExternal {
     pub creation_time_msec: Histogram,
     /// How long it took to remove a service
     pub removal_time_msec: Histogram,
+    /// How long it took to restart a service
+    pub restart_time_msec: Histogram,
     /// Number of (srv create) calls
     pub creation_count: Counter,
     /// Number of (srv remove) calls
     pub removal_count: Counter,
+    /// Number of (srv restart) calls
+    pub restart_count: Counter,
 
     /// Number of (srv create) failures
     pub creation_failure_count: Counter,
@@ -117,6 +124,13 @@ impl ServicesMetricsExternal {
             "how long it took to remove a service",
         );
 
+        let restart_time_msec = register(
+            sub_registry,
+            Histogram::new(execution_time_buckets()),
+            "restart_time_msec",
+            "how long it took to restart a service",
+        );
+
         let creation_count = register(
             sub_registry,
             Counter::default(),
@@ -131,6 +145,13 @@ impl ServicesMetricsExternal {
             "number of srv remove calls",
         );
 
+        let restart_count = register(
+            sub_registry,
+            Counter::default(),
+            "restart_count",
+            "number of srv restart calls",
+        );
+
         let mem_max_bytes = register(
             sub_registry,
             Histogram::new(mem_buckets_8gib()),
@@ -211,8 +232,10 @@ impl ServicesMetricsExternal {
             services_count,
             creation_time_msec,
             removal_time_msec,
+            restart_time_msec,
             creation_count,
             removal_count,
+            restart_count,
             creation_failure_count,
             modules_in_services_count,
             call_time_msec,
@@ -235,6 +258,13 @@ impl ServicesMetricsExternal {
         self.removal_time_msec.observe(removal_time);
     }
 
+    /// Collect all metrics that are relevant on service restart.
+    /// Number of running services doesn't change.
+    pub fn observe_restarted(&self, restart_time: f64) {
+        self.restart_count.inc();
+        self.restart_time_msec.observe(restart_time);
+    }
+
     pub fn observe_created(&self, modules_num: f64, creation_time: f64) {
         self.services_count.inc();
         self.modules_in_services_count.observe(modules_num);
@@ -16,7 +16,7 @@ use fluence_libp2p::types::Outlet;
 pub use crate::services_metrics::backend::ServicesMetricsBackend;
 pub use crate::services_metrics::builtin::ServicesMetricsBuiltin;
 pub use crate::services_metrics::external::ServiceType;
-use crate::services_metrics::external::ServiceTypeLabel;
+pub use crate::services_metrics::external::ServiceTypeLabel;
 pub use crate::services_metrics::external::ServicesMetricsExternal;
 pub use crate::services_metrics::message::{ServiceCallStats, ServiceMemoryStat};
 
@@ -76,10 +76,16 @@ impl ServicesMetrics {
     }
 
     pub fn observe_builtins(&self, is_ok: bool, call_time: f64) {
+        self.observe_call(ServiceType::Builtin, is_ok, call_time);
+    }
+
+    pub fn observe_custom_service(&self, service_id: String, is_ok: bool, call_time: f64) {
+        self.observe_call(ServiceType::Custom(service_id), is_ok, call_time);
+    }
+
+    fn observe_call(&self, service_type: ServiceType, is_ok: bool, call_time: f64) {
         self.observe_external(|external| {
-            let label = ServiceTypeLabel {
-                service_type: ServiceType::Builtin,
-            };
+            let label = ServiceTypeLabel { service_type };
             external
                 .call_time_msec
                 .get_or_create(&label)
@@ -158,6 +164,20 @@ impl ServicesMetrics {
         });
     }
 
+    /// Collect all metrics that are relevant on service restart.
+    pub fn observe_restarted(
+        &self,
+        service_id: String,
+        service_type: ServiceType,
+        stats: ServiceMemoryStat,
+        restart_time: f64,
+    ) {
+        self.observe_external(|external| {
+            external.observe_restarted(restart_time);
+            self.observe_service_mem(service_id, service_type, stats);
+        });
+    }
+
     pub fn observe_removed(&self, removal_time: f64) {
         self.observe_external(|external| {
             external.observe_removed(removal_time);
@@ -48,16 +48,24 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .value_name("PORT")
             .default_value("18080")
             .help("open metrics port"),
-        Arg::new(EXTERNAL_ADDR)
+        Arg::new(LISTEN_IP)
             .display_order(4)
             .help_heading(Some("Networking"))
             .takes_value(true)
+            .short('i')
+            .long("listen-ip")
+            .value_name("IP")
+            .help("local IP address to bind tcp and websocket listeners to [default: 0.0.0.0]"),
+        Arg::new(EXTERNAL_ADDR)
+            .display_order(5)
+            .help_heading(Some("Networking"))
+            .takes_value(true)
             .short('x')
             .long("external-ip")
             .value_name("IP")
             .help("node external IP address to advertise to other peers"),
         Arg::new(EXTERNAL_MULTIADDRS)
-            .display_order(5)
+            .display_order(6)
             .help_heading(Some("Networking"))
             .takes_value(true)
             .multiple_values(true)
@@ -66,14 +74,14 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .value_name("MULTIADDR")
             .help("external multiaddresses to advertize"),
         Arg::new(ALLOW_PRIVATE_IPS)
-            .display_order(6)
+            .display_order(7)
             .help_heading(Some("Networking"))
             .short('a')
             .long("allow-private-ips")
             .takes_value(false)
             .help("allow private IP addresses from other nodes"),
         Arg::new(BOOTSTRAP_NODE)
-            .display_order(7)
+            .display_order(8)
             .help_heading(Some("Networking"))
             .value_name("MULTIADDR")
             .takes_value(true)
@@ -82,7 +90,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .multiple_values(true)
             .help("bootstrap nodes of the Fluence network"),
         Arg::new(BOOTSTRAP_FREQ)
-            .display_order(8)
+            .display_order(9)
             .help_heading(Some("Networking"))
             .value_name("N")
             .takes_value(true)
@@ -90,7 +98,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .long("bootstrap-freq")
             .help("bootstrap kademlia each time N bootstraps (re)connect"),
         Arg::new(LOCAL)
-            .display_order(9)
+            .display_order(10)
             .help_heading(Some("Networking"))
             .short('l')
             .long("local")
@@ -99,7 +107,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .help("if passed, bootstrap nodes aren't used"),
         // keypair
         Arg::new(ROOT_KEY_PAIR_VALUE)
-            .display_order(10)
+            .display_order(11)
             .help_heading(Some("Node keypair"))
             .takes_value(true)
             .short('k')
@@ -109,7 +117,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .conflicts_with(ROOT_KEY_PAIR_PATH)
             .conflicts_with(SECRET_KEY),
         Arg::new(ROOT_KEY_PAIR_PATH)
-            .display_order(11)
+            .display_order(12)
             .help_heading(Some("Node keypair"))
             .takes_value(true)
             .short('p')
@@ -118,14 +126,14 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .conflicts_with(ROOT_KEY_PAIR_VALUE)
             .conflicts_with(SECRET_KEY),
         Arg::new(ROOT_KEY_FORMAT)
-            .display_order(12)
+            .display_order(13)
             .help_heading(Some("Node keypair"))
             .takes_value(true)
             .short('f')
             .long("keypair-format")
             .possible_values(["ed25519", "secp256k1", "rsa"]),
         Arg::new(ROOT_KEY_PAIR_GENERATE)
-            .display_order(13)
+            .display_order(14)
             .help_heading(Some("Node keypair"))
             .takes_value(true)
             .short('g')
@@ -134,7 +142,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .possible_values(["true", "false"])
             .help("generate keypair on absence"),
         Arg::new(SECRET_KEY)
-            .display_order(14)
+            .display_order(15)
             .takes_value(true)
             .help_heading(Some("Node keypair"))
             .short('y')
@@ -144,7 +152,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .help("Node secret key in base64 (usually 32 bytes)"),
         // node configuration
         Arg::new(CONFIG_FILE)
-            .display_order(15)
+            .display_order(16)
             .help_heading(Some("Node configuration"))
             .takes_value(true)
             .short('c')
@@ -152,7 +160,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .value_name("PATH")
             .help("TOML configuration file"),
         Arg::new(CERTIFICATE_DIR)
-            .display_order(16)
+            .display_order(17)
             .help_heading(Some("Node configuration"))
             .takes_value(true)
             .short('d')
@@ -160,16 +168,31 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .value_name("PATH")
             .help("certificate dir"),
         Arg::new(MANAGEMENT_PEER_ID)
-            .display_order(17)
+            .display_order(18)
             .help_heading(Some("Node configuration"))
             .takes_value(true)
             .long("management-key")
             .short('m')
             .value_name("PEER ID")
             .help("PeerId of the node's administrator"),
+        Arg::new(DRY_RUN_CONFIG)
+            .display_order(19)
+            .help_heading(Some("Node configuration"))
+            .long("dry-run-config")
+            .takes_value(false)
+            .help("validate configuration, print its summary and exit without starting the node"),
+        Arg::new(LOG_FORMAT)
+            .display_order(20)
+            .help_heading(Some("Node configuration"))
+            .takes_value(true)
+            .long("log-format")
+            .value_name("FORMAT")
+            .possible_values(["default", "json"])
+            .default_value("default")
+            .help("log output format: human-readable or JSON lines"),
         // services
         Arg::new(SERVICE_ENVS)
-            .display_order(18)
+            .display_order(21)
             .help_heading(Some("Services configuration"))
             .value_name("NAME=VALUE")
             .takes_value(true)
@@ -178,7 +201,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .multiple_values(true)
             .help("envs to pass to core modules"),
         Arg::new(BLUEPRINT_DIR)
-            .display_order(19)
+            .display_order(22)
             .help_heading(Some("Services configuration"))
             .takes_value(true)
             .short('u')
@@ -186,7 +209,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .value_name("PATH")
             .help("directory containing blueprints and wasm modules"),
         Arg::new(SERVICES_WORKDIR)
-            .display_order(20)
+            .display_order(23)
             .help_heading(Some("Services configuration"))
             .takes_value(true)
             .short('r')
@@ -195,7 +218,7 @@ pub fn create_args<'help>() -> Vec<Arg<'help>> {
             .help("directory where all services will store their data"),
         // AIR
         Arg::new(AQUA_VM_POOL_SIZE)
-            .display_order(21)
+            .display_order(24)
             .help_heading(Some("AIR configuration"))
             .takes_value(true)
             .long("aqua-pool-size")
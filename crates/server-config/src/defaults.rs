@@ -159,6 +159,10 @@ pub fn default_execution_timeout() -> Duration {
     Duration::from_secs(20)
 }
 
+pub fn default_shutdown_grace_period() -> Duration {
+    Duration::from_secs(10)
+}
+
 pub fn default_autodeploy_retry_attempts() -> u16 {
     5
 }
@@ -189,6 +193,38 @@ pub fn default_module_max_heap_size() -> bytesize::ByteSize {
     bytesize::ByteSize::b(bytesize::gib(4_u64) - 1)
 }
 
+pub fn default_max_call_depth() -> usize {
+    16
+}
+
+pub fn default_max_script_size() -> bytesize::ByteSize {
+    bytesize::ByteSize::mib(1)
+}
+
+pub fn default_module_upload_max_size() -> bytesize::ByteSize {
+    bytesize::ByteSize::mib(32)
+}
+
+pub fn default_module_uploads() -> usize {
+    8
+}
+
+pub fn default_module_uploads_per_peer() -> usize {
+    2
+}
+
+pub fn default_module_upload_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
 pub fn default_max_builtin_metrics_storage_size() -> usize {
     5
 }
+
+pub fn default_particle_dedup_capacity() -> usize {
+    10_000
+}
+
+pub fn default_particle_dedup_window() -> Duration {
+    Duration::from_secs(60)
+}
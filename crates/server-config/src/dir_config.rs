@@ -16,11 +16,12 @@
 use crate::defaults::{avm_base_dir, builtins_base_dir, default_base_dir, services_base_dir};
 
 use air_interpreter_fs::air_interpreter_path;
+use config_utils::particles_vault_dir;
 use fs_utils::{canonicalize, create_dirs, to_abs_path};
 
-use eyre::WrapErr;
+use eyre::{eyre, WrapErr};
 use serde::Deserialize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct UnresolvedDirConfig {
@@ -40,6 +41,10 @@ pub struct UnresolvedDirConfig {
     #[serde(default)]
     pub avm_base_dir: Option<PathBuf>,
 
+    /// Deprecated name of `avm_base_dir`, used only if `avm_base_dir` isn't set
+    #[serde(default)]
+    pub stepper_base_dir: Option<PathBuf>,
+
     /// Path to AIR interpreter .wasm file (aquamarine.wasm)
     #[serde(default)]
     pub air_interpreter_path: Option<PathBuf>,
@@ -54,31 +59,44 @@ pub struct UnresolvedDirConfig {
 
 impl UnresolvedDirConfig {
     pub fn resolve(self) -> eyre::Result<ResolvedDirConfig> {
-        let base = to_abs_path(self.base_dir);
+        let this = self.expand_env_vars()?;
+        let base = to_abs_path(this.base_dir);
 
-        let services_base_dir = self.services_base_dir.unwrap_or(services_base_dir(&base));
-        let builtins_base_dir = self.builtins_base_dir.unwrap_or(builtins_base_dir(&base));
-        let avm_base_dir = self.avm_base_dir.unwrap_or(avm_base_dir(&base));
-        let air_interpreter_path = self
+        let services_base_dir = this.services_base_dir.unwrap_or(services_base_dir(&base));
+        let builtins_base_dir = this.builtins_base_dir.unwrap_or(builtins_base_dir(&base));
+        if this.stepper_base_dir.is_some() {
+            log::warn!("stepper_base_dir is deprecated, use avm_base_dir instead");
+        }
+        let avm_base_dir = this
+            .avm_base_dir
+            .or(this.stepper_base_dir)
+            .unwrap_or(avm_base_dir(&base));
+        let air_interpreter_path = this
             .air_interpreter_path
             .unwrap_or(air_interpreter_path(&base));
-        let spell_base_dir = self.spell_base_dir.unwrap_or(base.join("spell"));
-        let keypairs_base_dir = self.keypairs_base_dir.unwrap_or(base.join("keypairs"));
+        let spell_base_dir = this.spell_base_dir.unwrap_or(base.join("spell"));
+        let keypairs_base_dir = this.keypairs_base_dir.unwrap_or(base.join("keypairs"));
+        let particles_vault_dir = particles_vault_dir(&avm_base_dir);
 
-        create_dirs(&[
+        let dirs = [
             &base,
             &services_base_dir,
             &avm_base_dir,
+            &particles_vault_dir,
             &builtins_base_dir,
             &spell_base_dir,
             &keypairs_base_dir,
-        ])
-        .context("creating configured directories")?;
+        ];
+        create_dirs(&dirs).context("creating configured directories")?;
+        for dir in dirs {
+            check_writable(dir)?;
+        }
 
         let base = canonicalize(base)?;
         let services_base_dir = canonicalize(services_base_dir)?;
         let builtins_base_dir = canonicalize(builtins_base_dir)?;
         let avm_base_dir = canonicalize(avm_base_dir)?;
+        let particles_vault_dir = canonicalize(particles_vault_dir)?;
         let spell_base_dir = canonicalize(spell_base_dir)?;
         let keypairs_base_dir = canonicalize(keypairs_base_dir)?;
 
@@ -87,11 +105,83 @@ impl UnresolvedDirConfig {
             services_base_dir,
             builtins_base_dir,
             avm_base_dir,
+            particles_vault_dir,
             air_interpreter_path,
             spell_base_dir,
             keypairs_base_dir,
         })
     }
+
+    /// Expand `$VAR` and `${VAR}` in all configured paths
+    fn expand_env_vars(self) -> eyre::Result<Self> {
+        let expand = |path: Option<PathBuf>| path.map(expand_env_vars).transpose();
+
+        Ok(Self {
+            base_dir: expand_env_vars(self.base_dir)?,
+            services_base_dir: expand(self.services_base_dir)?,
+            builtins_base_dir: expand(self.builtins_base_dir)?,
+            avm_base_dir: expand(self.avm_base_dir)?,
+            stepper_base_dir: expand(self.stepper_base_dir)?,
+            air_interpreter_path: expand(self.air_interpreter_path)?,
+            spell_base_dir: expand(self.spell_base_dir)?,
+            keypairs_base_dir: expand(self.keypairs_base_dir)?,
+        })
+    }
+}
+
+/// Substitute environment variables referenced as `$VAR` or `${VAR}` in `path`.
+/// Fails if a referenced variable is not defined.
+fn expand_env_vars(path: PathBuf) -> eyre::Result<PathBuf> {
+    let original = match path.to_str() {
+        Some(str) if str.contains('$') => str,
+        _ => return Ok(path),
+    };
+
+    let mut expanded = String::with_capacity(original.len());
+    let mut rest = original;
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        let (name, tail) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| eyre!("unclosed '${{' in path {:?}", original))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+
+        if name.is_empty() {
+            // not a variable reference, keep '$' as is
+            expanded.push('$');
+            continue;
+        }
+
+        let value = std::env::var(name).map_err(|_| {
+            eyre!(
+                "environment variable {} used in path {:?} is not defined",
+                name,
+                original
+            )
+        })?;
+        expanded.push_str(&value);
+        rest = tail;
+    }
+    expanded.push_str(rest);
+
+    Ok(PathBuf::from(expanded))
+}
+
+/// Fail early if `dir` is not writable, e.g. because of a read-only volume mount
+fn check_writable(dir: &Path) -> eyre::Result<()> {
+    let probe = dir.join(".write_probe");
+    std::fs::write(&probe, [])
+        .and_then(|_| std::fs::remove_file(&probe))
+        .map_err(|err| eyre!("directory {:?} is not writable: {}", dir, err))
 }
 
 #[derive(Clone, Debug)]
@@ -102,8 +192,161 @@ pub struct ResolvedDirConfig {
     pub builtins_base_dir: PathBuf,
     /// Directory where particle's prev_data is stored
     pub avm_base_dir: PathBuf,
+    /// Directory where particle's vaults are stored
+    pub particles_vault_dir: PathBuf,
     /// Directory where interpreter's WASM module is stored
     pub air_interpreter_path: PathBuf,
     pub spell_base_dir: PathBuf,
     pub keypairs_base_dir: PathBuf,
 }
+
+#[cfg(test)]
+mod tests {
+    use fs_utils::make_tmp_dir;
+
+    use super::*;
+
+    fn unresolved(base_dir: PathBuf) -> UnresolvedDirConfig {
+        UnresolvedDirConfig {
+            base_dir,
+            services_base_dir: None,
+            builtins_base_dir: None,
+            avm_base_dir: None,
+            stepper_base_dir: None,
+            air_interpreter_path: None,
+            spell_base_dir: None,
+            keypairs_base_dir: None,
+        }
+    }
+
+    #[test]
+    fn derived_dirs() {
+        let base_dir = make_tmp_dir();
+        let resolved = unresolved(base_dir.clone())
+            .resolve()
+            .expect("resolve dir config");
+
+        let base_dir = base_dir.canonicalize().unwrap();
+        assert_eq!(resolved.avm_base_dir, avm_base_dir(&base_dir));
+        assert_eq!(
+            resolved.particles_vault_dir,
+            particles_vault_dir(&avm_base_dir(&base_dir))
+        );
+        assert!(resolved.particles_vault_dir.is_dir());
+    }
+
+    #[test]
+    fn all_dirs_created() {
+        let base_dir = make_tmp_dir();
+        // resolving twice must not fail on already existing dirs
+        unresolved(base_dir.clone())
+            .resolve()
+            .expect("resolve dir config");
+        let resolved = unresolved(base_dir)
+            .resolve()
+            .expect("resolve dir config again");
+
+        for dir in [
+            &resolved.base_dir,
+            &resolved.services_base_dir,
+            &resolved.builtins_base_dir,
+            &resolved.avm_base_dir,
+            &resolved.particles_vault_dir,
+            &resolved.spell_base_dir,
+            &resolved.keypairs_base_dir,
+        ] {
+            assert!(dir.is_dir(), "{dir:?} must exist");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn readonly_dir() {
+        use std::fs::Permissions;
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+
+        let base_dir = make_tmp_dir();
+        let services_dir = base_dir.join("readonly_services");
+        std::fs::create_dir_all(&services_dir).unwrap();
+        std::fs::set_permissions(&services_dir, Permissions::from_mode(0o555)).unwrap();
+        if check_writable(&services_dir).is_ok() {
+            // permissions aren't enforced, e.g. when running as root.
+            // Written to stderr directly, so that test output capturing doesn't hide the skip
+            writeln!(
+                std::io::stderr(),
+                "readonly_dir skipped: read-only {services_dir:?} is writable"
+            )
+            .ok();
+            return;
+        }
+
+        let mut config = unresolved(base_dir);
+        config.services_base_dir = Some(services_dir.clone());
+        let err = config
+            .resolve()
+            .expect_err("read-only dir must be rejected");
+
+        assert!(err.to_string().contains(&format!("{services_dir:?}")));
+    }
+
+    #[test]
+    fn env_vars_expanded() {
+        let base_dir = make_tmp_dir();
+        std::env::set_var("DIR_CONFIG_TEST_BASE", &base_dir);
+
+        let config = unresolved(PathBuf::from("$DIR_CONFIG_TEST_BASE/data"));
+        let resolved = config.resolve().expect("resolve dir config");
+        assert_eq!(
+            resolved.base_dir,
+            base_dir.join("data").canonicalize().unwrap()
+        );
+
+        let path = expand_env_vars(PathBuf::from("${DIR_CONFIG_TEST_BASE}/data")).unwrap();
+        assert_eq!(path, base_dir.join("data"));
+    }
+
+    #[test]
+    fn undefined_env_var() {
+        let config = unresolved(PathBuf::from("$DIR_CONFIG_TEST_UNDEFINED/data"));
+        let err = config
+            .resolve()
+            .expect_err("undefined variable must be rejected");
+
+        assert!(err.to_string().contains("DIR_CONFIG_TEST_UNDEFINED"));
+    }
+
+    #[test]
+    fn no_env_vars() {
+        for path in ["/var/lib/fluence", "relative/dir", "/cost/100$"] {
+            let expanded = expand_env_vars(PathBuf::from(path)).unwrap();
+            assert_eq!(expanded, PathBuf::from(path));
+        }
+    }
+
+    #[test]
+    fn avm_base_dir_spellings() {
+        let base_dir = make_tmp_dir();
+        let avm_dir = base_dir.join("avm");
+        let stepper_dir = base_dir.join("stepper_legacy");
+
+        let parse = |config: String| -> ResolvedDirConfig {
+            toml::from_str::<UnresolvedDirConfig>(&config)
+                .expect("deserialize dir config")
+                .resolve()
+                .expect("resolve dir config")
+        };
+        let base = format!("base_dir = {:?}\n", base_dir);
+
+        let config = parse(format!("{base}avm_base_dir = {avm_dir:?}"));
+        assert_eq!(config.avm_base_dir, avm_dir.canonicalize().unwrap());
+
+        let config = parse(format!("{base}stepper_base_dir = {stepper_dir:?}"));
+        assert_eq!(config.avm_base_dir, stepper_dir.canonicalize().unwrap());
+
+        let config = parse(format!(
+            "{base}avm_base_dir = {avm_dir:?}\nstepper_base_dir = {stepper_dir:?}"
+        ));
+        assert_eq!(config.avm_base_dir, avm_dir.canonicalize().unwrap());
+    }
+}
@@ -45,16 +45,19 @@ pub use resolved_config::{load_config, resolve_config};
 pub use bootstrap_config::BootstrapConfig;
 pub use kademlia_config::KademliaConfig;
 pub use network_config::NetworkConfig;
-pub use node_config::{NodeConfig, TransportConfig};
+pub use node_config::{
+    ModuleUploadsConfig, NodeConfig, ParticleDedupConfig, ParticleRateLimit, ParticleTtlLimit,
+    TransportConfig,
+};
 pub use resolved_config::{ResolvedConfig, UnresolvedConfig};
 pub use services_config::ServicesConfig;
 
 pub mod config_keys {
     pub use crate::resolved_config::{
         ALLOW_PRIVATE_IPS, AQUA_VM_POOL_SIZE, BLUEPRINT_DIR, BOOTSTRAP_FREQ, BOOTSTRAP_NODE,
-        CERTIFICATE_DIR, CONFIG_FILE, EXTERNAL_ADDR, EXTERNAL_MULTIADDRS, LOCAL,
-        MANAGEMENT_PEER_ID, METRICS_PORT, ROOT_KEY_FORMAT, ROOT_KEY_PAIR_GENERATE,
-        ROOT_KEY_PAIR_PATH, ROOT_KEY_PAIR_VALUE, SECRET_KEY, SERVICES_WORKDIR, SERVICE_ENVS,
-        TCP_PORT, WEBSOCKET_PORT,
+        CERTIFICATE_DIR, CONFIG_FILE, DRY_RUN_CONFIG, EXTERNAL_ADDR, EXTERNAL_MULTIADDRS,
+        LISTEN_IP, LOCAL, LOG_FORMAT, MANAGEMENT_PEER_ID, METRICS_PORT, ROOT_KEY_FORMAT,
+        ROOT_KEY_PAIR_GENERATE, ROOT_KEY_PAIR_PATH, ROOT_KEY_PAIR_VALUE, SECRET_KEY,
+        SERVICES_WORKDIR, SERVICE_ENVS, TCP_PORT, WEBSOCKET_PORT,
     };
 }
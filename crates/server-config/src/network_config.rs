@@ -14,12 +14,14 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
 use libp2p::{core::Multiaddr, identity::Keypair, PeerId};
 use libp2p_metrics::Metrics;
 
 use config_utils::to_peer_id;
 use particle_protocol::ProtocolConfig;
-use peer_metrics::{ConnectionPoolMetrics, ConnectivityMetrics};
+use peer_metrics::{ConnectionPoolMetrics, ConnectivityMetrics, KademliaMetrics};
 
 use crate::{BootstrapConfig, KademliaConfig, ResolvedConfig};
 
@@ -33,10 +35,12 @@ pub struct NetworkConfig {
     pub protocol_config: ProtocolConfig,
     pub kademlia_config: KademliaConfig,
     pub particle_queue_buffer: usize,
+    pub connection_idle_timeout: Option<Duration>,
     pub bootstrap_frequency: usize,
     pub allow_local_addresses: bool,
     pub connectivity_metrics: Option<ConnectivityMetrics>,
     pub connection_pool_metrics: Option<ConnectionPoolMetrics>,
+    pub kademlia_metrics: Option<KademliaMetrics>,
 }
 
 impl NetworkConfig {
@@ -44,6 +48,7 @@ impl NetworkConfig {
         libp2p_metrics: Option<Metrics>,
         connectivity_metrics: Option<ConnectivityMetrics>,
         connection_pool_metrics: Option<ConnectionPoolMetrics>,
+        kademlia_metrics: Option<KademliaMetrics>,
         key_pair: Keypair,
         config: &ResolvedConfig,
         node_version: &'static str,
@@ -58,10 +63,12 @@ impl NetworkConfig {
             protocol_config: config.protocol_config.clone(),
             kademlia_config: config.kademlia.clone(),
             particle_queue_buffer: config.particle_queue_buffer,
+            connection_idle_timeout: config.connection_idle_timeout,
             bootstrap_frequency: config.bootstrap_frequency,
             allow_local_addresses: config.allow_local_addresses,
             connectivity_metrics,
             connection_pool_metrics,
+            kademlia_metrics,
         }
     }
 }
@@ -1,7 +1,8 @@
-use std::collections::HashMap;
-use std::net::IpAddr;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
 use base64::{engine::general_purpose::STANDARD as base64, Engine};
@@ -65,7 +66,7 @@ pub struct NodeConfig {
     pub external_address: Option<IpAddr>,
 
     /// External multiaddresses to advertise; more flexible that IpAddr
-    #[serde(default)]
+    #[serde(default, deserialize_with = "parse_external_multiaddrs")]
     pub external_multiaddresses: Vec<Multiaddr>,
 
     #[serde(flatten)]
@@ -93,6 +94,11 @@ pub struct NodeConfig {
     #[serde(default)]
     pub aquavm_max_heap_size: Option<bytesize::ByteSize>,
 
+    /// Hex-encoded SHA256 of the AIR interpreter.
+    /// If set, node refuses to start with an interpreter that has a different hash.
+    #[serde(default)]
+    pub air_interpreter_sha256: Option<String>,
+
     /// Maximum heap size in bytes available for a WASM module.
     #[serde_as(as = "DisplayFromStr")]
     #[serde(default = "default_module_max_heap_size")]
@@ -103,11 +109,78 @@ pub struct NodeConfig {
     #[serde(default)]
     pub module_default_heap_size: Option<bytesize::ByteSize>,
 
+    /// Maximum number of live services a single peer may create.
+    /// Management peer isn't limited. No limit if not set.
+    #[serde(default)]
+    pub max_services_per_owner: Option<usize>,
+
+    /// Maximum depth of nested calls, e.g. custom services calling back into builtins
+    #[serde(default = "default_max_call_depth")]
+    pub max_call_depth: usize,
+
+    /// Maximum size of a script file read from particle vault
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default = "default_max_script_size")]
+    pub max_script_size: bytesize::ByteSize,
+
+    /// Limits on modules uploaded in chunks
+    #[serde(default)]
+    pub module_uploads: ModuleUploadsConfig,
+
+    /// Limits sustained rate of particles accepted from a single init peer id.
+    /// Particles over the limit are delayed, or dropped if they'd expire before their turn.
+    /// Management peer isn't limited. No limit if not set.
+    #[serde(default)]
+    pub particle_rate_limit: Option<ParticleRateLimit>,
+
+    /// Bounds how long the node tracks a single particle. No limit if not set.
+    #[serde(default)]
+    pub max_particle_ttl: Option<ParticleTtlLimit>,
+
+    /// Drops repeated deliveries of the same particle, e.g. caused by network retries
+    #[serde(default)]
+    pub particle_dedup: ParticleDedupConfig,
+
+    /// If not empty, only particles from these init peer ids are accepted.
+    /// Particles from this node and from the management peer are always accepted.
+    #[serde_as(as = "HashSet<DisplayFromStr>")]
+    #[serde(default)]
+    pub allowed_peer_ids: HashSet<PeerId>,
+
+    /// Particles from these init peer ids are dropped. Takes precedence over `allowed_peer_ids`
+    #[serde_as(as = "HashSet<DisplayFromStr>")]
+    #[serde(default)]
+    pub denied_peer_ids: HashSet<PeerId>,
+
+    /// If true, particles not signed by their init peer id are dropped
+    #[serde(default)]
+    pub verify_particle_signatures: bool,
+
+    /// If true, particle vaults aren't removed after particle expiration. Useful for debugging
+    #[serde(default)]
+    pub keep_particle_vaults: bool,
+
+    /// If true, the incoming data trace and call results of a particle are logged at debug
+    /// level when its interpretation fails. Traces can be large, so it's off by default
+    #[serde(default)]
+    pub log_failed_particle_traces: bool,
+
+    /// Log levels of module targets, e.g. `aquamarine = "debug"`.
+    /// Directives from `RUST_LOG` take precedence over these.
+    #[serde_as(as = "HashMap<_, DisplayFromStr>")]
+    #[serde(default)]
+    pub log_levels: HashMap<String, log::LevelFilter>,
+
     #[serde(default)]
     pub kademlia: KademliaConfig,
 
     #[serde(default = "default_particle_queue_buffer_size")]
     pub particle_queue_buffer: usize,
+    /// Connections that didn't send or receive particles for that long are closed.
+    /// Connections are never closed for inactivity if not set
+    #[serde(default)]
+    #[serde(with = "humantime_serde")]
+    pub connection_idle_timeout: Option<Duration>,
     #[serde(default = "default_particle_processor_parallelism")]
     pub particle_processor_parallelism: Option<usize>,
 
@@ -135,11 +208,89 @@ pub struct NodeConfig {
     #[serde(with = "humantime_serde")]
     pub particle_execution_timeout: Duration,
 
+    /// On shutdown, subsystems that didn't stop within that time are abandoned
+    #[serde(default = "default_shutdown_grace_period")]
+    #[serde(with = "humantime_serde")]
+    pub shutdown_grace_period: Duration,
+
     #[serde(with = "peerid_serializer")]
     #[serde(default = "default_management_peer_id")]
     pub management_peer_id: PeerId,
 }
 
+/// Limits on modules uploaded in chunks via `dist.begin_module_upload`.
+/// Uploads are kept in memory, so up to `max_uploads * max_size` bytes may be held at once
+#[serde_as]
+#[derive(Clone, Copy, Deserialize, Debug)]
+pub struct ModuleUploadsConfig {
+    /// Maximum size of a module assembled from chunks
+    #[serde_as(as = "DisplayFromStr")]
+    #[serde(default = "default_module_upload_max_size")]
+    pub max_size: bytesize::ByteSize,
+    /// Maximum number of uploads in progress
+    #[serde(default = "default_module_uploads")]
+    pub max_uploads: usize,
+    /// Maximum number of uploads in progress started by a single peer
+    #[serde(default = "default_module_uploads_per_peer")]
+    pub max_uploads_per_peer: usize,
+    /// Uploads that didn't receive chunks for that long are dropped
+    #[serde(default = "default_module_upload_timeout")]
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl Default for ModuleUploadsConfig {
+    fn default() -> Self {
+        Self {
+            max_size: default_module_upload_max_size(),
+            max_uploads: default_module_uploads(),
+            max_uploads_per_peer: default_module_uploads_per_peer(),
+            timeout: default_module_upload_timeout(),
+        }
+    }
+}
+
+/// Token bucket parameters for particles sent by a single peer
+#[derive(Clone, Copy, Deserialize, Debug)]
+pub struct ParticleRateLimit {
+    /// How many particles per second a peer may send in the long run
+    pub particles_per_sec: f64,
+    /// How many particles a peer may send at once. Defaults to `particles_per_sec`
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// Upper bound on TTL of particles processed by the node
+#[derive(Clone, Copy, Deserialize, Debug)]
+pub struct ParticleTtlLimit {
+    #[serde(with = "humantime_serde")]
+    pub max_ttl: Duration,
+    /// If true, particles with a larger TTL are dropped, otherwise the node tracks them
+    /// for no longer than `max_ttl`. Particle's TTL isn't changed, so it's still forwarded intact.
+    #[serde(default)]
+    pub reject: bool,
+}
+
+/// Particle is a duplicate if a particle with the same id and data was received within `window`
+#[derive(Clone, Copy, Deserialize, Debug)]
+pub struct ParticleDedupConfig {
+    /// How many recently received particles are remembered. De-duplication is disabled if 0
+    #[serde(default = "default_particle_dedup_capacity")]
+    pub capacity: usize,
+    #[serde(default = "default_particle_dedup_window")]
+    #[serde(with = "humantime_serde")]
+    pub window: Duration,
+}
+
+impl Default for ParticleDedupConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_particle_dedup_capacity(),
+            window: default_particle_dedup_window(),
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, Derivative, Copy)]
 #[derivative(Debug)]
 pub struct TransportConfig {
@@ -177,10 +328,16 @@ pub struct ListenConfig {
     #[serde(default = "default_tcp_port")]
     pub tcp_port: u16,
 
-    /// Local ip address to listen on
+    /// Local ip address to listen on, either IPv4 or IPv6
     #[serde(default = "default_listen_ip")]
     pub listen_ip: IpAddr,
 
+    /// Also listen on the counterpart of `listen_ip` from the other IP version,
+    /// e.g. on `::` for `0.0.0.0` or on `127.0.0.1` for `::1`.
+    /// Only unspecified and loopback `listen_ip` have a counterpart
+    #[serde(default)]
+    pub dual_stack: bool,
+
     /// For ws connections
     #[serde(default = "default_websocket_port")]
     pub websocket_port: u16,
@@ -189,6 +346,28 @@ pub struct ListenConfig {
     pub listen_multiaddrs: Vec<Multiaddr>,
 }
 
+impl ListenConfig {
+    /// IP addresses to listen on: `listen_ip` and, with `dual_stack`, its counterpart
+    pub fn listen_ips(&self) -> Vec<IpAddr> {
+        let mut ips = vec![self.listen_ip];
+        if self.dual_stack {
+            ips.extend(self.dual_stack_ip());
+        }
+        ips
+    }
+
+    /// Address of the other IP version that corresponds to `listen_ip`
+    pub fn dual_stack_ip(&self) -> Option<IpAddr> {
+        match self.listen_ip {
+            IpAddr::V4(ip) if ip.is_unspecified() => Some(Ipv6Addr::UNSPECIFIED.into()),
+            IpAddr::V4(ip) if ip.is_loopback() => Some(Ipv6Addr::LOCALHOST.into()),
+            IpAddr::V6(ip) if ip.is_unspecified() => Some(Ipv4Addr::UNSPECIFIED.into()),
+            IpAddr::V6(ip) if ip.is_loopback() => Some(Ipv4Addr::LOCALHOST.into()),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, Debug, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[repr(transparent)]
 pub struct PeerIdSerializable(#[serde(with = "peerid_serializer")] PeerId);
@@ -279,6 +458,26 @@ where
         .map_err(|e| serde::de::Error::custom(format!("{e:?}")))
 }
 
+/// Parse multiaddrs one by one to point at the malformed one in the error
+fn parse_external_multiaddrs<'de, D>(deserializer: D) -> Result<Vec<Multiaddr>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let addrs = Vec::<String>::deserialize(deserializer)?;
+    addrs
+        .into_iter()
+        .map(|addr| match Multiaddr::from_str(&addr) {
+            Ok(maddr) if maddr.is_empty() => Err(serde::de::Error::custom(format!(
+                "invalid external multiaddress '{addr}': address is empty"
+            ))),
+            Ok(maddr) => Ok(maddr),
+            Err(err) => Err(serde::de::Error::custom(format!(
+                "invalid external multiaddress '{addr}': {err}"
+            ))),
+        })
+        .collect()
+}
+
 fn parse_envs<'de, D>(deserializer: D) -> Result<HashMap<Vec<u8>, Vec<u8>>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -16,6 +16,7 @@
 
 use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 
 use clap::{ArgMatches, Values};
 use eyre::{eyre, ContextCompat, WrapErr};
@@ -30,6 +31,7 @@ use crate::node_config::NodeConfig;
 
 pub const WEBSOCKET_PORT: &str = "websocket_port";
 pub const TCP_PORT: &str = "tcp_port";
+pub const LISTEN_IP: &str = "listen_ip";
 pub const ROOT_KEY_PAIR: &str = "root_key_pair";
 pub const ROOT_KEY_PAIR_VALUE: &str = "value";
 pub const ROOT_KEY_FORMAT: &str = "format";
@@ -50,10 +52,14 @@ pub const ALLOW_PRIVATE_IPS: &str = "allow_local_addresses";
 pub const METRICS_PORT: &str = "metrics_port";
 pub const AQUA_VM_POOL_SIZE: &str = "aquavm_pool_size";
 pub const SECRET_KEY: &str = "secret_key";
+pub const DRY_RUN_CONFIG: &str = "dry_run_config";
+pub const LOG_FORMAT: &str = "log_format";
+pub const INCLUDE: &str = "include";
 
 const ARGS: &[&str] = &[
     WEBSOCKET_PORT,
     TCP_PORT,
+    LISTEN_IP,
     ROOT_KEY_PAIR_VALUE,
     ROOT_KEY_PAIR_GENERATE,
     ROOT_KEY_FORMAT,
@@ -83,6 +89,14 @@ pub struct UnresolvedConfig {
 
 impl UnresolvedConfig {
     pub fn resolve(self) -> eyre::Result<ResolvedConfig> {
+        let listen_config = &self.node_config.listen_config;
+        if listen_config.dual_stack && listen_config.dual_stack_ip().is_none() {
+            return Err(eyre!(
+                "dual_stack requires listen_ip to be unspecified or loopback, got {}",
+                listen_config.listen_ip
+            ));
+        }
+
         Ok(ResolvedConfig {
             dir_config: self.dir_config.resolve()?,
             node_config: self.node_config,
@@ -133,7 +147,17 @@ impl ResolvedConfig {
 
         addrs.extend(self.external_multiaddresses.iter().cloned());
 
-        addrs
+        // duplicates would be added to the swarm several times, inflating their score
+        let mut unique = Vec::with_capacity(addrs.len());
+        for addr in addrs {
+            if unique.contains(&addr) {
+                log::warn!("Duplicate external address {} is ignored", addr);
+            } else {
+                unique.push(addr);
+            }
+        }
+
+        unique
     }
 
     pub fn metrics_listen_addr(&self) -> SocketAddr {
@@ -143,17 +167,65 @@ impl ResolvedConfig {
         )
     }
 
+    /// Overview of the resolved config, printed by `--dry-run-config`
+    pub fn summary(&self) -> String {
+        let dirs = &self.dir_config;
+        let external_addresses = self
+            .external_addresses()
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "peer id: {}\n\
+             management peer id: {}\n\
+             listen ip: {}\n\
+             tcp port: {}\n\
+             websocket port: {}\n\
+             metrics port: {}\n\
+             external addresses: [{}]\n\
+             base dir: {:?}\n\
+             services dir: {:?}\n\
+             builtins dir: {:?}\n\
+             avm dir: {:?}\n\
+             spell dir: {:?}\n\
+             keypairs dir: {:?}\n\
+             air interpreter: {:?}",
+            self.root_key_pair.get_peer_id(),
+            self.management_peer_id,
+            self.listen_config.listen_ip,
+            self.listen_config.tcp_port,
+            self.listen_config.websocket_port,
+            self.metrics_config.metrics_port,
+            external_addresses,
+            dirs.base_dir,
+            dirs.services_base_dir,
+            dirs.builtins_base_dir,
+            dirs.avm_base_dir,
+            dirs.spell_base_dir,
+            dirs.keypairs_base_dir,
+            dirs.air_interpreter_path,
+        )
+    }
+
     pub fn listen_multiaddrs(&self) -> Vec<Multiaddr> {
         let config = &self.listen_config;
 
-        let mut tcp = Multiaddr::from(config.listen_ip);
-        tcp.push(Protocol::Tcp(config.tcp_port));
+        config
+            .listen_ips()
+            .into_iter()
+            .flat_map(|ip| {
+                let mut tcp = Multiaddr::from(ip);
+                tcp.push(Protocol::Tcp(config.tcp_port));
 
-        let mut ws = Multiaddr::from(config.listen_ip);
-        ws.push(Protocol::Tcp(config.websocket_port));
-        ws.push(Protocol::Ws("/".into()));
+                let mut ws = Multiaddr::from(ip);
+                ws.push(Protocol::Tcp(config.websocket_port));
+                ws.push(Protocol::Ws("/".into()));
 
-        vec![tcp, ws]
+                [tcp, ws]
+            })
+            .collect()
     }
 }
 
@@ -243,28 +315,39 @@ pub fn load_config(arguments: ArgMatches) -> eyre::Result<ResolvedConfig> {
     let config_file = arguments.value_of(CONFIG_FILE).map(Into::into);
     let config_file = config_file.unwrap_or(default_config_path());
 
-    let config_bytes = if config_file.is_file() {
+    let config = if config_file.is_file() {
         let config_file = to_abs_path(config_file);
 
         log::info!("Loading config from {:?}", config_file);
 
-        std::fs::read(&config_file)
-            .wrap_err_with(|| format!("Failed reading config {config_file:?}"))?
+        read_config_with_includes(&config_file, &mut vec![])?
     } else {
         log::info!("Config wasn't found, using default settings");
-        Vec::default()
+        toml::value::Table::default()
     };
 
-    let config = resolve_config(&arguments, &config_bytes)
+    let config = resolve_config_table(&arguments, config)
         .wrap_err(eyre!("config deserialization failed"))?;
 
     Ok(config)
 }
 
 pub fn resolve_config(arguments: &ArgMatches, content: &[u8]) -> eyre::Result<ResolvedConfig> {
-    let mut config: toml::value::Table =
-        toml::from_slice(content).wrap_err("deserializing config")?;
+    let config: toml::value::Table = toml::from_slice(content).wrap_err("deserializing config")?;
+    if config.contains_key(INCLUDE) {
+        return Err(eyre!(
+            "'{}' is supported only in config files, paths are resolved relative to them",
+            INCLUDE
+        ));
+    }
 
+    resolve_config_table(arguments, config)
+}
+
+fn resolve_config_table(
+    arguments: &ArgMatches,
+    mut config: toml::value::Table,
+) -> eyre::Result<ResolvedConfig> {
     insert_args_to_config(arguments, &mut config)?;
 
     let config = toml::value::Value::Table(config);
@@ -278,10 +361,88 @@ pub fn resolve_config(arguments: &ArgMatches, content: &[u8]) -> eyre::Result<Re
     Ok(config)
 }
 
+/// Reads config file and merges files listed in its `include` directive into it.
+/// Included files are applied in order, so later ones override keys of earlier ones,
+/// and keys of the including file override all of them.
+/// Include paths are relative to the directory of the including file.
+///
+/// `parents` are the files currently being read, used to detect include cycles.
+fn read_config_with_includes(
+    path: &Path,
+    parents: &mut Vec<PathBuf>,
+) -> eyre::Result<toml::value::Table> {
+    let path =
+        std::fs::canonicalize(path).wrap_err_with(|| format!("Failed reading config {path:?}"))?;
+    if let Some(pos) = parents.iter().position(|p| p == &path) {
+        let cycle = parents[pos..]
+            .iter()
+            .chain(std::iter::once(&path))
+            .map(|p| format!("{p:?}"))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        return Err(eyre!("config include cycle: {}", cycle));
+    }
+
+    let content =
+        std::fs::read(&path).wrap_err_with(|| format!("Failed reading config {path:?}"))?;
+    let mut config: toml::value::Table =
+        toml::from_slice(&content).wrap_err_with(|| format!("deserializing config {path:?}"))?;
+
+    let includes = match config.remove(INCLUDE) {
+        None => vec![],
+        Some(toml::Value::Array(includes)) => includes
+            .into_iter()
+            .map(|include| match include {
+                toml::Value::String(include) => Ok(PathBuf::from(include)),
+                other => Err(eyre!("'{}' must contain paths, got {}", INCLUDE, other)),
+            })
+            .collect::<eyre::Result<Vec<_>>>()
+            .wrap_err_with(|| format!("invalid '{INCLUDE}' in config {path:?}"))?,
+        Some(other) => {
+            return Err(eyre!(
+                "'{}' in config {:?} must be an array of paths, got {}",
+                INCLUDE,
+                path,
+                other
+            ))
+        }
+    };
+
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    parents.push(path);
+    let mut merged = toml::value::Table::default();
+    for include in includes {
+        let included = read_config_with_includes(&dir.join(include), parents)?;
+        merge_tables(&mut merged, included);
+    }
+    parents.pop();
+    merge_tables(&mut merged, config);
+
+    Ok(merged)
+}
+
+/// Inserts keys of `overrides` into `base`, merging nested tables key by key
+fn merge_tables(base: &mut toml::value::Table, overrides: toml::value::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => {
+                merge_tables(base, value)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
     use base64::{engine::general_purpose::STANDARD as base64, Engine};
     use fluence_keypair::KeyPair;
+    use libp2p::PeerId;
 
     use fs_utils::make_tmp_dir;
 
@@ -320,6 +481,310 @@ mod tests {
         assert_eq!(config.node_config.script_storage_max_failures, 10);
     }
 
+    #[test]
+    fn test_peer_access_lists() {
+        let allowed = "12D3KooWB9P1xmV3c7ZPpBemovbwCiRRTKd3Kq2jsVPQN4ZukDfy";
+        let denied = "12D3KooWGUC4FFaovSd24iQupgyYqVGfxHtVgHCRso1728Hfp7U1";
+        let config = format!(
+            r#"
+            allowed_peer_ids = ["{allowed}"]
+            denied_peer_ids = ["{denied}"]
+            "#
+        );
+
+        let config = resolve_config(&matches(), config.as_bytes()).expect("deserialize config");
+
+        let allowed = PeerId::from_str(allowed).unwrap();
+        let denied = PeerId::from_str(denied).unwrap();
+        assert_eq!(config.allowed_peer_ids, HashSet::from([allowed]));
+        assert_eq!(config.denied_peer_ids, HashSet::from([denied]));
+
+        // lists are empty by default
+        let config = resolve_config(&matches(), &[]).expect("deserialize config");
+        assert!(config.allowed_peer_ids.is_empty());
+        assert!(config.denied_peer_ids.is_empty());
+    }
+
+    #[test]
+    fn test_external_addresses() {
+        let config = r#"
+            external_address = "1.2.3.4"
+            tcp_port = 7777
+            external_multiaddresses = ["/ip4/1.2.3.4/tcp/7777", "/dns4/example.com/tcp/80", "/dns4/example.com/tcp/80"]
+            "#;
+
+        let config = resolve_config(&matches(), config.as_bytes()).expect("deserialize config");
+
+        let addrs: Vec<String> = config
+            .external_addresses()
+            .into_iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "/ip4/1.2.3.4/tcp/7777",
+                "/ip4/1.2.3.4/tcp/9999/ws",
+                "/dns4/example.com/tcp/80"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_listen_ip() {
+        let config = resolve_config(&matches(), &[]).expect("deserialize config");
+        let addrs: Vec<String> = config
+            .listen_multiaddrs()
+            .into_iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            addrs,
+            vec!["/ip4/0.0.0.0/tcp/7777", "/ip4/0.0.0.0/tcp/9999/ws"]
+        );
+
+        let matches = clap::App::new("Fluence node")
+            .args(create_args().as_slice())
+            .get_matches_from(["particle-node", "--listen-ip", "127.0.0.1"]);
+        let config = resolve_config(&matches, &[]).expect("deserialize config");
+        let addrs: Vec<String> = config
+            .listen_multiaddrs()
+            .into_iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            addrs,
+            vec!["/ip4/127.0.0.1/tcp/7777", "/ip4/127.0.0.1/tcp/9999/ws"]
+        );
+    }
+
+    #[test]
+    fn test_dual_stack() {
+        let listen_addrs = |config: &str| -> eyre::Result<Vec<String>> {
+            let config = resolve_config(&matches(), config.as_bytes())?;
+            Ok(config
+                .listen_multiaddrs()
+                .into_iter()
+                .map(|a| a.to_string())
+                .collect())
+        };
+
+        assert_eq!(
+            listen_addrs(r#"listen_ip = "::1""#).unwrap(),
+            vec!["/ip6/::1/tcp/7777", "/ip6/::1/tcp/9999/ws"]
+        );
+        assert_eq!(
+            listen_addrs("dual_stack = true").unwrap(),
+            vec![
+                "/ip4/0.0.0.0/tcp/7777",
+                "/ip4/0.0.0.0/tcp/9999/ws",
+                "/ip6/::/tcp/7777",
+                "/ip6/::/tcp/9999/ws"
+            ]
+        );
+        assert_eq!(
+            listen_addrs(
+                r#"
+                listen_ip = "::1"
+                dual_stack = true
+                "#
+            )
+            .unwrap(),
+            vec![
+                "/ip6/::1/tcp/7777",
+                "/ip6/::1/tcp/9999/ws",
+                "/ip4/127.0.0.1/tcp/7777",
+                "/ip4/127.0.0.1/tcp/9999/ws"
+            ]
+        );
+
+        // specific address has no counterpart of the other IP version
+        let err = listen_addrs(
+            r#"
+            listen_ip = "10.0.0.1"
+            dual_stack = true
+            "#,
+        )
+        .err()
+        .expect("dual stack on a specific address must fail");
+        assert!(format!("{err:?}").contains("dual_stack"), "{err:?}");
+    }
+
+    #[test]
+    fn test_ipv6_external_address() {
+        let config = r#"
+            external_address = "2001:db8::1"
+            external_multiaddresses = ["/ip6/2001:db8::2/tcp/7777"]
+            "#;
+
+        let config = resolve_config(&matches(), config.as_bytes()).expect("deserialize config");
+
+        let addrs: Vec<String> = config
+            .external_addresses()
+            .into_iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                "/ip6/2001:db8::1/tcp/7777",
+                "/ip6/2001:db8::1/tcp/9999/ws",
+                "/ip6/2001:db8::2/tcp/7777"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_external_address() {
+        for addr in ["/ip4/1.2.3.4/tcp", "1.2.3.4:7777", ""] {
+            let config =
+                format!(r#"external_multiaddresses = ["/ip4/1.2.3.4/tcp/7777", "{addr}"]"#);
+
+            let err = resolve_config(&matches(), config.as_bytes())
+                .err()
+                .expect("malformed address must fail config resolution");
+            let err = format!("{err:?}");
+            assert!(
+                err.contains(&format!("invalid external multiaddress '{addr}'")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn dry_run_config() {
+        let dir = make_tmp_dir();
+        let config_path = dir.join("Config.toml");
+        let management = "12D3KooWB9P1xmV3c7ZPpBemovbwCiRRTKd3Kq2jsVPQN4ZukDfy";
+        let args = |config_path: &std::path::Path| {
+            clap::App::new("Fluence node")
+                .args(create_args().as_slice())
+                .get_matches_from([
+                    "particle-node",
+                    "--dry-run-config",
+                    "--config",
+                    config_path.to_str().unwrap(),
+                ])
+        };
+
+        let config = format!(
+            r#"
+            base_dir = "{}"
+            management_peer_id = "{management}"
+            "#,
+            dir.display()
+        );
+        std::fs::write(&config_path, config).unwrap();
+        let matches = args(&config_path);
+        assert!(matches.is_present(DRY_RUN_CONFIG));
+        let config = load_config(matches).expect("valid config");
+        let summary = config.summary();
+        assert!(summary.contains("tcp port: 7777"), "{summary}");
+        assert!(
+            summary.contains(&format!("management peer id: {management}")),
+            "{summary}"
+        );
+        let peer_id = config.root_key_pair.get_peer_id();
+        assert!(
+            summary.contains(&format!("peer id: {peer_id}")),
+            "{summary}"
+        );
+
+        std::fs::write(&config_path, r#"management_peer_id = "not a peer id""#).unwrap();
+        load_config(args(&config_path))
+            .err()
+            .expect("invalid config must fail");
+    }
+
+    #[test]
+    fn config_includes() {
+        let dir = make_tmp_dir();
+        std::fs::create_dir(dir.join("env")).unwrap();
+        let peer = |n: usize| {
+            [
+                "12D3KooWB9P1xmV3c7ZPpBemovbwCiRRTKd3Kq2jsVPQN4ZukDfy",
+                "12D3KooWGUC4FFaovSd24iQupgyYqVGfxHtVgHCRso1728Hfp7U1",
+                "12D3KooWEXNUbCXooUwHrHBbrmjsrpHXoEphPwbjQXEGyzbqKnE9",
+            ][n]
+        };
+
+        std::fs::write(
+            dir.join("env/base.toml"),
+            format!(
+                r#"
+                script_storage_max_failures = 1
+                aquavm_pool_size = 1
+                allowed_peer_ids = ["{}"]
+                "#,
+                peer(0)
+            ),
+        )
+        .unwrap();
+        // included paths are relative to the including file
+        std::fs::write(
+            dir.join("env/prod.toml"),
+            r#"
+            include = ["base.toml"]
+            script_storage_max_failures = 2
+            aquavm_pool_size = 2
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("Config.toml"),
+            format!(
+                r#"
+                include = ["env/base.toml", "env/prod.toml"]
+                base_dir = "{}"
+                aquavm_pool_size = 3
+                "#,
+                dir.display()
+            ),
+        )
+        .unwrap();
+
+        let matches = clap::App::new("Fluence node")
+            .args(create_args().as_slice())
+            .get_matches_from([
+                "particle-node",
+                "--config",
+                dir.join("Config.toml").to_str().unwrap(),
+                "--management-key",
+                peer(2),
+            ]);
+        let config = load_config(matches).expect("load config");
+
+        // only set in base.toml
+        let allowed = PeerId::from_str(peer(0)).unwrap();
+        assert_eq!(config.allowed_peer_ids, HashSet::from([allowed]));
+        // prod.toml is included after base.toml
+        assert_eq!(config.script_storage_max_failures, 2);
+        // including file overrides included ones
+        assert_eq!(config.aquavm_pool_size, 3);
+        // command line arguments override all files
+        assert_eq!(config.management_peer_id.to_string(), peer(2));
+    }
+
+    #[test]
+    fn config_include_cycle() {
+        let dir = make_tmp_dir();
+        std::fs::write(dir.join("a.toml"), r#"include = ["b.toml"]"#).unwrap();
+        std::fs::write(dir.join("b.toml"), r#"include = ["c.toml"]"#).unwrap();
+        std::fs::write(dir.join("c.toml"), r#"include = ["a.toml"]"#).unwrap();
+
+        let matches = clap::App::new("Fluence node")
+            .args(create_args().as_slice())
+            .get_matches_from([
+                "particle-node",
+                "--config",
+                dir.join("a.toml").to_str().unwrap(),
+            ]);
+        let err = load_config(matches).err().expect("include cycle must fail");
+        let err = format!("{err:?}");
+        assert!(err.contains("config include cycle"), "{err}");
+        assert!(err.contains("a.toml\" -> "), "{err}");
+    }
+
     #[test]
     fn parse_path_keypair_generate() {
         let key_path = make_tmp_dir().join("secret_key.ed25519");
@@ -21,6 +21,8 @@ use libp2p::PeerId;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::ModuleUploadsConfig;
+
 #[derive(Debug, Clone)]
 pub struct ServicesConfig {
     /// Peer id of the current node
@@ -47,6 +49,15 @@ pub struct ServicesConfig {
     pub max_heap_size: ByteSize,
     /// Default heap size in bytes available for the module unless otherwise specified.
     pub default_heap_size: Option<ByteSize>,
+    /// Maximum number of live services owned by a single peer, unlimited if None.
+    /// Doesn't apply to management and builtins management peers.
+    pub max_services_per_owner: Option<usize>,
+    /// Maximum depth of nested service calls made on behalf of a single call from AquaVM
+    pub max_call_depth: usize,
+    /// Maximum size of a script file read from particle vault
+    pub max_script_size: ByteSize,
+    /// Limits on modules uploaded in chunks
+    pub module_uploads: ModuleUploadsConfig,
 }
 
 impl ServicesConfig {
@@ -60,6 +71,10 @@ impl ServicesConfig {
         builtins_management_peer_id: PeerId,
         max_heap_size: ByteSize,
         default_heap_size: Option<ByteSize>,
+        max_services_per_owner: Option<usize>,
+        max_call_depth: usize,
+        max_script_size: ByteSize,
+        module_uploads: ModuleUploadsConfig,
     ) -> Result<Self, std::io::Error> {
         let base_dir = to_abs_path(base_dir);
 
@@ -75,6 +90,10 @@ impl ServicesConfig {
             builtins_management_peer_id,
             max_heap_size,
             default_heap_size,
+            max_services_per_owner,
+            max_call_depth,
+            max_script_size,
+            module_uploads,
         };
 
         create_dirs(&[
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub use crate::config::*;
+pub use crate::cron::CronSchedule;
 
 pub type SpellId = String;
 
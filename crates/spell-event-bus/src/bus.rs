@@ -1,5 +1,6 @@
 use crate::api::*;
 use crate::config::{SpellTriggerConfigs, TriggerConfig};
+use crate::cron::CronSchedule;
 use async_std::sync::Arc;
 use async_std::task;
 use fluence_libp2p::types::{Inlet, Outlet};
@@ -47,10 +48,18 @@ impl PeerEventSubscribers {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum Schedule {
+    /// Trigger the spell every `period`
+    Interval(Duration),
+    /// Trigger the spell at times matching the cron expression
+    Cron(CronSchedule),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct Periodic {
     id: Arc<SpellId>,
-    period: Duration,
+    schedule: Schedule,
     end_at: Option<Instant>,
 }
 
@@ -66,15 +75,18 @@ impl Scheduled {
         Self { data, run_at }
     }
 
-    /// Reschedule a spell to `now` + `period`.
+    /// Reschedule a spell to `now` + `period`, or to the next cron fire time.
     /// Return `None` if the spell is supposed to end at the given time `end_at`.
     fn at(data: Periodic, now: Instant) -> Option<Scheduled> {
         if data.end_at.map(|end_at| end_at <= now).unwrap_or(false) {
             return None;
         }
 
-        // We do checked_add here only to avoid a mere possibility of internal panic.
-        let run_at = now.checked_add(data.period)?;
+        let run_at = match &data.schedule {
+            // We do checked_add here only to avoid a mere possibility of internal panic.
+            Schedule::Interval(period) => now.checked_add(*period)?,
+            Schedule::Cron(cron) => cron.next_instant(now)?,
+        };
         Some(Scheduled { data, run_at })
     }
 }
@@ -112,12 +124,22 @@ impl SubscribersState {
                 TriggerConfig::Timer(config) => {
                     let periodic = Periodic {
                         id: spell_id.clone(),
-                        period: config.period,
+                        schedule: Schedule::Interval(config.period),
                         end_at: config.end_at,
                     };
                     let scheduled = Scheduled::new(periodic, config.start_at);
                     self.scheduled.push(scheduled);
                 }
+                TriggerConfig::Cron(cron) => {
+                    let periodic = Periodic {
+                        id: spell_id.clone(),
+                        schedule: Schedule::Cron(cron.clone()),
+                        end_at: None,
+                    };
+                    if let Some(scheduled) = Scheduled::at(periodic, Instant::now()) {
+                        self.scheduled.push(scheduled);
+                    }
+                }
                 TriggerConfig::PeerEvent(config) => {
                     self.subscribers
                         .add(spell_id.clone(), config.events.clone());
@@ -546,4 +568,32 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn test_cron_schedule() {
+        let now = Instant::now();
+        let periodic = Periodic {
+            id: Arc::new("spell1".to_string()),
+            schedule: Schedule::Cron("* * * * *".parse().unwrap()),
+            end_at: None,
+        };
+
+        let scheduled = Scheduled::at(periodic, now).expect("cron is scheduled");
+        let delay = scheduled.run_at.duration_since(now);
+        assert!(delay > Duration::ZERO && delay <= Duration::from_secs(61));
+    }
+
+    #[test]
+    fn test_invalid_cron() {
+        let config = fluence_spell_dtos::trigger_config::TriggerConfig::default();
+        assert_matches!(
+            from_user_config(config.clone(), Some("0 0 * *")),
+            Err(ConfigError::InvalidCron { .. })
+        );
+        assert_matches!(
+            from_user_config(config.clone(), Some("0 0 30 2 *")),
+            Err(ConfigError::InvalidCron { .. })
+        );
+        assert_matches!(from_user_config(config, Some("0 0 * * *")), Ok(Some(_)));
+    }
 }
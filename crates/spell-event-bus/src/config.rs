@@ -1,4 +1,6 @@
 use crate::api::PeerEventType;
+use crate::cron::CronSchedule;
+use chrono::Utc;
 use fluence_spell_dtos::trigger_config::{
     ClockConfig, ConnectionPoolConfig, TriggerConfig as UserTriggerConfig,
 };
@@ -20,6 +22,8 @@ pub enum ConfigError {
     InvalidPeriod,
     #[error("invalid config: end_sec is less than start_sec or in the past")]
     InvalidEndSec,
+    #[error("invalid config: cron expression '{expr}' is invalid: {reason}")]
+    InvalidCron { expr: String, reason: String },
 }
 
 /// Convert timestamp to std::time::Instant.
@@ -31,8 +35,10 @@ fn to_instant(timestamp: u64) -> Option<Instant> {
 }
 
 /// Convert user-friendly config to event-bus-friendly config, validating it in the process.
+/// `cron` is an optional cron expression to trigger the spell on, see [CronSchedule] for the format.
 pub fn from_user_config(
     user_config: UserTriggerConfig,
+    cron: Option<&str>,
 ) -> Result<Option<SpellTriggerConfigs>, ConfigError> {
     let mut triggers = Vec::new();
 
@@ -46,6 +52,10 @@ pub fn from_user_config(
         triggers.push(TriggerConfig::PeerEvent(peer_event_config));
     }
 
+    if let Some(expr) = cron {
+        triggers.push(TriggerConfig::Cron(parse_cron(expr)?));
+    }
+
     let cfg = if !triggers.is_empty() {
         Some(SpellTriggerConfigs { triggers })
     } else {
@@ -54,6 +64,20 @@ pub fn from_user_config(
     Ok(cfg)
 }
 
+fn parse_cron(expr: &str) -> Result<CronSchedule, ConfigError> {
+    let invalid = |reason: String| ConfigError::InvalidCron {
+        expr: expr.to_string(),
+        reason,
+    };
+    let schedule: CronSchedule = expr.parse().map_err(invalid)?;
+    // don't accept schedules like "0 0 30 2 *" that would never trigger the spell
+    if schedule.next_after(Utc::now()).is_none() {
+        return Err(invalid("schedule never fires".to_string()));
+    }
+
+    Ok(schedule)
+}
+
 fn from_connection_config(connection_config: &ConnectionPoolConfig) -> Option<PeerEventConfig> {
     let mut pool_events = Vec::with_capacity(2);
     if connection_config.connect {
@@ -118,6 +142,7 @@ pub struct SpellTriggerConfigs {
 pub(crate) enum TriggerConfig {
     Timer(TimerConfig),
     PeerEvent(PeerEventConfig),
+    Cron(CronSchedule),
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,183 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use croner::Cron;
+
+/// Cron schedule in the standard 5-field format: `minute hour day-of-month month day-of-week`.
+/// All times are UTC. Parsing and matching are done by `croner`, which follows the usual cron
+/// semantics: if both day fields are restricted, a day matching either of them fires.
+#[derive(Clone)]
+pub struct CronSchedule {
+    expr: String,
+    cron: Cron,
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        // croner also accepts an optional seconds field, but spells are scheduled with minute precision
+        let fields = expr.split_whitespace().count();
+        if fields != 5 {
+            return Err(format!("expected 5 fields, got {fields}"));
+        }
+
+        let cron = Cron::new(expr).parse().map_err(|err| err.to_string())?;
+        Ok(Self {
+            expr: expr.to_string(),
+            cron,
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Returns the first time strictly after `time` that matches the schedule
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.cron.find_next_occurrence(&time, false).ok()
+    }
+
+    /// Converts the next fire time to `Instant`, counting from `now`
+    pub(crate) fn next_instant(&self, now: Instant) -> Option<Instant> {
+        let utc_now = Utc::now();
+        let mut next = self.next_after(utc_now)?;
+        // monotonic and wall clocks drift apart, so the timer may fire slightly before the scheduled
+        // minute. Skip that minute so the spell isn't triggered twice.
+        if next - utc_now < ChronoDuration::seconds(1) {
+            next = self.next_after(next)?;
+        }
+        let delay = (next - utc_now).to_std().ok()?;
+        now.checked_add(delay)
+    }
+}
+
+impl fmt::Debug for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CronSchedule").field(&self.expr).finish()
+    }
+}
+
+impl PartialEq for CronSchedule {
+    fn eq(&self, other: &Self) -> bool {
+        self.expr == other.expr
+    }
+}
+
+impl Eq for CronSchedule {}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate, Utc};
+
+    use crate::cron::CronSchedule;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        let time = NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap();
+        DateTime::from_utc(time, Utc)
+    }
+
+    fn next(expr: &str, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        expr.parse::<CronSchedule>().unwrap().next_after(time)
+    }
+
+    #[test]
+    fn every_minute() {
+        let time = utc(2023, 3, 10, 12, 34, 56);
+        assert_eq!(next("* * * * *", time), Some(utc(2023, 3, 10, 12, 35, 0)));
+        // next fire time is strictly after the given time
+        let time = utc(2023, 3, 10, 12, 35, 0);
+        assert_eq!(next("* * * * *", time), Some(utc(2023, 3, 10, 12, 36, 0)));
+    }
+
+    #[test]
+    fn daily_at_midnight() {
+        let time = utc(2023, 3, 10, 12, 34, 0);
+        assert_eq!(next("0 0 * * *", time), Some(utc(2023, 3, 11, 0, 0, 0)));
+        // crosses year boundary
+        let time = utc(2023, 12, 31, 23, 59, 30);
+        assert_eq!(next("0 0 * * *", time), Some(utc(2024, 1, 1, 0, 0, 0)));
+    }
+
+    #[test]
+    fn steps_and_lists() {
+        let time = utc(2023, 3, 10, 10, 7, 0);
+        assert_eq!(
+            next("*/15 * * * *", time),
+            Some(utc(2023, 3, 10, 10, 15, 0))
+        );
+        assert_eq!(
+            next("5,50 * * * *", time),
+            Some(utc(2023, 3, 10, 10, 50, 0))
+        );
+        assert_eq!(
+            next("0 9-17/4 * * *", time),
+            Some(utc(2023, 3, 10, 13, 0, 0))
+        );
+    }
+
+    #[test]
+    fn weekdays() {
+        // 2023-03-11 is Saturday
+        let time = utc(2023, 3, 11, 8, 0, 0);
+        assert_eq!(next("30 9 * * 1-5", time), Some(utc(2023, 3, 13, 9, 30, 0)));
+        assert_eq!(next("0 0 * * 0", time), Some(utc(2023, 3, 12, 0, 0, 0)));
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week() {
+        // 2023-03-10 is Friday, next Sunday is the 12th, next 15th is a Wednesday
+        let time = utc(2023, 3, 10, 0, 0, 0);
+        assert_eq!(next("0 12 15 * 0", time), Some(utc(2023, 3, 12, 12, 0, 0)));
+        assert_eq!(next("0 12 15 * *", time), Some(utc(2023, 3, 15, 12, 0, 0)));
+    }
+
+    #[test]
+    fn rare_dates() {
+        let time = utc(2023, 3, 1, 0, 0, 0);
+        assert_eq!(next("0 0 29 2 *", time), Some(utc(2024, 2, 29, 0, 0, 0)));
+        assert_eq!(next("0 0 31 * *", time), Some(utc(2023, 3, 31, 0, 0, 0)));
+        // February 30th never happens
+        assert_eq!(next("0 0 30 2 *", time), None);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let invalid = [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "a * * * *",
+        ];
+        for expr in invalid {
+            assert!(
+                expr.parse::<CronSchedule>().is_err(),
+                "'{expr}' must be invalid"
+            );
+        }
+    }
+}
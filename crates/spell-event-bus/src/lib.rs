@@ -5,3 +5,4 @@
 pub mod api;
 pub mod bus;
 mod config;
+mod cron;
@@ -3,3 +3,8 @@ use uuid::Uuid;
 pub fn uuid() -> String {
     Uuid::new_v4().to_string()
 }
+
+/// Deterministic v5 UUID derived from `seed`, same seed always gives the same UUID
+pub fn uuid_from_seed(seed: &[u8]) -> String {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, seed).to_string()
+}
@@ -22,6 +22,8 @@ use std::path;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
+use base64::engine::general_purpose::{GeneralPurpose, STANDARD, URL_SAFE};
+use base64::Engine;
 use derivative::Derivative;
 use fluence_keypair::{KeyPair, Signature};
 use futures::stream::FuturesUnordered;
@@ -36,23 +38,29 @@ use JValue::Array;
 
 use connection_pool::{ConnectionPoolApi, ConnectionPoolT};
 use kademlia::{KademliaApi, KademliaApiT};
+use key_manager::KeyManager;
 use now_millis::{now_ms, now_sec};
 use particle_args::{from_base58, Args, ArgsError, JError};
 use particle_execution::{FunctionOutcome, ParticleParams, ServiceFunction};
 use particle_modules::{
-    AddBlueprint, ModuleConfig, ModuleRepository, NamedModuleConfig, WASIConfig,
+    AddBlueprint, ModuleCompression, ModuleConfig, ModuleRepository, NamedModuleConfig,
+    UploadLimits, WASIConfig,
 };
 use particle_protocol::Contact;
-use particle_services::{ParticleAppServices, VIRTUAL_PARTICLE_VAULT_PREFIX};
+use particle_services::{ParticleAppServices, ServiceError, VIRTUAL_PARTICLE_VAULT_PREFIX};
 use peer_metrics::ServicesMetrics;
 use script_storage::ScriptStorageApi;
 use server_config::ServicesConfig;
+use uuid_utils::{uuid, uuid_from_seed};
 
 use crate::debug::fmt_custom_services;
 use crate::error::HostClosureCallError;
-use crate::error::HostClosureCallError::{DecodeBase58, DecodeUTF8};
-use crate::func::{binary, unary};
-use crate::identify::NodeInfo;
+use crate::error::HostClosureCallError::{
+    DecodeBase58, DecodeBase64, DecodeHex, DecodeUTF8, OddLengthHex,
+};
+use crate::external_addresses::{ExternalAddressCommand, ExternalAddressesApi};
+use crate::func::{binary, ternary, unary};
+use crate::identify::{Identify, NodeInfo};
 use crate::outcome::{ok, wrap, wrap_unit};
 use crate::{json, math};
 
@@ -63,6 +71,39 @@ pub struct CustomService {
     pub unhandled: Option<Mutex<ServiceFunction>>,
 }
 
+/// How long `peer ping` waits for a round-trip measurement if `timeout_ms` isn't specified
+const DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Maximum number of bytes `op random_bytes` may return at once
+const MAX_RANDOM_BYTES: usize = 1024;
+
+/// `kad closest_connected` doesn't look beyond that many closest peers for connected ones
+const MAX_CLOSEST_CONNECTED_LOOKUP: usize = 1024;
+
+/// Maximum size of a JSON-encoded value stored by `kad put_record`
+const MAX_RECORD_VALUE_SIZE: usize = 64 * 1024;
+
+/// Defines `builtins_call` dispatching `(service, function)` pairs to their handlers,
+/// and [Builtins::BUILTINS] listing the same pairs, so that the list can't get out of sync
+macro_rules! builtins_table {
+    (
+        $vis:vis async fn $name:ident(&$self:ident, $args:ident, $particle:ident) {
+            $(($service:literal, $function:literal) => $call:expr),* $(,)?
+        }
+    ) => {
+        /// `(service, function)` pairs handled by [Builtins::builtins_call], advertised in `peer identify`
+        pub const BUILTINS: &'static [(&'static str, &'static str)] = &[$(($service, $function)),*];
+
+        $vis async fn $name(&$self, $args: Args, $particle: ParticleParams) -> FunctionOutcome {
+            use Result as R;
+            match ($args.service_id.as_str(), $args.function_name.as_str()) {
+                $(($service, $function) => $call,)*
+                _ => FunctionOutcome::NotDefined { args: $args, params: $particle },
+            }
+        }
+    };
+}
+
 #[derive(Derivative)]
 #[derivative(Debug)]
 pub struct Builtins<C> {
@@ -74,15 +115,22 @@ pub struct Builtins<C> {
     pub local_peer_id: PeerId,
     #[derivative(Debug = "ignore")]
     pub root_keypair: KeyPair,
+    #[derivative(Debug = "ignore")]
+    pub key_manager: KeyManager,
 
     pub modules: ModuleRepository,
     pub services: ParticleAppServices,
-    pub node_info: NodeInfo,
+    pub node_info: RwLock<NodeInfo>,
+    pub external_addresses: ExternalAddressesApi,
 
     #[derivative(Debug(format_with = "fmt_custom_services"))]
     pub custom_services: RwLock<HashMap<String, CustomService>>,
 
     particles_vault_dir: path::PathBuf,
+    max_call_depth: usize,
+    /// Maximum size of a script file read from particle vault
+    max_script_size: u64,
+    start_time: Instant,
 }
 
 impl<C> Builtins<C>
@@ -93,9 +141,11 @@ where
         connectivity: C,
         script_storage: ScriptStorageApi,
         node_info: NodeInfo,
+        external_addresses: ExternalAddressesApi,
         config: ServicesConfig,
         services_metrics: ServicesMetrics,
         root_keypair: KeyPair,
+        key_manager: KeyManager,
     ) -> Self {
         let modules_dir = &config.modules_dir;
         let blueprint_dir = &config.blueprint_dir;
@@ -106,11 +156,19 @@ where
             vault_dir,
             config.max_heap_size,
             config.default_heap_size,
-        );
+        )
+        .with_upload_limits(UploadLimits {
+            max_size: config.module_uploads.max_size.as_u64() as usize,
+            max_uploads: config.module_uploads.max_uploads,
+            max_uploads_per_peer: config.module_uploads.max_uploads_per_peer,
+            timeout: config.module_uploads.timeout,
+        });
         let particles_vault_dir = vault_dir.to_path_buf();
         let management_peer_id = config.management_peer_id;
         let builtins_management_peer_id = config.builtins_management_peer_id;
         let local_peer_id = config.local_peer_id;
+        let max_call_depth = config.max_call_depth;
+        let max_script_size = config.max_script_size.as_u64();
         let services = ParticleAppServices::new(config, modules.clone(), Some(services_metrics));
 
         Self {
@@ -120,22 +178,38 @@ where
             builtins_management_peer_id,
             local_peer_id,
             root_keypair,
+            key_manager,
             modules,
             services,
-            node_info,
+            node_info: RwLock::new(node_info),
+            external_addresses,
             particles_vault_dir,
             custom_services: <_>::default(),
+            max_call_depth,
+            max_script_size,
+            start_time: Instant::now(),
         }
     }
 
     pub async fn call(&self, args: Args, particle: ParticleParams) -> FunctionOutcome {
+        if particle.call_depth > self.max_call_depth {
+            return FunctionOutcome::Err(JError::new(format!(
+                "maximum call depth of {} exceeded on call to {}.{}",
+                self.max_call_depth, args.service_id, args.function_name
+            )));
+        }
+
         let start = Instant::now();
         let result = self.builtins_call(args, particle).await;
         let end = start.elapsed().as_secs();
         match result {
-            FunctionOutcome::NotDefined { args, params } => self
-                .custom_service_call(args, params)
-                .or_else(|args, params| self.call_service(args, params)),
+            FunctionOutcome::NotDefined { args, params } => {
+                match self.nested_custom_service_call(args, params).await {
+                    // WASM service calls are measured in ParticleAppServices
+                    FunctionOutcome::NotDefined { args, params } => self.call_service(args, params),
+                    result => result,
+                }
+            }
             result => {
                 if let Some(metrics) = self.services.metrics.as_ref() {
                     metrics.observe_builtins(result.not_err(), end as f64);
@@ -145,81 +219,151 @@ where
         }
     }
 
-    pub fn custom_service_call(&self, args: Args, particle: ParticleParams) -> FunctionOutcome {
-        if let Some(function) = self
-            .custom_services
-            .read()
-            .get(&args.service_id)
-            .and_then(|fs| {
+    /// Calls a custom service one level deeper than the caller and records its metrics.
+    /// Returns `NotDefined` with the incremented call depth if there's no such custom service
+    async fn nested_custom_service_call(
+        &self,
+        args: Args,
+        mut params: ParticleParams,
+    ) -> FunctionOutcome {
+        // services may call back into builtins, so count how deep we are
+        params.call_depth += 1;
+        let service_id = args.service_id.clone();
+        let start = Instant::now();
+        let result = self.custom_service_call(args, params).await;
+        if !matches!(result, FunctionOutcome::NotDefined { .. }) {
+            let call_time = start.elapsed().as_secs_f64();
+            if let Some(metrics) = self.services.metrics.as_ref() {
+                metrics.observe_custom_service(service_id, result.not_err(), call_time);
+            }
+        }
+        result
+    }
+
+    pub async fn custom_service_call(
+        &self,
+        args: Args,
+        particle: ParticleParams,
+    ) -> FunctionOutcome {
+        // locks are released before the call is awaited, so the function may call back into builtins
+        let call = {
+            let custom_services = self.custom_services.read();
+            let function = custom_services.get(&args.service_id).and_then(|fs| {
                 fs.functions
                     .get(&args.function_name)
                     .or(fs.unhandled.as_ref())
-            })
-        {
-            let mut function = function.lock();
-            async_std::task::block_on(function(args, particle))
-        } else {
-            FunctionOutcome::NotDefined {
-                args,
-                params: particle,
+            });
+            match function {
+                Some(function) => {
+                    let mut function = function.lock();
+                    function(args, particle)
+                }
+                None => {
+                    return FunctionOutcome::NotDefined {
+                        args,
+                        params: particle,
+                    }
+                }
             }
-        }
+        };
+
+        call.await
     }
 
-    // TODO: get rid of all blocking methods (std::fs and such)
-    pub async fn builtins_call(&self, args: Args, particle: ParticleParams) -> FunctionOutcome {
-        use Result as R;
-        #[rustfmt::skip]
-        match (args.service_id.as_str(), args.function_name.as_str()) {
-            ("peer", "identify")              => ok(json!(self.node_info)),
+    /// Removes custom service, returns true if it was registered
+    pub fn remove_custom_service(&self, service_id: &str) -> bool {
+        self.custom_services.write().remove(service_id).is_some()
+    }
+
+    builtins_table! {
+        // TODO: get rid of all blocking methods (std::fs and such)
+        pub async fn builtins_call(&self, args, particle) {
+            ("peer", "identify")              => ok(self.identify().await),
             ("peer", "timestamp_ms")          => ok(json!(now_ms() as u64)),
             ("peer", "timestamp_sec")         => ok(json!(now_sec())),
+            ("peer", "monotonic_ms")          => ok(json!(self.monotonic_ms())),
+            ("peer", "set_external_address")  => wrap_unit(self.set_external_address(args, particle)),
             ("peer", "is_connected")          => wrap(self.is_connected(args).await),
             ("peer", "connect")               => wrap(self.connect(args).await),
             ("peer", "get_contact")           => self.get_contact(args).await,
-            ("peer", "timeout")               => self.timeout(args).await,
+            ("peer", "timeout")               => self.timeout(args, particle).await,
+            ("peer", "ping")                  => self.ping(args).await,
+            ("peer", "init_peer_id")          => ok(json!(particle.init_peer_id.to_string())),
+            ("peer", "particle_id")           => ok(json!(particle.id)),
+            ("peer", "particle_timestamp_ms") => ok(json!(particle.timestamp)),
 
             ("kad", "neighborhood")           => wrap(self.neighborhood(args).await),
             ("kad", "neigh_with_addrs")       => wrap(self.neighborhood_with_addresses(args).await),
+            ("kad", "closest_connected")      => wrap(self.closest_connected(args).await),
             ("kad", "merge")                  => wrap(self.kad_merge(args.function_args)),
+            ("kad", "put_record")             => wrap_unit(self.put_record(args).await),
+            ("kad", "get_record")             => wrap(self.get_record(args).await),
+            ("kad", "start_providing")        => wrap_unit(self.start_providing(args).await),
+            ("kad", "get_providers")          => wrap(self.get_providers(args).await),
 
             ("srv", "list")                   => ok(self.list_services()),
             ("srv", "create")                 => wrap(self.create_service(args, particle)),
             ("srv", "get_interface")          => wrap(self.get_interface(args)),
             ("srv", "resolve_alias")          => wrap(self.resolve_alias(args)),
             ("srv", "add_alias")              => wrap_unit(self.add_alias(args, particle)),
+            ("srv", "list_aliases")           => wrap(self.list_aliases(args, particle)),
+            ("srv", "rename_alias")           => wrap_unit(self.rename_alias(args, particle)),
             ("srv", "remove")                 => wrap_unit(self.remove_service(args, particle)),
+            ("srv", "last_error")             => wrap(self.last_service_error(args)),
+            ("srv", "restart")                => wrap_unit(self.restart_service(args, particle)),
+            ("srv", "call_with_timeout")      => self.call_with_timeout(args, particle).await,
 
             ("dist", "add_module_from_vault") => wrap(self.add_module_from_vault(args, particle)),
             ("dist", "add_module")            => wrap(self.add_module(args)),
+            ("dist", "begin_module_upload")   => wrap(self.begin_module_upload(particle)),
+            ("dist", "append_module_chunk")   => wrap_unit(self.append_module_chunk(args, particle)),
+            ("dist", "finish_module_upload")  => wrap(self.finish_module_upload(args, particle)),
             ("dist", "add_blueprint")         => wrap(self.add_blueprint(args)),
             ("dist", "make_module_config")    => wrap(make_module_config(args)),
+            ("dist", "validate_module_config") => wrap_unit(self.validate_module_config(args)),
             ("dist", "load_module_config")    => wrap(self.load_module_config_from_vault(args, particle)),
             ("dist", "default_module_config") => wrap(self.default_module_config(args)),
             ("dist", "make_blueprint")        => wrap(self.make_blueprint(args)),
             ("dist", "load_blueprint")        => wrap(self.load_blueprint_from_vault(args, particle)),
             ("dist", "list_modules")          => wrap(self.list_modules()),
             ("dist", "get_module_interface")  => wrap(self.get_module_interface(args)),
+            ("dist", "get_module_config")     => wrap(self.get_module_config(args)),
             ("dist", "list_blueprints")       => wrap(self.get_blueprints()),
+            ("dist", "remove_module")         => wrap_unit(self.remove_module(args, particle)),
+            ("dist", "remove_blueprint")      => wrap_unit(self.remove_blueprint(args, particle)),
 
             ("script", "add")                 => wrap(self.add_script_from_arg(args, particle)),
             ("script", "add_from_vault")      => wrap(self.add_script_from_vault(args, particle)),
             ("script", "remove")              => wrap(self.remove_script(args, particle).await),
+            ("script", "update")              => wrap(self.update_script(args, particle).await),
             ("script", "list")                => wrap(self.list_scripts().await),
 
             ("op", "noop")                    => FunctionOutcome::Empty,
             ("op", "array")                   => ok(Array(args.function_args)),
             ("op", "array_length")            => wrap(self.array_length(args.function_args)),
+            ("op", "array_zip")               => wrap(self.array_zip(args)),
+            ("op", "array_chunk")             => wrap(self.array_chunk(args)),
+            ("op", "array_pluck")             => wrap(self.array_pluck(args)),
             ("op", "concat")                  => wrap(self.concat(args.function_args)),
             ("op", "string_to_b58")           => wrap(self.string_to_b58(args.function_args)),
             ("op", "string_from_b58")         => wrap(self.string_from_b58(args.function_args)),
             ("op", "bytes_from_b58")          => wrap(self.bytes_from_b58(args.function_args)),
             ("op", "bytes_to_b58")            => wrap(self.bytes_to_b58(args.function_args)),
+            ("op", "string_to_b64")           => wrap(self.string_to_b64(args.function_args)),
+            ("op", "string_from_b64")         => wrap(self.string_from_b64(args.function_args)),
+            ("op", "bytes_from_b64")          => wrap(self.bytes_from_b64(args.function_args)),
+            ("op", "bytes_to_b64")            => wrap(self.bytes_to_b64(args.function_args)),
+            ("op", "bytes_from_hex")          => wrap(self.bytes_from_hex(args.function_args)),
+            ("op", "bytes_to_hex")            => wrap(self.bytes_to_hex(args.function_args)),
             ("op", "sha256_string")           => wrap(self.sha256_string(args.function_args)),
             ("op", "concat_strings")          => wrap(self.concat_strings(args.function_args)),
             ("op", "identity")                => self.identity(args.function_args),
+            ("op", "random_bytes")            => unary(args, |n: i64| -> R<Vec<u8>, _> { random_bytes(n) }),
+            ("op", "uuid")                    => wrap(self.uuid(args.function_args)),
+            ("op", "sleep")                   => self.sleep(args, particle).await,
 
             ("debug", "stringify")            => self.stringify(args.function_args),
+            ("debug", "log")                  => wrap_unit(self.log(args, particle)),
 
             ("stat", "service_memory") => unary(args, |id: String| -> R<Vec<JValue>, _> { self.services.get_service_mem_stats(id) }),
             ("stat", "service_stat")   => wrap(self.service_stat(args)),
@@ -232,48 +376,88 @@ where
             ("math", "rem")        => binary(args, |x: i64, y: i64| -> R<i64, _> { math::rem(x, y) }),
             ("math", "pow")        => binary(args, |x: i64, y: u32| -> R<i64, _> { math::pow(x, y) }),
             ("math", "log")        => binary(args, |x: i64, y: i64| -> R<u32, _> { math::log(x, y) }),
+            ("math", "gcd")        => binary(args, |x: i64, y: i64| -> R<i64, _> { math::gcd(x, y) }),
+            ("math", "lcm")        => binary(args, |x: i64, y: i64| -> R<i64, _> { math::lcm(x, y) }),
+            ("math", "clamp")      => ternary(args, |x: i64, lo: i64, hi: i64| -> R<i64, _> { math::clamp(x, lo, hi) }),
 
             ("cmp", "gt")          => binary(args, |x: i64, y: i64| -> R<bool, _> { math::gt(x, y) }),
             ("cmp", "gte")         => binary(args, |x: i64, y: i64| -> R<bool, _> { math::gte(x, y) }),
             ("cmp", "lt")          => binary(args, |x: i64, y: i64| -> R<bool, _> { math::lt(x, y) }),
             ("cmp", "lte")         => binary(args, |x: i64, y: i64| -> R<bool, _> { math::lte(x, y) }),
             ("cmp", "cmp")         => binary(args, |x: i64, y: i64| -> R<i8, _> { math::cmp(x, y) }),
+            ("cmp", "eq")          => binary(args, |x: JValue, y: JValue| -> R<bool, _> { math::eq(x, y) }),
+            ("cmp", "neq")         => binary(args, |x: JValue, y: JValue| -> R<bool, _> { math::neq(x, y) }),
+
+            ("bits", "and")        => binary(args, |x: i64, y: i64| -> R<i64, _> { math::bit_and(x, y) }),
+            ("bits", "or")         => binary(args, |x: i64, y: i64| -> R<i64, _> { math::bit_or(x, y) }),
+            ("bits", "xor")        => binary(args, |x: i64, y: i64| -> R<i64, _> { math::bit_xor(x, y) }),
+            ("bits", "not")        => unary(args, |x: i64| -> R<i64, _> { math::bit_not(x) }),
+            ("bits", "shl")        => binary(args, |x: i64, n: u32| -> R<i64, _> { math::shl(x, n) }),
+            ("bits", "shr")        => binary(args, |x: i64, n: u32| -> R<i64, _> { math::shr(x, n) }),
 
             ("array", "sum")       => unary(args, |xs: Vec<i64> | -> R<i64, _> { math::array_sum(xs) }),
             ("array", "dedup")     => unary(args, |xs: Vec<String>| -> R<Vec<String>, _> { math::dedup(xs) }),
             ("array", "intersect") => binary(args, |xs: HashSet<String>, ys: HashSet<String>| -> R<Vec<String>, _> { math::intersect(xs, ys) }),
             ("array", "diff")      => binary(args, |xs: HashSet<String>, ys: HashSet<String>| -> R<Vec<String>, _> { math::diff(xs, ys) }),
             ("array", "sdiff")     => binary(args, |xs: HashSet<String>, ys: HashSet<String>| -> R<Vec<String>, _> { math::sdiff(xs, ys) }),
-            ("array", "slice")     => wrap(self.array_slice(args.function_args)),
+            ("array", "slice")     => ternary(args, |array: JValue, start: JValue, end: JValue| -> R<JValue, _> { self.array_slice(array, start, end) }),
             ("array", "length")    => wrap(self.array_length(args.function_args)),
 
-            ("sig", "sign")        => wrap(self.sign(args)),
-            ("sig", "verify")      => wrap(self.verify(args)),
-            ("sig", "get_peer_id") => wrap(self.get_peer_id()),
+            ("sig", "sign")            => wrap(self.sign(args)),
+            ("sig", "verify")          => wrap(self.verify(args)),
+            ("sig", "get_peer_id")     => wrap(self.get_peer_id()),
+            ("sig", "get_peer_id_for") => wrap(self.get_peer_id_for(args)),
 
             ("json", "obj")        => wrap(json::obj(args)),
             ("json", "put")        => wrap(json::put(args)),
             ("json", "puts")       => wrap(json::puts(args)),
             ("json", "parse")      => unary(args, |s: String| -> R<JValue, _> { json::parse(&s) }),
             ("json", "stringify")  => unary(args, |v: JValue| -> R<String, _> { Ok(json::stringify(v)) }),
-
-            _                      => FunctionOutcome::NotDefined { args, params: particle },
         }
     }
 
+    async fn identify(&self) -> JValue {
+        let connected_peers = self.connection_pool().count_connections().await;
+
+        json!(Identify {
+            node_info: &self.node_info.read(),
+            uptime_sec: self.start_time.elapsed().as_secs(),
+            connected_peers,
+            builtins: Self::BUILTINS,
+        })
+    }
+
     async fn neighbor_peers(&self, args: Args) -> Result<Vec<PeerId>, JError> {
         let mut args = args.function_args.into_iter();
         let key = from_base58("key", &mut args)?;
         let already_hashed: Option<bool> = Args::next_opt("already_hashed", &mut args)?;
         let count: Option<usize> = Args::next_opt("count", &mut args)?;
         let count = count.unwrap_or_else(|| K_VALUE.get());
+        let exclude_self: Option<bool> = Args::next_opt("exclude_self", &mut args)?;
+        let only_connected: Option<bool> = Args::next_opt("only_connected", &mut args)?;
 
         let key = if already_hashed == Some(true) {
             MultihashGeneric::from_bytes(&key)?
         } else {
             Code::Sha2_256.digest(&key)
         };
-        let neighbors = self.kademlia().neighborhood(key, count).await?;
+        let mut neighbors = self.kademlia().neighborhood(key, count).await?;
+
+        if exclude_self == Some(true) {
+            neighbors.retain(|peer| peer != &self.local_peer_id);
+        }
+
+        if only_connected == Some(true) {
+            let connected = neighbors
+                .iter()
+                .map(|peer| self.connection_pool().is_connected(*peer));
+            let connected = futures::future::join_all(connected).await;
+            neighbors = neighbors
+                .into_iter()
+                .zip(connected)
+                .filter_map(|(peer, connected)| connected.then_some(peer))
+                .collect();
+        }
 
         Ok(neighbors)
     }
@@ -310,6 +494,99 @@ where
         Ok(neighbors)
     }
 
+    /// Returns up to `count` peers closest to `key` that are currently connected, with addresses
+    async fn closest_connected(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let key = from_base58("key", &mut args)?;
+        let count: Option<usize> = Args::next_opt("count", &mut args)?;
+        let count = count.unwrap_or_else(|| K_VALUE.get());
+        let key = Code::Sha2_256.digest(&key);
+
+        // disconnected peers are skipped, so look further into the neighborhood to fill the count
+        let mut lookup = count;
+        loop {
+            let neighbors = self.kademlia().neighborhood(key, lookup).await?;
+            let exhausted = neighbors.len() < lookup;
+            let contacts = neighbors
+                .into_iter()
+                .map(|peer| self.connection_pool().get_contact(peer));
+            let connected = futures::future::join_all(contacts).await;
+            let connected: Vec<_> = connected.into_iter().flatten().take(count).collect();
+
+            if connected.len() == count || exhausted || lookup >= MAX_CLOSEST_CONNECTED_LOOKUP {
+                let connected = connected
+                    .into_iter()
+                    .map(|contact| {
+                        json!({
+                            "peer_id": contact.peer_id.to_string(),
+                            "addresses": contact.addresses,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                return Ok(json!(connected));
+            }
+
+            lookup = lookup.saturating_mul(2);
+        }
+    }
+
+    /// Stores JSON value in the DHT under `key`. Value is replicated to the closest peers
+    async fn put_record(&self, args: Args) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let key: String = Args::next("key", &mut args)?;
+        let value: JValue = Args::next("value", &mut args)?;
+        let ttl_sec: Option<u64> = Args::next_opt("ttl_sec", &mut args)?;
+
+        let value = serde_json::to_vec(&value)?;
+        if value.len() > MAX_RECORD_VALUE_SIZE {
+            return Err(JError::new(format!(
+                "record value is {} bytes, max allowed is {} bytes",
+                value.len(),
+                MAX_RECORD_VALUE_SIZE
+            )));
+        }
+
+        let ttl = ttl_sec.map(Duration::from_secs);
+        self.kademlia()
+            .put_record(key.into_bytes(), value, ttl)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns JSON value stored in the DHT under `key`, or null if there's none
+    async fn get_record(&self, args: Args) -> Result<JValue, JError> {
+        let key: String = Args::next("key", &mut args.function_args.into_iter())?;
+
+        let value = self.kademlia().get_record(key.into_bytes()).await?;
+        let value = match value {
+            Some(value) => serde_json::from_slice(&value)?,
+            None => JValue::Null,
+        };
+
+        Ok(value)
+    }
+
+    /// Announces this peer as a provider of `key`, e.g. of a blueprint or a service
+    async fn start_providing(&self, args: Args) -> Result<(), JError> {
+        let key: String = Args::next("key", &mut args.function_args.into_iter())?;
+        self.kademlia().start_providing(key.into_bytes()).await?;
+
+        Ok(())
+    }
+
+    /// Returns ids of peers providing `key`
+    async fn get_providers(&self, args: Args) -> Result<JValue, JError> {
+        let key: String = Args::next("key", &mut args.function_args.into_iter())?;
+        let providers = self.kademlia().get_providers(key.into_bytes()).await?;
+        let providers = providers
+            .into_iter()
+            .map(|peer| peer.to_base58())
+            .collect::<Vec<_>>();
+
+        Ok(json!(providers))
+    }
+
     async fn is_connected(&self, args: Args) -> Result<JValue, JError> {
         let peer: String = Args::next("peer_id", &mut args.function_args.into_iter())?;
         let peer = PeerId::from_str(peer.as_str())?;
@@ -340,6 +617,24 @@ where
         }
     }
 
+    async fn ping(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+
+        let peer_id: String = Args::next("peer_id", &mut args)?;
+        let peer_id = PeerId::from_str(peer_id.as_str())?;
+        let timeout_ms: Option<u64> = Args::next_opt("timeout_ms", &mut args)?;
+        let timeout = timeout_ms.map_or(DEFAULT_PING_TIMEOUT, Duration::from_millis);
+
+        let rtt = self.connection_pool().ping(peer_id, timeout).await;
+        match rtt {
+            Some(rtt) => Ok(json!(rtt.as_millis() as u64)),
+            None => Err(JError::new(format!(
+                "ping to {peer_id} failed: peer isn't connected or didn't respond within {}ms",
+                timeout.as_millis()
+            ))),
+        }
+    }
+
     fn add_script_from_arg(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
         let script: String = Args::next("script", &mut args)?;
@@ -367,11 +662,13 @@ where
         let delay = delay.map(Duration::from_secs);
         let delay = get_delay(delay, interval);
 
+        let max_failures = parse_from_str("max_failures", &mut args)?;
+
         let creator = params.init_peer_id;
 
         let id = self
             .script_storage
-            .add_script(script, interval, delay, creator)?;
+            .add_script(script, interval, delay, creator, max_failures)?;
 
         Ok(json!(id))
     }
@@ -382,8 +679,12 @@ where
         particle_id: &str,
     ) -> Result<String, JError> {
         let resolved_path = resolve_vault_path(&self.particles_vault_dir, path, particle_id)?;
-        std::fs::read_to_string(resolved_path)
-            .map_err(|_| JError::new(format!("Error reading script file `{}`", path.display())))
+        read_script(&resolved_path, self.max_script_size).map_err(|err| {
+            JError::new(format!(
+                "Error reading script file `{}`: {err}",
+                path.display()
+            ))
+        })
     }
 
     async fn remove_script(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
@@ -402,6 +703,26 @@ where
         Ok(json!(ok))
     }
 
+    async fn update_script(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+
+        let force = params.init_peer_id == self.management_peer_id;
+
+        let uuid: String = Args::next("uuid", &mut args)?;
+        let interval = parse_from_str("interval_sec", &mut args)?;
+        let interval = interval.map(Duration::from_secs);
+        let delay = parse_from_str("delay_sec", &mut args)?;
+        let delay = delay.map(Duration::from_secs);
+        let actor = params.init_peer_id;
+
+        let ok = self
+            .script_storage
+            .update_script(uuid, interval, delay, actor, force)
+            .await?;
+
+        Ok(json!(ok))
+    }
+
     async fn list_scripts(&self) -> Result<JValue, JError> {
         let scripts = self.script_storage.list_scripts().await?;
 
@@ -422,7 +743,9 @@ where
         ))
     }
 
-    async fn timeout(&self, args: Args) -> FunctionOutcome {
+    /// Sleeps for `duration_ms`, but no longer than the particle's remaining TTL
+    /// Then returns optional `message`
+    async fn timeout(&self, args: Args, params: ParticleParams) -> FunctionOutcome {
         use async_std::future;
         use std::future::pending;
 
@@ -431,7 +754,8 @@ where
         let dur_field = "duration_ms";
         let duration = parse_from_str(dur_field, &mut args)?;
         let duration = duration.ok_or(ArgsError::MissingField(dur_field))?;
-        let duration = Duration::from_millis(duration);
+        // there's no point in sleeping after particle has expired
+        let duration = Duration::from_millis(duration).min(params.time_to_live());
 
         let message = Args::next_opt("message", &mut args)?;
 
@@ -443,6 +767,69 @@ where
             .unwrap_or(FunctionOutcome::Empty)
     }
 
+    /// Returns a random v4 UUID
+    /// If optional `seed` bytes are passed, returns a deterministic v5 UUID derived from them
+    fn uuid(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let seed: Option<Vec<u8>> = Args::next_opt("seed", &mut args)?;
+        let uuid = match seed {
+            Some(seed) => uuid_from_seed(&seed),
+            None => uuid(),
+        };
+        Ok(JValue::String(uuid))
+    }
+
+    /// Sleeps for `duration_ms`, but no longer than the particle's remaining TTL
+    async fn sleep(&self, args: Args, params: ParticleParams) -> FunctionOutcome {
+        let mut args = args.function_args.into_iter();
+        let duration: u64 = Args::next("duration_ms", &mut args)?;
+        let duration = Duration::from_millis(duration).min(params.time_to_live());
+
+        async_std::task::sleep(duration).await;
+
+        FunctionOutcome::Empty
+    }
+
+    /// Milliseconds elapsed since node start, measured by a monotonic clock
+    ///
+    /// Unlike `peer.timestamp_ms`, it never goes backwards on system clock adjustments,
+    /// so it's suitable for measuring intervals on a single node. Values aren't comparable
+    /// across nodes or restarts, use `peer.timestamp_ms` for absolute time.
+    fn monotonic_ms(&self) -> u64 {
+        self.start_time.elapsed().as_millis() as u64
+    }
+
+    /// Adds or removes (if `remove` is true) an external address advertised by the node
+    fn set_external_address(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        if params.init_peer_id != self.management_peer_id {
+            return Err(JError::new(format!(
+                "only management peer id can set external addresses, {} is not",
+                params.init_peer_id
+            )));
+        }
+
+        let mut args = args.function_args.into_iter();
+        let maddr: Multiaddr = Args::next("multiaddr", &mut args)?;
+        let remove: Option<bool> = Args::next_opt("remove", &mut args)?;
+
+        let mut node_info = self.node_info.write();
+        let addresses = &mut node_info.external_addresses;
+        if remove == Some(true) {
+            addresses.retain(|a| a != &maddr);
+            self.external_addresses
+                .send(ExternalAddressCommand::Remove(maddr))?;
+        } else {
+            self.external_addresses.validate(&maddr)?;
+            if !addresses.contains(&maddr) {
+                addresses.push(maddr.clone());
+            }
+            self.external_addresses
+                .send(ExternalAddressCommand::Add(maddr))?;
+        }
+
+        Ok(())
+    }
+
     fn string_to_b58(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
         let mut args = args.into_iter();
         let string: String = Args::next("string", &mut args)?;
@@ -474,6 +861,66 @@ where
         Ok(JValue::String(string))
     }
 
+    fn string_to_b64(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let string: String = Args::next("string", &mut args)?;
+        let b64 = base64_engine(false).encode(string);
+        Ok(JValue::String(b64))
+    }
+
+    /// Attempts to decode UTF8 string from a given base64 string
+    /// May fail at base64 decoding and on UTF8 decoding
+    fn string_from_b64(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let string: String = Args::next("b64_string", &mut args)?;
+        let vec = base64_engine(false).decode(string).map_err(DecodeBase64)?;
+        let string = String::from_utf8(vec).map_err(DecodeUTF8)?;
+        Ok(JValue::String(string))
+    }
+
+    /// Decodes bytes from a given base64 string
+    /// Uses URL-safe alphabet if optional `url_safe` argument is `true`
+    fn bytes_from_b64(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let string: String = Args::next("b64_string", &mut args)?;
+        let url_safe = Args::next_opt("url_safe", &mut args)?.unwrap_or(false);
+        let vec = base64_engine(url_safe)
+            .decode(string)
+            .map_err(DecodeBase64)?;
+        Ok(json!(vec))
+    }
+
+    /// Encodes bytes to base64 string
+    /// Uses URL-safe alphabet if optional `url_safe` argument is `true`
+    fn bytes_to_b64(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let bytes: Vec<u8> = Args::next("bytes", &mut args)?;
+        let url_safe = Args::next_opt("url_safe", &mut args)?.unwrap_or(false);
+        let string = base64_engine(url_safe).encode(bytes);
+        Ok(JValue::String(string))
+    }
+
+    /// Decodes bytes from a given hex string, both lowercase and uppercase digits are accepted
+    /// Fails on odd-length strings and on non-hex characters
+    fn bytes_from_hex(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let string: String = Args::next("hex_string", &mut args)?;
+        if string.len() % 2 != 0 {
+            return Err(OddLengthHex(string.len()).into());
+        }
+        let mut vec = vec![0u8; string.len() / 2];
+        faster_hex::hex_decode(string.as_bytes(), &mut vec).map_err(DecodeHex)?;
+        Ok(json!(vec))
+    }
+
+    /// Encodes bytes to lowercase hex string
+    fn bytes_to_hex(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
+        let mut args = args.into_iter();
+        let bytes: Vec<u8> = Args::next("bytes", &mut args)?;
+        let string = faster_hex::hex_string(&bytes);
+        Ok(JValue::String(string))
+    }
+
     /// Returns SHA256 of the passed string
     /// Accepts 3 arguments:
     /// `string` – string to hash
@@ -559,6 +1006,26 @@ where
         FunctionOutcome::Ok(JValue::String(debug))
     }
 
+    /// Writes message to the node log, tagged with particle id
+    /// Only management peer id may log at `warn` and `error` levels
+    fn log(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let level: String = Args::next("level", &mut args)?;
+        let message: String = Args::next("message", &mut args)?;
+
+        let level = parse_log_level(&level)?;
+        if level <= log::Level::Warn && params.init_peer_id != self.management_peer_id {
+            return Err(JError::new(format!(
+                "only management peer id can log at {} level, {} is not",
+                level, params.init_peer_id
+            )));
+        }
+
+        log::log!(level, "Particle {}: {}", params.id, message);
+
+        Ok(())
+    }
+
     /// Flattens an array of arrays
     fn concat(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
         let flattened: Vec<JValue> =
@@ -606,17 +1073,99 @@ where
         }
     }
 
-    /// takes a range of values from an array
-    /// slice(array: []JValue, start: usize, end: usize) -> []JValue
-    fn array_slice(&self, args: Vec<serde_json::Value>) -> Result<JValue, JError> {
-        let (array, start, end) = if let [array, start, end] = &args[..] {
-            (array, start, end)
-        } else {
+    /// Pairs up elements of two arrays, truncating to the shorter one unless `strict` is set
+    /// zip(a: []JValue, b: []JValue, strict: ?bool) -> [][JValue, JValue]
+    fn array_zip(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let a: JValue = Args::next("a", &mut args)?;
+        let b: JValue = Args::next("b", &mut args)?;
+        let strict: Option<bool> = Args::next_opt("strict", &mut args)?;
+
+        let (JValue::Array(a), JValue::Array(b)) = (a, b) else {
+            return Err(JError::new("both arguments of op array_zip must be arrays"));
+        };
+        if strict == Some(true) && a.len() != b.len() {
+            return Err(JError::new(format!(
+                "op array_zip arrays must have equal length in strict mode: {} and {} found",
+                a.len(),
+                b.len()
+            )));
+        }
+
+        let pairs = a.into_iter().zip(b).map(|(a, b)| json!([a, b])).collect();
+
+        Ok(JValue::Array(pairs))
+    }
+
+    /// Splits an array into chunks of `size` elements, the last chunk may be smaller
+    /// chunk(array: []JValue, size: i64) -> [][]JValue
+    fn array_chunk(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let array: JValue = Args::next("array", &mut args)?;
+        let size: i64 = Args::next("size", &mut args)?;
+
+        let JValue::Array(array) = array else {
+            return Err(JError::new(
+                "op array_chunk's first argument must be an array",
+            ));
+        };
+        if size <= 0 {
+            return Err(JError::new(format!(
+                "op array_chunk's size must be positive, was {size}"
+            )));
+        }
+
+        let chunks = array
+            .chunks(size as usize)
+            .map(|chunk| JValue::Array(chunk.to_vec()))
+            .collect();
+
+        Ok(JValue::Array(chunks))
+    }
+
+    /// Takes value at `key` from each object in the array. Elements that aren't objects or
+    /// lack the key are skipped, or cause an error if `strict` is set
+    /// pluck(array: []JValue, key: string, strict: ?bool) -> []JValue
+    fn array_pluck(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let array: JValue = Args::next("array", &mut args)?;
+        let key: String = Args::next("key", &mut args)?;
+        let strict: Option<bool> = Args::next_opt("strict", &mut args)?;
+        let strict = strict == Some(true);
+
+        let JValue::Array(array) = array else {
             return Err(JError::new(
-                "invalid number of parameters. need array, start index and end index",
+                "op array_pluck's first argument must be an array",
             ));
         };
 
+        let mut values = Vec::with_capacity(array.len());
+        for (i, element) in array.into_iter().enumerate() {
+            match element {
+                JValue::Object(mut object) => match object.remove(&key) {
+                    Some(value) => values.push(value),
+                    None if strict => {
+                        return Err(JError::new(format!(
+                            "op array_pluck: element #{i} has no key '{key}'"
+                        )))
+                    }
+                    None => {}
+                },
+                _ if strict => {
+                    return Err(JError::new(format!(
+                        "op array_pluck: element #{i} is not an object"
+                    )))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(JValue::Array(values))
+    }
+
+    /// takes a range of values from an array
+    /// slice(array: []JValue, start: usize, end: usize) -> []JValue
+    fn array_slice(&self, array: JValue, start: JValue, end: JValue) -> Result<JValue, JError> {
         let array = match array {
             JValue::Array(arr) if arr.is_empty() => return Ok(json!([])),
             JValue::Array(arr) => arr,
@@ -660,12 +1209,58 @@ where
         let mut args = args.function_args.into_iter();
         let module_bytes: String = Args::next("module_bytes", &mut args)?;
         let config = Args::next("config", &mut args)?;
+        let compression: Option<String> = Args::next_opt("compression", &mut args)?;
+
+        let module_hash = match compression {
+            Some(compression) => {
+                let compression: ModuleCompression = compression.parse()?;
+                self.modules
+                    .add_compressed_module_base64(module_bytes, config, compression)?
+            }
+            None => self.modules.add_module_base64(module_bytes, config)?,
+        };
+
+        Ok(JValue::String(module_hash))
+    }
+
+    fn begin_module_upload(&self, params: ParticleParams) -> Result<JValue, JError> {
+        let upload_id = self.modules.begin_module_upload(params.init_peer_id)?;
+        Ok(JValue::String(upload_id))
+    }
+
+    fn append_module_chunk(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let upload_id: String = Args::next("upload_id", &mut args)?;
+        let index: u32 = Args::next("index", &mut args)?;
+        let chunk: String = Args::next("chunk", &mut args)?;
+
+        self.modules
+            .append_module_chunk(params.init_peer_id, &upload_id, index, chunk)?;
+
+        Ok(())
+    }
+
+    fn finish_module_upload(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let upload_id: String = Args::next("upload_id", &mut args)?;
+        let config = Args::next("config", &mut args)?;
 
-        let module_hash = self.modules.add_module_base64(module_bytes, config)?;
+        let module_hash =
+            self.modules
+                .finish_module_upload(params.init_peer_id, &upload_id, config)?;
 
         Ok(JValue::String(module_hash))
     }
 
+    fn validate_module_config(&self, args: Args) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let config = Args::next("config", &mut args)?;
+
+        self.modules.validate_module_config(&config)?;
+
+        Ok(())
+    }
+
     fn add_module_from_vault(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
         let module_path: String = Args::next("module_path", &mut args)?;
@@ -686,6 +1281,50 @@ where
         Ok(JValue::String(blueprint_id))
     }
 
+    fn remove_module(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        if params.init_peer_id != self.management_peer_id {
+            return Err(JError::new(format!(
+                "only management peer id can remove modules, {} is not",
+                params.init_peer_id
+            )));
+        }
+
+        let mut args = args.function_args.into_iter();
+        let module_hash: String = Args::next("module_hash", &mut args)?;
+        self.modules.remove_module(&module_hash)?;
+
+        Ok(())
+    }
+
+    fn remove_blueprint(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        if params.init_peer_id != self.management_peer_id {
+            return Err(JError::new(format!(
+                "only management peer id can remove blueprints, {} is not",
+                params.init_peer_id
+            )));
+        }
+
+        let mut args = args.function_args.into_iter();
+        let blueprint_id: String = Args::next("blueprint_id", &mut args)?;
+
+        let services: Vec<_> = self
+            .services
+            .list_services_with_blueprints()
+            .into_iter()
+            .filter(|(_, bp)| bp == &blueprint_id)
+            .map(|(service_id, _)| service_id)
+            .collect();
+        if !services.is_empty() {
+            return Err(JError::new(format!(
+                "blueprint {blueprint_id} can't be removed: it is used by services {services:?}"
+            )));
+        }
+
+        self.modules.remove_blueprint(&blueprint_id)?;
+
+        Ok(())
+    }
+
     fn load_module_config_from_vault(
         &self,
         args: Args,
@@ -763,7 +1402,17 @@ where
         self.modules.get_interface(&hash)
     }
 
-    fn get_blueprints(&self) -> Result<JValue, JError> {
+    fn get_module_config(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let hash: String = Args::next("hex_hash", &mut args)?;
+        let config = self.modules.get_module_config(&hash)?;
+        let config = serde_json::to_value(config)
+            .map_err(|err| JError::new(format!("Error serializing config to JSON: {err}")))?;
+
+        Ok(config)
+    }
+
+    fn get_blueprints(&self) -> Result<JValue, JError> {
         self.modules
             .get_blueprints()
             .into_iter()
@@ -778,6 +1427,12 @@ where
     fn create_service(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
         let blueprint_id: String = Args::next("blueprint_id", &mut args)?;
+        let dry_run: Option<bool> = Args::next_opt("dry_run", &mut args)?;
+
+        if dry_run.unwrap_or(false) {
+            self.services.create_service_dry_run(&blueprint_id)?;
+            return Ok(json!({ "ok": true }));
+        }
 
         let service_id = self
             .services
@@ -794,6 +1449,14 @@ where
         Ok(())
     }
 
+    fn restart_service(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+        let service_id_or_alias: String = Args::next("service_id_or_alias", &mut args)?;
+        self.services
+            .restart_service(service_id_or_alias, params.init_peer_id)?;
+        Ok(())
+    }
+
     fn list_services(&self) -> JValue {
         JValue::Array(self.services.list_services())
     }
@@ -802,6 +1465,59 @@ where
         self.services.call_service(function_args, particle)
     }
 
+    /// Calls `function_name` of a service with `args`, failing if it doesn't complete
+    /// in `timeout_ms`. Timeout is capped by the particle's remaining TTL.
+    ///
+    /// A WASM service call can't be interrupted: it keeps running on a blocking thread
+    /// and holds the service until it finishes, but the particle doesn't wait for it
+    async fn call_with_timeout(&self, args: Args, params: ParticleParams) -> FunctionOutcome {
+        let mut function_args = args.function_args.into_iter();
+        let service_id: String = Args::next("service_id", &mut function_args)?;
+        let function_name: String = Args::next("function_name", &mut function_args)?;
+        let call_args: Vec<JValue> = Args::next("args", &mut function_args)?;
+        let timeout_ms: u64 = Args::next("timeout_ms", &mut function_args)?;
+        let timeout = Duration::from_millis(timeout_ms).min(params.time_to_live());
+
+        // tetraplets of the `args` array apply to each of its elements
+        let tetraplets = args.tetraplets.get(2).cloned().unwrap_or_default();
+        let call = Args {
+            service_id: service_id.clone(),
+            function_name: function_name.clone(),
+            tetraplets: vec![tetraplets; call_args.len()],
+            function_args: call_args,
+        };
+        // same path as a direct call from AquaVM, so call depth and metrics are accounted for
+        let call = async {
+            match self.nested_custom_service_call(call, params).await {
+                FunctionOutcome::NotDefined { args, params } => {
+                    let services = self.services.clone();
+                    async_std::task::spawn_blocking(move || services.call_service(args, params))
+                        .await
+                }
+                result => result,
+            }
+        };
+
+        match async_std::future::timeout(timeout, call).await {
+            Ok(FunctionOutcome::NotDefined { args, .. }) => {
+                let err = ServiceError::NoSuchServiceWithFunction {
+                    service: args.service_id,
+                    function: args.function_name,
+                };
+                FunctionOutcome::Err(err.into())
+            }
+            Ok(result) => result,
+            Err(_) => {
+                let err = ServiceError::CallTimedOut {
+                    service: service_id,
+                    function: function_name,
+                    timeout_ms: timeout.as_millis() as u64,
+                };
+                FunctionOutcome::Err(err.into())
+            }
+        }
+    }
+
     fn get_interface(&self, args: Args) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
         let service_id: String = Args::next("service_id", &mut args)?;
@@ -818,6 +1534,27 @@ where
         Ok(())
     }
 
+    fn list_aliases(&self, args: Args, params: ParticleParams) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+
+        let service_id: String = Args::next("service_id", &mut args)?;
+        let aliases = self
+            .services
+            .list_aliases(service_id, params.init_peer_id)?;
+
+        Ok(json!(aliases))
+    }
+
+    fn rename_alias(&self, args: Args, params: ParticleParams) -> Result<(), JError> {
+        let mut args = args.function_args.into_iter();
+
+        let old: String = Args::next("old", &mut args)?;
+        let new: String = Args::next("new", &mut args)?;
+        self.services.rename_alias(old, new, params.init_peer_id)?;
+
+        Ok(())
+    }
+
     fn resolve_alias(&self, args: Args) -> Result<JValue, JError> {
         let mut args = args.function_args.into_iter();
 
@@ -827,6 +1564,16 @@ where
         Ok(JValue::String(service_id))
     }
 
+    /// Returns the most recent call error of a service, or null if it never failed
+    fn last_service_error(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+
+        let service_id: String = Args::next("service_id", &mut args)?;
+        let last_error = self.services.last_error(service_id)?;
+
+        Ok(json!(last_error))
+    }
+
     fn kademlia(&self) -> &KademliaApi {
         self.connectivity.as_ref()
     }
@@ -926,6 +1673,21 @@ where
     fn get_peer_id(&self) -> Result<JValue, JError> {
         Ok(JValue::String(self.root_keypair.get_peer_id().to_base58()))
     }
+
+    /// Returns peer id of the worker keypair issued for `key_id`
+    fn get_peer_id_for(&self, args: Args) -> Result<JValue, JError> {
+        let mut args = args.function_args.into_iter();
+        let key_id: String = Args::next("key_id", &mut args)?;
+        let key_id = PeerId::from_str(&key_id)
+            .map_err(|err| JError::new(format!("invalid key_id '{key_id}': {err}")))?;
+
+        let peer_id = self
+            .key_manager
+            .find_scope_peer_id(key_id)
+            .ok_or_else(|| JError::new(format!("keypair for key_id {key_id} not found")))?;
+
+        Ok(JValue::String(peer_id.to_base58()))
+    }
 }
 
 fn make_module_config(args: Args) -> Result<JValue, JError> {
@@ -1019,6 +1781,14 @@ where
         })
 }
 
+fn base64_engine(url_safe: bool) -> &'static GeneralPurpose {
+    if url_safe {
+        &URL_SAFE
+    } else {
+        &STANDARD
+    }
+}
+
 fn get_delay(delay: Option<Duration>, interval: Option<Duration>) -> Duration {
     use rand::prelude::*;
     let mut rng = rand::thread_rng();
@@ -1029,6 +1799,59 @@ fn get_delay(delay: Option<Duration>, interval: Option<Duration>) -> Duration {
     }
 }
 
+/// Returns `n` random bytes generated by a cryptographically secure RNG
+/// `n` must be in `0..=MAX_RANDOM_BYTES`
+fn random_bytes(n: i64) -> Result<Vec<u8>, JError> {
+    use rand::prelude::*;
+
+    let n = usize::try_from(n)
+        .ok()
+        .filter(|n| *n <= MAX_RANDOM_BYTES)
+        .ok_or_else(|| {
+            JError::new(format!(
+                "number of random bytes must be between 0 and {MAX_RANDOM_BYTES}, was {n}"
+            ))
+        })?;
+
+    // ThreadRng is a CSPRNG periodically reseeded from the OS
+    let mut bytes = vec![0u8; n];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+/// Parses one of `trace`, `debug`, `info`, `warn`, `error`
+fn parse_log_level(level: &str) -> Result<log::Level, JError> {
+    match level {
+        "trace" => Ok(log::Level::Trace),
+        "debug" => Ok(log::Level::Debug),
+        "info" => Ok(log::Level::Info),
+        "warn" => Ok(log::Level::Warn),
+        "error" => Ok(log::Level::Error),
+        _ => Err(JError::new(format!(
+            "unknown log level '{level}', expected one of trace, debug, info, warn, error"
+        ))),
+    }
+}
+
+/// Reads at most `max_size` bytes of the script, fails if the file is bigger
+fn read_script(path: &path::Path, max_size: u64) -> std::io::Result<String> {
+    use std::io::{Error, ErrorKind, Read};
+
+    let mut script = String::new();
+    std::fs::File::open(path)?
+        .take(max_size + 1)
+        .read_to_string(&mut script)?;
+
+    if script.len() as u64 > max_size {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("script is bigger than {max_size} bytes"),
+        ));
+    }
+
+    Ok(script)
+}
+
 #[derive(thiserror::Error, Debug)]
 enum ResolveVaultError {
     #[error("Incorrect vault path `{1}`: doesn't belong to vault (`{2}`)")]
@@ -1054,6 +1877,25 @@ fn resolve_vault_path(
         ResolveVaultError::WrongVault(Some(e), path.to_path_buf(), virtual_prefix.clone())
     })?;
     let real_path = real_prefix.join(rest);
+
+    // Check every symlink on the way, so that a path can't leave the vault through a symlink
+    // and come back, e.g. `link_to_other_vault/../particle_id/file`
+    let mut component_path = real_prefix.clone();
+    for component in rest.components() {
+        component_path.push(component);
+        let is_symlink = component_path
+            .symlink_metadata()
+            .map_or(false, |meta| meta.file_type().is_symlink());
+        if is_symlink {
+            let target = component_path
+                .canonicalize()
+                .map_err(|e| ResolveVaultError::NotFound(e, path.to_path_buf()))?;
+            if !target.starts_with(&real_prefix) {
+                return Err(ResolveVaultError::WrongVault(None, target, real_prefix));
+            }
+        }
+    }
+
     let resolved_path = real_path
         .canonicalize()
         .map_err(|e| ResolveVaultError::NotFound(e, path.to_path_buf()))?;
@@ -1159,7 +2001,7 @@ mod resolve_path_tests {
 
     use particle_services::VIRTUAL_PARTICLE_VAULT_PREFIX;
 
-    use crate::builtins::{resolve_vault_path, ResolveVaultError};
+    use crate::builtins::{read_script, resolve_vault_path, ResolveVaultError};
 
     fn with_env(callback: fn(&str, &Path, &str, &Path) -> ()) {
         let particle_id = "particle_id";
@@ -1222,4 +2064,761 @@ mod resolve_path_tests {
             ));
         });
     }
+
+    #[test]
+    fn test_read_script_size_limit() {
+        with_env(|_particle_id, _real_prefix, _filename, path| {
+            std::fs::write(path, "(null)").expect("write script");
+            assert_eq!(read_script(path, 6).unwrap(), "(null)");
+            assert!(read_script(path, 5).is_err());
+
+            // sparse file, reading it fully would take 16 GiB of memory
+            let file = File::options().write(true).open(path).expect("open file");
+            file.set_len(16 * 1024 * 1024 * 1024).expect("extend file");
+            let err = read_script(path, 1024).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_path_symlink() {
+        with_env(|particle_id, real_prefix, filename, path| {
+            let vault = real_prefix.join(particle_id);
+            let virtual_vault = Path::new(VIRTUAL_PARTICLE_VAULT_PREFIX).join(particle_id);
+
+            // symlink within the vault is fine
+            std::os::unix::fs::symlink(path, vault.join("inner")).expect("create symlink");
+            let result = resolve_vault_path(real_prefix, &virtual_vault.join("inner"), particle_id);
+            assert_eq!(result.unwrap(), path);
+
+            // symlink to the directory outside of the vault
+            let outside = real_prefix.parent().unwrap().join("outside");
+            std::fs::create_dir_all(&outside).expect("create dir");
+            File::create(outside.join(filename)).expect("create file");
+            std::os::unix::fs::symlink(&outside, vault.join("escape")).expect("create symlink");
+
+            let virtual_path = virtual_vault.join("escape").join(filename);
+            let result = resolve_vault_path(real_prefix, &virtual_path, particle_id);
+            assert!(matches!(
+                result.unwrap_err(),
+                ResolveVaultError::WrongVault(_, _, _)
+            ));
+
+            // symlink to another particle's vault
+            let other_vault = real_prefix.join("other-particle-id");
+            std::fs::create_dir_all(&other_vault).expect("create dir");
+            std::os::unix::fs::symlink(&other_vault, vault.join("other")).expect("create symlink");
+
+            let result = resolve_vault_path(real_prefix, &virtual_vault.join("other"), particle_id);
+            assert!(matches!(
+                result.unwrap_err(),
+                ResolveVaultError::WrongVault(_, _, _)
+            ));
+
+            // path leaves the vault through a symlink and comes back,
+            // so only the resolved path is within the vault
+            let virtual_path = virtual_vault
+                .join("other")
+                .join("..")
+                .join(particle_id)
+                .join(filename);
+            let result = resolve_vault_path(real_prefix, &virtual_path, particle_id);
+            assert!(matches!(
+                result.unwrap_err(),
+                ResolveVaultError::WrongVault(_, _, _)
+            ));
+        });
+    }
+}
+
+#[cfg(test)]
+mod custom_service_tests {
+    use std::collections::{HashMap, HashSet};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use fluence_keypair::KeyPair;
+    use futures::channel::mpsc::unbounded;
+    use futures::future::join;
+    use futures::{FutureExt, StreamExt};
+    use libp2p::core::Multiaddr;
+    use libp2p::PeerId;
+    use serde_json::{json, Value as JValue};
+
+    use connection_pool::ConnectionPoolApi;
+    use fluence_libp2p::RandomPeerId;
+    use kademlia::KademliaApi;
+    use key_manager::KeyManager;
+    use now_millis::now_ms;
+    use particle_args::{Args, JError};
+    use particle_execution::{
+        sync_service_function, FunctionOutcome, ParticleFunction, ParticleParams, ServiceFunction,
+    };
+    use particle_protocol::Contact;
+    use peer_metrics::{ServiceType, ServiceTypeLabel, ServicesMetrics, ServicesMetricsExternal};
+    use prometheus_client::registry::Registry;
+    use script_storage::ScriptStorageApi;
+    use server_config::ServicesConfig;
+
+    use crate::builtins::parse_log_level;
+    use crate::{Builtins, ExternalAddressCommand, ExternalAddressesApi, NodeInfo};
+
+    #[derive(Clone)]
+    struct Connectivity {
+        kademlia: KademliaApi,
+        connection_pool: ConnectionPoolApi,
+    }
+
+    impl AsRef<KademliaApi> for Connectivity {
+        fn as_ref(&self) -> &KademliaApi {
+            &self.kademlia
+        }
+    }
+
+    impl AsRef<ConnectionPoolApi> for Connectivity {
+        fn as_ref(&self) -> &ConnectionPoolApi {
+            &self.connection_pool
+        }
+    }
+
+    fn make_builtins(base_dir: &Path, max_call_depth: usize) -> Builtins<Connectivity> {
+        let metrics = ServicesMetrics::new(None, unbounded().0, 10);
+        make_builtins_with_metrics(base_dir, max_call_depth, metrics)
+    }
+
+    fn make_builtins_with_metrics(
+        base_dir: &Path,
+        max_call_depth: usize,
+        metrics: ServicesMetrics,
+    ) -> Builtins<Connectivity> {
+        let connectivity = Connectivity {
+            kademlia: KademliaApi {
+                outlet: unbounded().0,
+            },
+            connection_pool: ConnectionPoolApi {
+                outlet: unbounded().0,
+                send_timeout: Duration::from_secs(1),
+            },
+        };
+        let script_storage = ScriptStorageApi {
+            outlet: unbounded().0,
+        };
+        let node_info = NodeInfo {
+            external_addresses: vec![],
+            node_version: "test",
+            air_version: "test",
+            git_commit: "test",
+            build_timestamp: "test",
+            rustc_version: "test",
+        };
+        let peer_id = KeyPair::generate_ed25519().get_peer_id();
+        let config = ServicesConfig::new(
+            peer_id,
+            base_dir.join("services"),
+            base_dir.join("vault"),
+            HashMap::new(),
+            peer_id,
+            peer_id,
+            server_config::default_module_max_heap_size(),
+            None,
+            None,
+            max_call_depth,
+            server_config::default_max_script_size(),
+            <_>::default(),
+        )
+        .expect("create services config");
+
+        Builtins::new(
+            connectivity,
+            script_storage,
+            node_info,
+            ExternalAddressesApi::new(unbounded().0, false),
+            config,
+            metrics,
+            KeyPair::generate_ed25519(),
+            KeyManager::new(base_dir.join("keypairs"), peer_id),
+        )
+    }
+
+    fn args(service_id: &str, function_name: &str) -> Args {
+        Args {
+            service_id: service_id.to_string(),
+            function_name: function_name.to_string(),
+            function_args: vec![],
+            tetraplets: vec![],
+        }
+    }
+
+    fn params() -> ParticleParams {
+        ParticleParams {
+            id: "particle".to_string(),
+            init_peer_id: KeyPair::generate_ed25519().get_peer_id(),
+            timestamp: 0,
+            ttl: 0,
+            script: "".to_string(),
+            signature: vec![],
+            call_depth: 0,
+        }
+    }
+
+    #[test]
+    fn recursive_custom_service() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = Arc::new(make_builtins(dir.path(), 4));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        // custom service that calls itself through builtins
+        let recursive: ServiceFunction = {
+            let builtins = Arc::downgrade(&builtins);
+            let calls = calls.clone();
+            Box::new(move |args, params| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let builtins = builtins.upgrade().expect("builtins are alive");
+                async move { builtins.call(args, params).await }.boxed()
+            })
+        };
+        builtins.extend(
+            "recursive".to_string(),
+            HashMap::from([("call".to_string(), recursive)]),
+            None,
+        );
+
+        let call = builtins.call(args("recursive", "call"), params());
+        let result = async_std::task::block_on(call);
+
+        match result {
+            FunctionOutcome::Err(err) => {
+                assert!(err.to_string().contains("maximum call depth of 4 exceeded"))
+            }
+            _ => panic!("expected call depth error"),
+        }
+        // service is called at depths 1 to 4, and once more before the guard fires
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn recursive_call_with_timeout() {
+        fn call_recursive() -> Args {
+            let mut args = args("srv", "call_with_timeout");
+            args.function_args = vec![json!("recursive"), json!("call"), json!([]), json!(10_000)];
+            args
+        }
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = Arc::new(make_builtins(dir.path(), 4));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        // custom service that calls itself through srv.call_with_timeout
+        let recursive: ServiceFunction = {
+            let builtins = Arc::downgrade(&builtins);
+            let calls = calls.clone();
+            Box::new(move |_, params| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let builtins = builtins.upgrade().expect("builtins are alive");
+                async move { builtins.call(call_recursive(), params).await }.boxed()
+            })
+        };
+        builtins.extend(
+            "recursive".to_string(),
+            HashMap::from([("call".to_string(), recursive)]),
+            None,
+        );
+
+        let params = ParticleParams {
+            timestamp: now_ms() as u64,
+            ttl: 60_000,
+            ..params()
+        };
+        let result = async_std::task::block_on(builtins.call(call_recursive(), params));
+
+        match result {
+            FunctionOutcome::Err(err) => {
+                assert!(err.to_string().contains("maximum call depth of 4 exceeded"))
+            }
+            _ => panic!("expected call depth error"),
+        }
+        // service is called at depths 1 to 4, and once more before the guard fires
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn async_unhandled() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let unhandled: ServiceFunction = Box::new(|args, _| {
+            async move {
+                async_std::task::sleep(Duration::from_millis(500)).await;
+                FunctionOutcome::Ok(json!(args.function_name))
+            }
+            .boxed()
+        });
+        builtins.extend("async".to_string(), HashMap::new(), Some(unhandled));
+
+        // both calls are awaited concurrently, none of them blocks the thread
+        let start = Instant::now();
+        let calls = join(
+            builtins.call(args("async", "first"), params()),
+            builtins.call(args("async", "second"), params()),
+        );
+        let (first, second) = async_std::task::block_on(calls);
+        assert!(start.elapsed() < Duration::from_millis(1000));
+
+        assert!(matches!(first, FunctionOutcome::Ok(v) if v == json!("first")));
+        assert!(matches!(second, FunctionOutcome::Ok(v) if v == json!("second")));
+    }
+
+    #[test]
+    fn call_with_timeout() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let slow: ServiceFunction = Box::new(|_, _| {
+            async move {
+                async_std::task::sleep(Duration::from_secs(5)).await;
+                FunctionOutcome::Ok(json!("slow"))
+            }
+            .boxed()
+        });
+        let fast = sync_service_function(|args, _| FunctionOutcome::Ok(json!(args.function_args)));
+        builtins.extend(
+            "custom".to_string(),
+            HashMap::from([("slow".to_string(), slow), ("fast".to_string(), fast)]),
+            None,
+        );
+
+        let call_with_timeout = |function: &str, timeout_ms: u64| {
+            let mut args = args("srv", "call_with_timeout");
+            args.function_args = vec![
+                json!("custom"),
+                json!(function),
+                json!([1, 2]),
+                json!(timeout_ms),
+            ];
+            let params = ParticleParams {
+                timestamp: now_ms() as u64,
+                ttl: 60_000,
+                ..params()
+            };
+            async_std::task::block_on(builtins.call(args, params))
+        };
+
+        let start = Instant::now();
+        let result = call_with_timeout("slow", 100);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        match result {
+            FunctionOutcome::Err(err) => {
+                assert!(
+                    err.to_string()
+                        .contains("custom.slow didn't complete in 100 ms"),
+                    "{}",
+                    err
+                )
+            }
+            _ => panic!("expected timeout error"),
+        }
+
+        let result = call_with_timeout("fast", 1000);
+        assert!(matches!(result, FunctionOutcome::Ok(v) if v == json!([1, 2])));
+    }
+
+    #[test]
+    fn sync_unhandled() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let unhandled =
+            sync_service_function(|args, _| FunctionOutcome::Ok(json!(args.function_name)));
+        builtins.extend("sync".to_string(), HashMap::new(), Some(unhandled));
+
+        let result = async_std::task::block_on(builtins.call(args("sync", "anything"), params()));
+        assert!(matches!(result, FunctionOutcome::Ok(v) if v == json!("anything")));
+    }
+
+    #[test]
+    fn remove_custom_service() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let function = sync_service_function(|_, _| FunctionOutcome::Ok(json!("hello")));
+        builtins.extend(
+            "custom".to_string(),
+            HashMap::from([("hello".to_string(), function)]),
+            None,
+        );
+
+        let call = builtins.custom_service_call(args("custom", "hello"), params());
+        let result = async_std::task::block_on(call);
+        assert!(matches!(result, FunctionOutcome::Ok(v) if v == json!("hello")));
+
+        assert!(builtins.remove_custom_service("custom"));
+        assert!(!builtins.remove_custom_service("custom"));
+
+        let call = builtins.custom_service_call(args("custom", "hello"), params());
+        let result = async_std::task::block_on(call);
+        assert!(matches!(result, FunctionOutcome::NotDefined { .. }));
+    }
+
+    #[test]
+    fn custom_service_metrics() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let external = ServicesMetricsExternal::new(&mut Registry::default());
+        let metrics = ServicesMetrics::new(Some(external.clone()), unbounded().0, 10);
+        let builtins = make_builtins_with_metrics(dir.path(), 4, metrics);
+
+        let ok = sync_service_function(|_, _| FunctionOutcome::Ok(json!("hello")));
+        let fail = sync_service_function(|_, _| FunctionOutcome::Err(JError::new("fail")));
+        builtins.extend(
+            "custom".to_string(),
+            HashMap::from([("ok".to_string(), ok), ("fail".to_string(), fail)]),
+            None,
+        );
+
+        async_std::task::block_on(builtins.call(args("custom", "ok"), params()));
+        async_std::task::block_on(builtins.call(args("custom", "ok"), params()));
+        async_std::task::block_on(builtins.call(args("custom", "fail"), params()));
+
+        let label = ServiceTypeLabel {
+            service_type: ServiceType::Custom("custom".to_string()),
+        };
+        assert_eq!(external.call_success_count.get_or_create(&label).get(), 2);
+        assert_eq!(external.call_failed_count.get_or_create(&label).get(), 1);
+
+        // builtins are not counted as custom services
+        async_std::task::block_on(builtins.call(args("peer", "timestamp_ms"), params()));
+        let builtin = ServiceTypeLabel {
+            service_type: ServiceType::Builtin,
+        };
+        assert_eq!(external.call_success_count.get_or_create(&builtin).get(), 1);
+        assert_eq!(external.call_success_count.get_or_create(&label).get(), 2);
+    }
+
+    fn sleep_args(duration_ms: u64) -> Args {
+        let mut args = args("op", "sleep");
+        args.function_args = vec![json!(duration_ms)];
+        args
+    }
+
+    fn params_with_ttl(ttl: u32) -> ParticleParams {
+        let mut params = params();
+        params.timestamp = now_ms() as u64;
+        params.ttl = ttl;
+        params
+    }
+
+    #[test]
+    fn sleep() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let start = Instant::now();
+        let result =
+            async_std::task::block_on(builtins.call(sleep_args(200), params_with_ttl(10_000)));
+        let elapsed = start.elapsed();
+
+        assert!(matches!(result, FunctionOutcome::Empty), "{result:?}");
+        assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+    }
+
+    #[test]
+    fn timeout_clamped_by_ttl() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let mut args = args("peer", "timeout");
+        args.function_args = vec![json!(60_000), json!("message")];
+
+        let start = Instant::now();
+        let result = async_std::task::block_on(builtins.call(args, params_with_ttl(200)));
+        let elapsed = start.elapsed();
+
+        match result {
+            FunctionOutcome::Ok(message) => assert_eq!(message, json!("message")),
+            unexpected => panic!("expected message, got {unexpected:?}"),
+        }
+        assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+    }
+
+    #[test]
+    fn sleep_clamped_by_ttl() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let start = Instant::now();
+        let result =
+            async_std::task::block_on(builtins.call(sleep_args(60_000), params_with_ttl(200)));
+        let elapsed = start.elapsed();
+
+        assert!(matches!(result, FunctionOutcome::Empty), "{result:?}");
+        assert!(elapsed < Duration::from_millis(1000), "{elapsed:?}");
+    }
+
+    #[test]
+    fn monotonic_ms() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let monotonic_ms = || {
+            let result =
+                async_std::task::block_on(builtins.call(args("peer", "monotonic_ms"), params()));
+            match result {
+                FunctionOutcome::Ok(value) => value.as_u64().expect("u64 value"),
+                other => panic!("expected u64, got {other:?}"),
+            }
+        };
+
+        let first = monotonic_ms();
+        std::thread::sleep(Duration::from_millis(10));
+        let second = monotonic_ms();
+        assert!(second >= first + 10, "{first} {second}");
+        assert!(monotonic_ms() >= second);
+    }
+
+    #[test]
+    fn log_levels() {
+        assert_eq!(parse_log_level("trace").unwrap(), log::Level::Trace);
+        assert_eq!(parse_log_level("debug").unwrap(), log::Level::Debug);
+        assert_eq!(parse_log_level("info").unwrap(), log::Level::Info);
+        assert_eq!(parse_log_level("warn").unwrap(), log::Level::Warn);
+        assert_eq!(parse_log_level("error").unwrap(), log::Level::Error);
+
+        let err = parse_log_level("fatal").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown log level 'fatal'"),
+            "{err}"
+        );
+        assert!(parse_log_level("INFO").is_err());
+    }
+
+    #[test]
+    fn log_permissions() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let log = |level: &str, params: ParticleParams| {
+            let mut args = args("debug", "log");
+            args.function_args = vec![json!(level), json!("message")];
+            async_std::task::block_on(builtins.call(args, params))
+        };
+
+        let mut management = params();
+        management.init_peer_id = builtins.management_peer_id;
+
+        for level in ["trace", "debug", "info", "warn", "error"] {
+            let result = log(level, management.clone());
+            assert!(matches!(result, FunctionOutcome::Empty), "{result:?}");
+        }
+
+        for level in ["trace", "debug", "info"] {
+            let result = log(level, params());
+            assert!(matches!(result, FunctionOutcome::Empty), "{result:?}");
+        }
+
+        for level in ["warn", "error"] {
+            let result = log(level, params());
+            assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+        }
+
+        let result = log("verbose", management);
+        assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+    }
+
+    #[test]
+    fn set_external_address() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut builtins = make_builtins(dir.path(), 4);
+        let (outlet, mut inlet) = unbounded();
+        builtins.external_addresses = ExternalAddressesApi::new(outlet, false);
+
+        let set = |maddr: &str, remove: bool, params: ParticleParams| {
+            let mut args = args("peer", "set_external_address");
+            args.function_args = vec![json!(maddr), json!(remove)];
+            async_std::task::block_on(builtins.call(args, params))
+        };
+        let addresses = || builtins.node_info.read().external_addresses.clone();
+
+        let mut management = params();
+        management.init_peer_id = builtins.management_peer_id;
+        let maddr: Multiaddr = "/ip4/8.8.8.8/tcp/7777".parse().unwrap();
+
+        let result = set("/ip4/8.8.8.8/tcp/7777", false, params());
+        assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+
+        let result = set("/ip4/8.8.8.8/tcp/7777", false, management.clone());
+        assert!(matches!(result, FunctionOutcome::Empty), "{result:?}");
+        assert_eq!(addresses(), vec![maddr.clone()]);
+        let command = inlet.try_next().unwrap();
+        assert_eq!(command, Some(ExternalAddressCommand::Add(maddr.clone())));
+
+        // private addresses are rejected unless allowed in config
+        for private in ["/ip4/127.0.0.1/tcp/7777", "/ip4/192.168.1.1/tcp/7777"] {
+            let result = set(private, false, management.clone());
+            assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+        }
+        let result = set("/tcp/7777", false, management.clone());
+        assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+        assert_eq!(addresses(), vec![maddr.clone()]);
+
+        let result = set("/ip4/8.8.8.8/tcp/7777", true, management);
+        assert!(matches!(result, FunctionOutcome::Empty), "{result:?}");
+        assert!(addresses().is_empty());
+        let command = inlet.try_next().unwrap();
+        assert_eq!(command, Some(ExternalAddressCommand::Remove(maddr)));
+    }
+
+    #[test]
+    fn get_peer_id_for() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let builtins = make_builtins(dir.path(), 4);
+
+        let get_peer_id_for = |key_id: String| {
+            let mut args = args("sig", "get_peer_id_for");
+            args.function_args = vec![json!(key_id)];
+            async_std::task::block_on(builtins.call(args, params()))
+        };
+
+        let owner = RandomPeerId::random();
+        let worker = builtins
+            .key_manager
+            .get_scope_peer_id(owner)
+            .expect("create worker keypair");
+
+        let result = get_peer_id_for(owner.to_base58());
+        assert!(
+            matches!(&result, FunctionOutcome::Ok(v) if v == &json!(worker.to_base58())),
+            "{result:?}"
+        );
+
+        let result = get_peer_id_for(RandomPeerId::random().to_base58());
+        assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+        let result = get_peer_id_for("not a peer id".to_string());
+        assert!(matches!(result, FunctionOutcome::Err(_)), "{result:?}");
+    }
+
+    /// Kademlia always returns `neighbors`, connection pool reports only `connected` as connected
+    fn make_connectivity(neighbors: Vec<PeerId>, connected: Vec<PeerId>) -> Connectivity {
+        let (kademlia_outlet, mut kademlia_inlet) = unbounded();
+        async_std::task::spawn(async move {
+            while let Some(cmd) = kademlia_inlet.next().await {
+                if let kademlia::Command::Neighborhood { count, out, .. } = cmd {
+                    out.send(Ok(neighbors.iter().take(count).cloned().collect()))
+                        .ok();
+                }
+            }
+        });
+        let (pool_outlet, mut pool_inlet) = unbounded();
+        async_std::task::spawn(async move {
+            while let Some(cmd) = pool_inlet.next().await {
+                match cmd {
+                    connection_pool::Command::IsConnected { peer_id, out } => {
+                        out.send(connected.contains(&peer_id)).ok();
+                    }
+                    connection_pool::Command::GetContact { peer_id, out } => {
+                        let contact = connected
+                            .contains(&peer_id)
+                            .then(|| Contact::new(peer_id, vec![]));
+                        out.send(contact).ok();
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        Connectivity {
+            kademlia: KademliaApi {
+                outlet: kademlia_outlet,
+            },
+            connection_pool: ConnectionPoolApi {
+                outlet: pool_outlet,
+                send_timeout: Duration::from_secs(1),
+            },
+        }
+    }
+
+    #[test]
+    fn neighborhood_filters() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut builtins = make_builtins(dir.path(), 4);
+        let local = builtins.local_peer_id;
+        let connected = RandomPeerId::random();
+        let dead = RandomPeerId::random();
+        builtins.connectivity = make_connectivity(vec![local, connected, dead], vec![connected]);
+
+        let neighborhood = |exclude_self: JValue, only_connected: JValue| {
+            let mut args = args("kad", "neighborhood");
+            let key = bs58::encode("key").into_string();
+            args.function_args = vec![
+                json!(key),
+                json!([]),
+                json!([]),
+                exclude_self,
+                only_connected,
+            ];
+            match async_std::task::block_on(builtins.call(args, params())) {
+                FunctionOutcome::Ok(v) => v,
+                other => panic!("expected neighborhood, got {other:?}"),
+            }
+        };
+        let ids = |peers: &[PeerId]| json!(peers.iter().map(|p| p.to_string()).collect::<Vec<_>>());
+
+        // flags are optional
+        let all = ids(&[local, connected, dead]);
+        assert_eq!(neighborhood(json!([]), json!([])), all);
+        assert_eq!(neighborhood(json!(false), json!(false)), all);
+        assert_eq!(
+            neighborhood(json!(true), json!(false)),
+            ids(&[connected, dead])
+        );
+        // local peer isn't connected to itself
+        assert_eq!(neighborhood(json!(false), json!(true)), ids(&[connected]));
+        assert_eq!(
+            neighborhood(json!([true]), json!([true])),
+            ids(&[connected])
+        );
+    }
+
+    #[test]
+    fn closest_connected_skips_disconnected() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let mut builtins = make_builtins(dir.path(), 4);
+        let peers: Vec<_> = (0..6).map(|_| RandomPeerId::random()).collect();
+        // only the farthest peers are connected, so the lookup must go past the first `count`
+        let connected = vec![peers[3], peers[5]];
+        builtins.connectivity = make_connectivity(peers, connected.clone());
+
+        let closest_connected = |count: usize| {
+            let mut args = args("kad", "closest_connected");
+            let key = bs58::encode("key").into_string();
+            args.function_args = vec![json!(key), json!(count)];
+            match async_std::task::block_on(builtins.call(args, params())) {
+                FunctionOutcome::Ok(v) => v,
+                other => panic!("expected closest peers, got {other:?}"),
+            }
+        };
+        let contacts = |peers: &[PeerId]| {
+            json!(peers
+                .iter()
+                .map(|p| json!({ "peer_id": p.to_string(), "addresses": [] }))
+                .collect::<Vec<_>>())
+        };
+
+        assert_eq!(closest_connected(1), contacts(&connected[..1]));
+        assert_eq!(closest_connected(2), contacts(&connected));
+        // there are no more connected peers to fill the count
+        assert_eq!(closest_connected(5), contacts(&connected));
+        assert_eq!(closest_connected(0), json!([]));
+    }
+
+    #[test]
+    fn builtins_list_has_no_duplicates() {
+        let builtins = Builtins::<Connectivity>::BUILTINS;
+        assert!(builtins.contains(&("op", "noop")));
+
+        let unique: HashSet<_> = builtins.iter().collect();
+        assert_eq!(unique.len(), builtins.len(), "BUILTINS has duplicates");
+    }
 }
@@ -20,6 +20,12 @@ use std::string::FromUtf8Error;
 pub enum HostClosureCallError {
     #[error("decode base58 failed: {0}")]
     DecodeBase58(#[source] bs58::decode::Error),
+    #[error("decode base64 failed: {0}")]
+    DecodeBase64(#[source] base64::DecodeError),
+    #[error("decode hex failed: {0}")]
+    DecodeHex(#[source] faster_hex::Error),
+    #[error("decode hex failed: hex string must have even length, got {0}")]
+    OddLengthHex(usize),
     #[error("decode from bytes to UTF8 failed: {0}")]
     DecodeUTF8(#[source] FromUtf8Error),
 }
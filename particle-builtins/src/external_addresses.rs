@@ -0,0 +1,86 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_libp2p::types::Outlet;
+use libp2p::core::{multiaddr::Protocol, Multiaddr};
+
+use particle_args::JError;
+
+/// Change of the external addresses, applied to the swarm by the node event loop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalAddressCommand {
+    Add(Multiaddr),
+    Remove(Multiaddr),
+}
+
+/// Updates external addresses of the running node
+#[derive(Debug, Clone)]
+pub struct ExternalAddressesApi {
+    outlet: Outlet<ExternalAddressCommand>,
+    /// Whether private and loopback addresses may be advertised
+    allow_private: bool,
+}
+
+impl ExternalAddressesApi {
+    pub fn new(outlet: Outlet<ExternalAddressCommand>, allow_private: bool) -> Self {
+        Self {
+            outlet,
+            allow_private,
+        }
+    }
+
+    /// Checks that `maddr` can be advertised as an external address
+    pub fn validate(&self, maddr: &Multiaddr) -> Result<(), JError> {
+        let has_host = maddr.iter().any(|p| {
+            matches!(
+                p,
+                Protocol::Ip4(_)
+                    | Protocol::Ip6(_)
+                    | Protocol::Dns(_)
+                    | Protocol::Dns4(_)
+                    | Protocol::Dns6(_)
+            )
+        });
+        if !has_host {
+            return Err(JError::new(format!(
+                "external address {maddr} must contain an ip or dns component"
+            )));
+        }
+
+        if !self.allow_private && is_private_maddr(maddr) {
+            return Err(JError::new(format!(
+                "external address {maddr} is private, set allow_local_addresses to advertise it"
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn send(&self, command: ExternalAddressCommand) -> Result<(), JError> {
+        self.outlet
+            .unbounded_send(command)
+            .map_err(|err| JError::new(format!("failed to update external addresses: {err}")))
+    }
+}
+
+fn is_private_maddr(maddr: &Multiaddr) -> bool {
+    maddr.iter().any(|p| match p {
+        Protocol::Ip4(addr) => !addr.is_global(),
+        Protocol::Ip6(addr) => !addr.is_global(),
+        Protocol::Memory(_) => true,
+        _ => false,
+    })
+}
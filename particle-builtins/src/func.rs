@@ -39,3 +39,90 @@ where
     let out = f(x, y)?;
     FunctionOutcome::Ok(json!(out))
 }
+
+pub fn ternary<X, Y, Z, Out, F>(args: Args, f: F) -> FunctionOutcome
+where
+    X: for<'de> Deserialize<'de>,
+    Y: for<'de> Deserialize<'de>,
+    Z: for<'de> Deserialize<'de>,
+    Out: Serialize,
+    F: Fn(X, Y, Z) -> Result<Out, JError>,
+{
+    if args.function_args.len() != 3 {
+        let err = format!("expected 3 arguments, got {}", args.function_args.len());
+        return FunctionOutcome::Err(JError::new(err));
+    }
+    let mut args = args.function_args.into_iter();
+
+    let x: X = Args::next("x", &mut args)?;
+    let y: Y = Args::next("y", &mut args)?;
+    let z: Z = Args::next("z", &mut args)?;
+    let out = f(x, y, z)?;
+    FunctionOutcome::Ok(json!(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use particle_args::{Args, JError};
+    use particle_execution::FunctionOutcome;
+    use serde_json::{json, Value as JValue};
+
+    use crate::func::ternary;
+
+    fn args(function_args: Vec<JValue>) -> Args {
+        Args {
+            service_id: "test".to_string(),
+            function_name: "ternary".to_string(),
+            function_args,
+            tetraplets: vec![],
+        }
+    }
+
+    fn sum(args: Args) -> FunctionOutcome {
+        ternary(args, |x: i64, y: i64, z: i64| -> Result<i64, JError> {
+            Ok(x + y + z)
+        })
+    }
+
+    fn expect_err(outcome: FunctionOutcome, expected: &str) {
+        match outcome {
+            FunctionOutcome::Err(err) => {
+                let err = err.to_string();
+                assert!(err.contains(expected), "{err}")
+            }
+            unexpected => panic!("expected error, got {unexpected:?}"),
+        }
+    }
+
+    #[test]
+    fn ternary_ok() {
+        match sum(args(vec![json!(1), json!(2), json!(3)])) {
+            FunctionOutcome::Ok(result) => assert_eq!(result, json!(6)),
+            unexpected => panic!("expected Ok, got {unexpected:?}"),
+        }
+    }
+
+    #[test]
+    fn ternary_argument_count() {
+        expect_err(
+            sum(args(vec![json!(1), json!(2)])),
+            "expected 3 arguments, got 2",
+        );
+        expect_err(
+            sum(args(vec![json!(1), json!(2), json!(3), json!(4)])),
+            "expected 3 arguments, got 4",
+        );
+    }
+
+    #[test]
+    fn ternary_argument_type() {
+        expect_err(
+            sum(args(vec![json!(1), json!(2), json!("three")])),
+            "Error while deserializing field 'z'",
+        );
+        expect_err(
+            sum(args(vec![json!("one"), json!(2), json!(3)])),
+            "Error while deserializing field 'x'",
+        );
+    }
+}
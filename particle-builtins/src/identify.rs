@@ -22,4 +22,22 @@ pub struct NodeInfo {
     pub external_addresses: Vec<Multiaddr>,
     pub node_version: &'static str,
     pub air_version: &'static str,
+    /// Commit the node was built from
+    pub git_commit: &'static str,
+    /// RFC 3339 time of the build
+    pub build_timestamp: &'static str,
+    pub rustc_version: &'static str,
+}
+
+/// Result of `peer identify`: static node info extended with its current state
+#[derive(Serialize, Clone, Debug)]
+pub struct Identify<'a> {
+    #[serde(flatten)]
+    pub node_info: &'a NodeInfo,
+    /// Seconds since the node has started
+    pub uptime_sec: u64,
+    /// Number of peers the node is currently connected to
+    pub connected_peers: usize,
+    /// `(service, function)` pairs of builtins this node handles
+    pub builtins: &'static [(&'static str, &'static str)],
 }
@@ -17,6 +17,7 @@
 #![feature(stmt_expr_attributes)]
 #![feature(try_trait_v2)]
 #![feature(try_blocks)]
+#![feature(ip)]
 #![recursion_limit = "512"]
 #![warn(rust_2018_idioms)]
 #![deny(
@@ -30,12 +31,14 @@
 )]
 
 pub use builtins::Builtins;
+pub use external_addresses::{ExternalAddressCommand, ExternalAddressesApi};
 pub use identify::NodeInfo;
 pub use outcome::{ok, wrap, wrap_unit};
 
 mod builtins;
 mod debug;
 mod error;
+mod external_addresses;
 mod func;
 mod identify;
 mod json;
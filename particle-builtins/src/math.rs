@@ -2,6 +2,7 @@ use std::collections::HashSet;
 use std::ops::Mul;
 
 use itertools::Itertools;
+use serde_json::Value as JValue;
 
 use particle_args::JError;
 
@@ -55,6 +56,39 @@ pub fn log(x: i64, y: i64) -> Result<u32, JError> {
         .ok_or_else(|| JError::new("i64 log overflow"))
 }
 
+/// greatest common divisor of x and y, always non-negative
+/// gcd(0, 0) = 0
+pub fn gcd(x: i64, y: i64) -> Result<i64, JError> {
+    let (mut a, mut b) = (x.unsigned_abs(), y.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    i64::try_from(a).map_err(|_| JError::new("i64 gcd overflow"))
+}
+
+/// least common multiple of x and y, always non-negative
+/// lcm(x, 0) = 0
+pub fn lcm(x: i64, y: i64) -> Result<i64, JError> {
+    if x == 0 || y == 0 {
+        return Ok(0);
+    }
+    let gcd = gcd(x, y)?;
+    (x / gcd)
+        .checked_mul(y)
+        .and_then(i64::checked_abs)
+        .ok_or_else(|| JError::new("i64 lcm overflow"))
+}
+
+/// x bounded to [lo, hi]
+pub fn clamp(x: i64, lo: i64, hi: i64) -> Result<i64, JError> {
+    if lo > hi {
+        return Err(JError::new(format!(
+            "clamp lower bound {lo} is greater than upper bound {hi}"
+        )));
+    }
+    Ok(x.clamp(lo, hi))
+}
+
 /// x > y
 pub fn gt(x: i64, y: i64) -> Result<bool, JError> {
     Ok(x.gt(&y))
@@ -84,6 +118,50 @@ pub fn cmp(x: i64, y: i64) -> Result<i8, JError> {
     Ok(ord as i8)
 }
 
+/// x == y for arbitrary JSON values
+/// Order of keys in objects doesn't matter, order of elements in arrays does
+/// Numbers are compared by their representation, so 1 and 1.0 are not equal
+pub fn eq(x: JValue, y: JValue) -> Result<bool, JError> {
+    Ok(x == y)
+}
+
+/// x != y for arbitrary JSON values, see [eq]
+pub fn neq(x: JValue, y: JValue) -> Result<bool, JError> {
+    Ok(x != y)
+}
+
+/// x & y
+pub fn bit_and(x: i64, y: i64) -> Result<i64, JError> {
+    Ok(x & y)
+}
+
+/// x | y
+pub fn bit_or(x: i64, y: i64) -> Result<i64, JError> {
+    Ok(x | y)
+}
+
+/// x ^ y (bitwise exclusive or)
+pub fn bit_xor(x: i64, y: i64) -> Result<i64, JError> {
+    Ok(x ^ y)
+}
+
+/// !x (bitwise negation)
+pub fn bit_not(x: i64) -> Result<i64, JError> {
+    Ok(!x)
+}
+
+/// x << n, n must be less than 64
+pub fn shl(x: i64, n: u32) -> Result<i64, JError> {
+    x.checked_shl(n)
+        .ok_or_else(|| JError::new(format!("i64 shl overflow: can't shift by {n} bits")))
+}
+
+/// x >> n (arithmetic shift), n must be less than 64
+pub fn shr(x: i64, n: u32) -> Result<i64, JError> {
+    x.checked_shr(n)
+        .ok_or_else(|| JError::new(format!("i64 shr overflow: can't shift by {n} bits")))
+}
+
 /// fold(_ + _) (sum of all numbers in array)
 pub fn array_sum(xs: Vec<i64>) -> Result<i64, JError> {
     xs.into_iter()
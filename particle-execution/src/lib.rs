@@ -3,7 +3,7 @@
 
 pub use function_outcome::FunctionOutcome;
 pub use particle_function::{
-    Output as ParticleFunctionOutput, ParticleFunction, ParticleFunctionMut,
+    sync_service_function, Output as ParticleFunctionOutput, ParticleFunction, ParticleFunctionMut,
     ParticleFunctionStatic, ServiceFunction,
 };
 pub use particle_params::ParticleParams;
@@ -29,6 +29,16 @@ pub type Output<'a> = BoxFuture<'a, FunctionOutcome>;
 pub type ServiceFunction =
     Box<dyn FnMut(Args, ParticleParams) -> Output<'static> + 'static + Send + Sync>;
 
+/// Wraps a synchronous closure into a [ServiceFunction]
+pub fn sync_service_function(
+    mut f: impl FnMut(Args, ParticleParams) -> FunctionOutcome + 'static + Send + Sync,
+) -> ServiceFunction {
+    Box::new(move |args, params| {
+        let outcome = f(args, params);
+        async move { outcome }.boxed()
+    })
+}
+
 pub trait ParticleFunction: 'static + Send + Sync {
     fn call(&self, args: Args, particle: ParticleParams) -> Output<'_>;
     fn extend(
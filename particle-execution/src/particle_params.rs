@@ -14,7 +14,10 @@
  * limitations under the License.
  */
 
+use std::time::Duration;
+
 use fluence_libp2p::PeerId;
+use now_millis::now_ms;
 use particle_protocol::Particle;
 
 /// Lightweight, static version of the [[Particle]] structure
@@ -29,6 +32,8 @@ pub struct ParticleParams {
     pub ttl: u32,
     pub script: String,
     pub signature: Vec<u8>,
+    /// How many nested builtin calls led to this one, 0 for calls made directly by AquaVM
+    pub call_depth: usize,
 }
 
 impl ParticleParams {
@@ -50,6 +55,21 @@ impl ParticleParams {
             ttl: *ttl,
             script: script.clone(),
             signature: signature.clone(),
+            call_depth: 0,
+        }
+    }
+
+    /// Deadline in milliseconds
+    pub fn deadline(&self) -> Option<u64> {
+        self.timestamp.checked_add(self.ttl as u64)
+    }
+
+    /// Remaining lifetime of the particle, zero if it has already expired
+    pub fn time_to_live(&self) -> Duration {
+        if let Some(ttl) = self.deadline().and_then(|d| d.checked_sub(now_ms() as u64)) {
+            Duration::from_millis(ttl)
+        } else {
+            Duration::default()
         }
     }
 }
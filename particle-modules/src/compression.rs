@@ -0,0 +1,103 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::Read;
+use std::str::FromStr;
+
+use flate2::read::GzDecoder;
+
+use crate::error::ModuleError::{DecompressModule, DecompressedModuleTooBig, UnknownCompression};
+use crate::error::{ModuleError, Result};
+
+/// Protects against gzip bombs
+const MAX_DECOMPRESSED_MODULE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Compression of uploaded module bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleCompression {
+    Gzip,
+}
+
+impl ModuleCompression {
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            ModuleCompression::Gzip => {
+                let mut module = vec![];
+                GzDecoder::new(bytes)
+                    .take(MAX_DECOMPRESSED_MODULE_SIZE + 1)
+                    .read_to_end(&mut module)
+                    .map_err(|err| DecompressModule { err })?;
+
+                if module.len() as u64 > MAX_DECOMPRESSED_MODULE_SIZE {
+                    return Err(DecompressedModuleTooBig {
+                        max_size: MAX_DECOMPRESSED_MODULE_SIZE,
+                    });
+                }
+
+                Ok(module)
+            }
+        }
+    }
+}
+
+impl FromStr for ModuleCompression {
+    type Err = ModuleError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gzip" => Ok(ModuleCompression::Gzip),
+            other => Err(UnknownCompression(other.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::str::FromStr;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use crate::error::ModuleError::{DecompressModule, UnknownCompression};
+    use crate::ModuleCompression;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            ModuleCompression::from_str("gzip").unwrap(),
+            ModuleCompression::Gzip
+        );
+        assert!(matches!(
+            ModuleCompression::from_str("zstd"),
+            Err(UnknownCompression(c)) if c == "zstd"
+        ));
+    }
+
+    #[test]
+    fn gzip_roundtrip() {
+        let bytes = b"\0asm some module bytes".to_vec();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&bytes).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let decompressed = ModuleCompression::Gzip.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, bytes);
+
+        let result = ModuleCompression::Gzip.decompress(&bytes);
+        assert!(matches!(result, Err(DecompressModule { .. })));
+    }
+}
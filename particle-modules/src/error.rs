@@ -18,11 +18,13 @@ use std::fmt::Debug;
 use std::path::PathBuf;
 
 use base64::DecodeError;
+use bytesize::ByteSize;
 use fluence_app_service::{MarineError, TomlMarineNamedModuleConfig};
 use marine_it_parser::ITParserError;
 use serde_json::Value as JValue;
 use thiserror::Error;
 
+use fluence_libp2p::PeerId;
 use json_utils::err_as_value;
 use service_modules::Blueprint;
 
@@ -172,13 +174,64 @@ pub enum ModuleError {
         err: serde_json::Error,
     },
 
+    #[error("Invalid module hash {hash:?}: {err}")]
+    InvalidModuleHash {
+        hash: String,
+        #[source]
+        err: eyre::Report,
+    },
+    #[error("Module {hash} can't be removed: it is used by blueprints {blueprints:?}")]
+    ModuleIsReferenced {
+        hash: String,
+        blueprints: Vec<String>,
+    },
+    #[error("Error removing module {path:?}: {err}")]
+    RemoveModule {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Error removing blueprint {path:?}: {err}")]
+    RemoveBlueprint {
+        path: PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+
     #[error(
-    "Config error: max_heap_size = '{max_heap_size_wanted}' can't be bigger than {max_heap_size_allowed}'"
+        "Config error: max_heap_size = '{}' can't be bigger than '{}'",
+        ByteSize::b(*max_heap_size_wanted),
+        ByteSize::b(*max_heap_size_allowed)
     )]
     MaxHeapSizeOverflow {
         max_heap_size_wanted: u64,
         max_heap_size_allowed: u64,
     },
+    #[error("Unknown module compression '{0}', only 'gzip' is supported")]
+    UnknownCompression(String),
+    #[error("Error decompressing module: {err}")]
+    DecompressModule {
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("Decompressed module is bigger than {max_size} bytes")]
+    DecompressedModuleTooBig { max_size: u64 },
+    #[error("Module upload {upload_id} wasn't found, it may have expired")]
+    UploadNotFound { upload_id: String },
+    #[error("Module upload {upload_id} expected chunk {expected}, got {got}")]
+    UnexpectedChunkIndex {
+        upload_id: String,
+        expected: u32,
+        got: u32,
+    },
+    #[error("Uploaded module is bigger than {max_size} bytes, upload is cancelled")]
+    UploadTooBig { max_size: usize },
+    #[error("Too many module uploads in progress, maximum is {max_uploads}")]
+    TooManyModuleUploads { max_uploads: usize },
+    #[error("Too many module uploads in progress started by {peer_id}, maximum is {max_uploads}")]
+    TooManyPeerModuleUploads { peer_id: PeerId, max_uploads: usize },
+    #[error("Module config '{name}' is invalid: {}", problems.join("; "))]
+    InvalidModuleConfig { name: String, problems: Vec<String> },
 }
 
 impl From<ModuleError> for JValue {
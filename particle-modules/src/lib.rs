@@ -29,13 +29,17 @@
 #[macro_use]
 extern crate fstrings;
 
+mod compression;
 mod error;
 mod files;
 mod modules;
+mod uploads;
 
+pub use compression::ModuleCompression;
 pub use error::ModuleError;
 pub use files::{load_blueprint, load_module_by_path, load_module_descriptor};
 pub use modules::{AddBlueprint, ModuleRepository};
+pub use uploads::UploadLimits;
 
 // reexport
 pub use fluence_app_service::{
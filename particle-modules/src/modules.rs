@@ -26,22 +26,26 @@ use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JValue};
 
+use fluence_libp2p::PeerId;
 use fs_utils::file_name;
 use particle_args::JError;
 use particle_execution::ParticleParams;
 use service_modules::{
-    extract_module_file_name, hash_dependencies, is_blueprint, is_module_wasm,
+    blueprint_fname, extract_module_file_name, hash_dependencies, is_blueprint, is_module_wasm,
     module_config_name_hash, module_file_name_hash, Blueprint, Dependency, Hash,
 };
 
+use crate::compression::ModuleCompression;
 use crate::error::ModuleError::{
     BlueprintNotFound, BlueprintNotFoundInVault, ConfigNotFoundInVault, EmptyDependenciesList,
     FacadeShouldBeHash, IncorrectVaultBlueprint, IncorrectVaultModuleConfig, InvalidBlueprintPath,
-    InvalidModuleConfigPath, InvalidModuleName, InvalidModulePath, MaxHeapSizeOverflow,
-    ModuleNotFoundInVault, ReadModuleInterfaceError, VaultDoesNotExist,
+    InvalidModuleConfig, InvalidModuleConfigPath, InvalidModuleHash, InvalidModuleName,
+    InvalidModulePath, MaxHeapSizeOverflow, ModuleIsReferenced, ModuleNotFoundInVault,
+    ReadModuleInterfaceError, RemoveBlueprint, RemoveModule, VaultDoesNotExist,
 };
 use crate::error::Result;
 use crate::files::{self, load_config_by_path, load_module_by_path, load_module_descriptor};
+use crate::uploads::{ModuleUploads, UploadLimits};
 
 type ModuleName = String;
 
@@ -68,6 +72,8 @@ pub struct ModuleRepository {
     blueprints: Arc<RwLock<HashMap<String, Blueprint>>>,
     max_heap_size: ByteSize,
     default_heap_size: Option<ByteSize>,
+    /// Modules being uploaded in chunks
+    uploads: Arc<Mutex<ModuleUploads>>,
 }
 
 impl ModuleRepository {
@@ -117,9 +123,16 @@ impl ModuleRepository {
             particles_vault_dir: particles_vault_dir.to_path_buf(),
             max_heap_size,
             default_heap_size,
+            uploads: <_>::default(),
         }
     }
 
+    /// Sets limits on modules uploaded in chunks
+    pub fn with_upload_limits(mut self, limits: UploadLimits) -> Self {
+        self.uploads = Arc::new(Mutex::new(ModuleUploads::new(limits)));
+        self
+    }
+
     /// check that module file name is equal to module hash
     /// if not, rename module and config files
     fn maybe_migrate_module(path: &Path, hash: &Hash, modules_dir: &Path) {
@@ -143,19 +156,23 @@ impl ModuleRepository {
         }
     }
 
-    // set default if max_heap_size and mem_pages_count are not specified
-    fn check_module_heap_size(&self, config: &mut TomlMarineNamedModuleConfig) -> Result<()> {
-        let heap_size = match (config.config.max_heap_size, config.config.mem_pages_count) {
+    /// max_heap_size if it's specified, otherwise derived from mem_pages_count or default
+    /// Default is clamped to the node-wide max_heap_size, so it never causes rejection
+    fn module_heap_size(&self, config: &TomlMarineNamedModuleConfig) -> Option<ByteSize> {
+        match (config.config.max_heap_size, config.config.mem_pages_count) {
             (Some(heap_size), _) => Some(heap_size),
             (None, Some(pages_count)) => {
                 Some(ByteSize::b(marine_utils::wasm_pages_to_bytes(pages_count)))
             }
-            (None, None) => self.default_heap_size,
-        };
-
-        config.config.max_heap_size = heap_size;
+            (None, None) => self
+                .default_heap_size
+                .map(|default| default.min(self.max_heap_size)),
+        }
+    }
 
-        if let Some(heap_size) = heap_size {
+    /// Rejects configs requesting heap bigger than node-wide max_heap_size
+    fn check_module_heap_size(&self, config: &TomlMarineNamedModuleConfig) -> Result<()> {
+        if let Some(heap_size) = self.module_heap_size(config) {
             if heap_size > self.max_heap_size {
                 return Err(MaxHeapSizeOverflow {
                     max_heap_size_wanted: heap_size.as_u64(),
@@ -166,11 +183,79 @@ impl ModuleRepository {
 
         Ok(())
     }
-    pub fn add_module(&self, module: Vec<u8>, config: TomlMarineNamedModuleConfig) -> Result<Hash> {
+
+    /// Checks that module config can be used on this node: heap size fits into the limit,
+    /// and all preopened files, mapped dirs and mounted binaries exist on disk.
+    /// Reports all found problems at once.
+    pub fn validate_module_config(&self, config: &TomlMarineNamedModuleConfig) -> Result<()> {
+        let mut problems = vec![];
+
+        if let Some(heap_size) = self.module_heap_size(config) {
+            if heap_size > self.max_heap_size {
+                problems.push(format!(
+                    "max_heap_size {} can't be bigger than {}",
+                    heap_size, self.max_heap_size
+                ));
+            }
+        }
+
+        if let Some(wasi) = &config.config.wasi {
+            for file in wasi.preopened_files.iter().flatten() {
+                if !Path::new(file).exists() {
+                    problems.push(format!("preopened file {file:?} doesn't exist"));
+                }
+            }
+
+            for (alias, dir) in wasi.mapped_dirs.iter().flatten() {
+                match dir.as_str().map(Path::new) {
+                    Some(dir) if dir.is_dir() => {}
+                    Some(dir) => problems.push(format!(
+                        "mapped dir {alias} => {dir:?} doesn't exist or isn't a directory"
+                    )),
+                    None => {
+                        problems.push(format!("mapped dir {alias} => {dir} must be a string path"))
+                    }
+                }
+            }
+        }
+
+        for (name, binary) in config.config.mounted_binaries.iter().flatten() {
+            match binary.as_str().map(Path::new) {
+                Some(binary) if binary.is_file() => {}
+                Some(binary) => problems.push(format!(
+                    "mounted binary {name} => {binary:?} doesn't exist or isn't a file"
+                )),
+                None => problems.push(format!(
+                    "mounted binary {name} => {binary} must be a string path"
+                )),
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidModuleConfig {
+                name: config.name.clone(),
+                problems,
+            })
+        }
+    }
+
+    pub fn add_module(
+        &self,
+        module: Vec<u8>,
+        mut config: TomlMarineNamedModuleConfig,
+    ) -> Result<Hash> {
         let hash = Hash::new(&module);
 
-        let mut config = files::add_module(&self.modules_dir, &hash, &module, config)?;
-        self.check_module_heap_size(&mut config)?;
+        // check heap size before anything is written to disk
+        self.check_module_heap_size(&config)?;
+        // store the default heap size clamped to the limit, so that the module config on disk
+        // is what services are created with
+        if config.config.max_heap_size.is_none() && config.config.mem_pages_count.is_none() {
+            config.config.max_heap_size = self.module_heap_size(&config);
+        }
+        let config = files::add_module(&self.modules_dir, &hash, &module, config)?;
         self.modules_by_name
             .lock()
             .insert(config.name, hash.clone());
@@ -178,6 +263,56 @@ impl ModuleRepository {
         Ok(hash)
     }
 
+    /// Removes module and its config from disk, unless some blueprint depends on it
+    pub fn remove_module(&self, hex_hash: &str) -> Result<()> {
+        let hash = Hash::from_hex(hex_hash).map_err(|err| InvalidModuleHash {
+            hash: hex_hash.to_string(),
+            err: err.into(),
+        })?;
+
+        let mut modules_by_name = self.modules_by_name.lock();
+        let blueprints: Vec<_> = self
+            .blueprints
+            .read()
+            .values()
+            .filter(|bp| {
+                bp.dependencies.iter().any(|dep| match dep {
+                    Dependency::Hash(dep) => dep == &hash,
+                    Dependency::Name(name) => modules_by_name.get(name) == Some(&hash),
+                })
+            })
+            .map(|bp| bp.id.clone())
+            .collect();
+        if !blueprints.is_empty() {
+            return Err(ModuleIsReferenced {
+                hash: hex_hash.to_string(),
+                blueprints,
+            });
+        }
+
+        let wasm = self.modules_dir.join(module_file_name_hash(&hash));
+        std::fs::remove_file(&wasm).map_err(|err| RemoveModule { path: wasm, err })?;
+        let config = self.modules_dir.join(module_config_name_hash(&hash));
+        if let Err(err) = std::fs::remove_file(&config) {
+            log::warn!("Error removing module config {:?}: {:?}", config, err);
+        }
+
+        modules_by_name.retain(|_, module_hash| module_hash != &hash);
+        self.module_interface_cache.write().remove(&hash);
+
+        Ok(())
+    }
+
+    /// Returns config of the module as it was stored on disk
+    pub fn get_module_config(&self, hex_hash: &str) -> Result<TomlMarineNamedModuleConfig> {
+        let hash = Hash::from_hex(hex_hash).map_err(|err| InvalidModuleHash {
+            hash: hex_hash.to_string(),
+            err: err.into(),
+        })?;
+
+        load_config_by_path(&self.modules_dir.join(module_config_name_hash(&hash)))
+    }
+
     fn check_vault_exists(&self, particle_id: &str) -> Result<PathBuf> {
         let vault_path = self.particles_vault_dir.join(particle_id);
         if !vault_path.exists() {
@@ -244,6 +379,52 @@ impl ModuleRepository {
         Ok(String::from(hash.to_hex().as_ref()))
     }
 
+    /// Same as [Self::add_module_base64], but module bytes are compressed.
+    /// Module is stored decompressed, so its hash doesn't depend on compression.
+    pub fn add_compressed_module_base64(
+        &self,
+        module: String,
+        config: TomlMarineNamedModuleConfig,
+        compression: ModuleCompression,
+    ) -> Result<String> {
+        let module = base64.decode(module)?;
+        let module = compression.decompress(&module)?;
+        let hash = self.add_module(module, config)?;
+
+        Ok(String::from(hash.to_hex().as_ref()))
+    }
+
+    /// Starts chunked module upload on behalf of `owner`, returns upload id.
+    /// Only the owner can append chunks to the upload and finish it
+    pub fn begin_module_upload(&self, owner: PeerId) -> Result<String> {
+        self.uploads.lock().begin(owner)
+    }
+
+    /// Appends base64-encoded chunk to the upload. Chunks must be sent in order, starting from 0
+    pub fn append_module_chunk(
+        &self,
+        owner: PeerId,
+        upload_id: &str,
+        index: u32,
+        chunk: String,
+    ) -> Result<()> {
+        let chunk = base64.decode(chunk)?;
+        self.uploads.lock().append(owner, upload_id, index, &chunk)
+    }
+
+    /// Adds module assembled from uploaded chunks
+    pub fn finish_module_upload(
+        &self,
+        owner: PeerId,
+        upload_id: &str,
+        config: TomlMarineNamedModuleConfig,
+    ) -> Result<String> {
+        let module = self.uploads.lock().finish(owner, upload_id)?;
+        let hash = self.add_module(module, config)?;
+
+        Ok(String::from(hash.to_hex().as_ref()))
+    }
+
     pub fn add_module_from_vault(
         &self,
         module_path: String,
@@ -299,6 +480,20 @@ impl ModuleRepository {
         Ok(blueprint.id)
     }
 
+    /// Removes blueprint from disk and from the in-memory index
+    pub fn remove_blueprint(&self, id: &str) -> Result<()> {
+        let mut blueprints = self.blueprints.write();
+        if !blueprints.contains_key(id) {
+            return Err(BlueprintNotFound { id: id.to_string() });
+        }
+
+        let path = self.blueprints_dir.join(blueprint_fname(id));
+        std::fs::remove_file(&path).map_err(|err| RemoveBlueprint { path, err })?;
+        blueprints.remove(id);
+
+        Ok(())
+    }
+
     pub fn list_modules(&self) -> std::result::Result<JValue, JError> {
         // TODO: refactor errors to enums
         let modules = fs_utils::list_files(&self.modules_dir)
@@ -502,32 +697,30 @@ fn resolve_hash(
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
     use std::str::FromStr;
 
     use base64::{engine::general_purpose::STANDARD as base64, Engine};
     use bytesize::ByteSize;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
     use fluence_app_service::{TomlMarineModuleConfig, TomlMarineNamedModuleConfig};
+    use fluence_libp2p::PeerId;
     use tempdir::TempDir;
 
     use service_modules::load_module;
     use service_modules::{Dependency, Hash};
+    use toml_utils::table;
 
-    use crate::error::ModuleError::MaxHeapSizeOverflow;
-    use crate::{AddBlueprint, ModuleRepository};
+    use crate::error::ModuleError::{
+        BlueprintNotFound, InvalidModuleConfig, InvalidModuleHash, MaxHeapSizeOverflow,
+        ModuleIsReferenced, NoModuleConfig,
+    };
+    use crate::{AddBlueprint, ModuleCompression, ModuleRepository, WASIConfig};
 
     #[test]
     fn test_add_blueprint() {
-        let module_dir = TempDir::new("test").unwrap();
-        let bp_dir = TempDir::new("test").unwrap();
-        let vault_dir = TempDir::new("test").unwrap();
-        let max_heap_size = server_config::default_module_max_heap_size();
-        let repo = ModuleRepository::new(
-            module_dir.path(),
-            bp_dir.path(),
-            vault_dir.path(),
-            max_heap_size,
-            None,
-        );
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
 
         let dep1 = Dependency::Hash(Hash::new(&[1, 2, 3]));
         let dep2 = Dependency::Hash(Hash::new(&[3, 2, 1]));
@@ -559,25 +752,55 @@ mod tests {
 
     #[test]
     fn test_add_module_get_interface() {
-        let module_dir = TempDir::new("test").unwrap();
-        let bp_dir = TempDir::new("test2").unwrap();
-        let vault_dir = TempDir::new("test3").unwrap();
-        let max_heap_size = server_config::default_module_max_heap_size();
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
+
+        let module = tetraplets_module();
+
+        let hash = repo
+            .add_module_base64(base64.encode(module), tetraplets_config())
+            .unwrap();
+
+        let result = repo.get_interface(&hash);
+        assert!(result.is_ok())
+    }
+
+    /// Temporary dirs of a test repository, removed on drop
+    struct RepositoryDirs {
+        modules: TempDir,
+        blueprints: TempDir,
+        vault: TempDir,
+    }
+
+    fn test_repository(
+        max_heap_size: ByteSize,
+        default_heap_size: Option<ByteSize>,
+    ) -> (ModuleRepository, RepositoryDirs) {
+        let dirs = RepositoryDirs {
+            modules: TempDir::new("modules").unwrap(),
+            blueprints: TempDir::new("blueprints").unwrap(),
+            vault: TempDir::new("vault").unwrap(),
+        };
         let repo = ModuleRepository::new(
-            module_dir.path(),
-            bp_dir.path(),
-            vault_dir.path(),
+            dirs.modules.path(),
+            dirs.blueprints.path(),
+            dirs.vault.path(),
             max_heap_size,
-            None,
+            default_heap_size,
         );
 
-        let module = load_module(
+        (repo, dirs)
+    }
+
+    fn tetraplets_module() -> Vec<u8> {
+        load_module(
             "../crates/particle-node-tests/tests/tetraplets/artifacts",
             "tetraplets",
         )
-        .expect("load module");
+        .expect("load module")
+    }
 
-        let config: TomlMarineNamedModuleConfig = TomlMarineNamedModuleConfig {
+    fn tetraplets_config() -> TomlMarineNamedModuleConfig {
+        TomlMarineNamedModuleConfig {
             name: "tetra".to_string(),
             file_name: None,
             load_from: None,
@@ -589,14 +812,65 @@ mod tests {
                 mounted_binaries: None,
                 logging_mask: None,
             },
-        };
+        }
+    }
+
+    #[test]
+    fn test_remove_module() {
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
 
+        let module = tetraplets_module();
         let hash = repo
-            .add_module_base64(base64.encode(module), config)
+            .add_module_base64(base64.encode(module), tetraplets_config())
             .unwrap();
 
-        let result = repo.get_interface(&hash);
-        assert!(result.is_ok())
+        repo.remove_module(&hash)
+            .expect("remove unreferenced module");
+
+        assert!(repo.list_modules().unwrap().as_array().unwrap().is_empty());
+        assert!(repo.get_interface(&hash).is_err());
+    }
+
+    #[test]
+    fn test_remove_referenced_module() {
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
+
+        let module = tetraplets_module();
+        let hash = repo
+            .add_module_base64(base64.encode(module), tetraplets_config())
+            .unwrap();
+        let dependency = Dependency::Hash(Hash::from_hex(&hash).unwrap());
+        let blueprint_id = repo
+            .add_blueprint(AddBlueprint::new("tetra".to_string(), vec![dependency]))
+            .unwrap();
+
+        let result = repo.remove_module(&hash);
+
+        match result {
+            Err(ModuleIsReferenced { blueprints, .. }) => {
+                assert_eq!(blueprints, vec![blueprint_id])
+            }
+            other => panic!("expected ModuleIsReferenced, got {other:?}"),
+        }
+        assert_eq!(repo.list_modules().unwrap().as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_remove_blueprint() {
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
+
+        let dep = Dependency::Hash(Hash::new(&[1, 2, 3]));
+        let id = repo
+            .add_blueprint(AddBlueprint::new("bp".to_string(), vec![dep]))
+            .unwrap();
+        assert_eq!(repo.get_blueprints().len(), 1);
+
+        repo.remove_blueprint(&id).expect("remove blueprint");
+        assert!(repo.get_blueprints().is_empty());
+        assert!(repo.get_blueprint_from_cache(&id).is_err());
+
+        let result = repo.remove_blueprint(&id);
+        assert!(matches!(result, Err(BlueprintNotFound { .. })));
     }
 
     #[test]
@@ -617,37 +891,13 @@ mod tests {
 
     #[test]
     fn test_add_module_max_heap_size_overflow() {
-        let module_dir = TempDir::new("test").unwrap();
-        let bp_dir = TempDir::new("test2").unwrap();
-        let vault_dir = TempDir::new("test3").unwrap();
         let max_heap_size = ByteSize::from_str("10 Mb").unwrap();
-        let repo = ModuleRepository::new(
-            module_dir.path(),
-            bp_dir.path(),
-            vault_dir.path(),
-            max_heap_size,
-            None,
-        );
+        let (repo, dirs) = test_repository(max_heap_size, None);
 
-        let module = load_module(
-            "../crates/particle-node-tests/tests/tetraplets/artifacts",
-            "tetraplets",
-        )
-        .expect("load module");
+        let module = tetraplets_module();
 
-        let config: TomlMarineNamedModuleConfig = TomlMarineNamedModuleConfig {
-            name: "tetra".to_string(),
-            file_name: None,
-            load_from: None,
-            config: TomlMarineModuleConfig {
-                mem_pages_count: None,
-                max_heap_size: Some(ByteSize::b(max_heap_size.as_u64() + 10)),
-                logger_enabled: None,
-                wasi: None,
-                mounted_binaries: None,
-                logging_mask: None,
-            },
-        };
+        let mut config = tetraplets_config();
+        config.config.max_heap_size = Some(ByteSize::b(max_heap_size.as_u64() + 10));
 
         let result = repo.add_module_base64(base64.encode(module), config);
 
@@ -662,5 +912,170 @@ mod tests {
                 }
             )
         );
+        // rejected module isn't saved
+        assert!(repo.list_modules().unwrap().as_array().unwrap().is_empty());
+        assert!(fs_utils::list_files(dirs.modules.path())
+            .into_iter()
+            .flatten()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_add_module_max_heap_size_within_limit() {
+        let max_heap_size = ByteSize::from_str("10 Mb").unwrap();
+        let (repo, _dirs) = test_repository(max_heap_size, None);
+
+        let module = tetraplets_module();
+
+        let mut config = tetraplets_config();
+        config.config.max_heap_size = Some(max_heap_size);
+
+        repo.add_module_base64(base64.encode(module), config)
+            .expect("heap size equal to the limit is allowed");
+    }
+
+    #[test]
+    fn test_default_heap_size_clamped() {
+        let max_heap_size = ByteSize::from_str("10 Mb").unwrap();
+        let default_heap_size = ByteSize::from_str("100 Mb").unwrap();
+        let (repo, _dirs) = test_repository(max_heap_size, Some(default_heap_size));
+
+        let config = tetraplets_config();
+        assert_eq!(repo.module_heap_size(&config), Some(max_heap_size));
+
+        let module = tetraplets_module();
+        let hash = repo
+            .add_module_base64(base64.encode(module), config)
+            .expect("default heap size doesn't cause rejection");
+
+        let stored = repo.get_module_config(&hash).expect("get module config");
+        assert_eq!(stored.config.max_heap_size, Some(max_heap_size));
+    }
+
+    #[test]
+    fn test_add_compressed_module() {
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
+
+        let module = tetraplets_module();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&module).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let hash = repo
+            .add_module_base64(base64.encode(&module), tetraplets_config())
+            .unwrap();
+        let compressed_hash = repo
+            .add_compressed_module_base64(
+                base64.encode(compressed),
+                tetraplets_config(),
+                ModuleCompression::Gzip,
+            )
+            .unwrap();
+
+        assert_eq!(hash, compressed_hash);
+        assert!(repo.get_interface(&compressed_hash).is_ok());
+    }
+
+    #[test]
+    fn test_chunked_module_upload() {
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
+
+        let module = tetraplets_module();
+
+        let peer = PeerId::random();
+        let upload_id = repo.begin_module_upload(peer).unwrap();
+        let chunk_size = module.len() / 3 + 1;
+        for (index, chunk) in module.chunks(chunk_size).enumerate() {
+            repo.append_module_chunk(peer, &upload_id, index as u32, base64.encode(chunk))
+                .unwrap();
+        }
+        let chunked_hash = repo
+            .finish_module_upload(peer, &upload_id, tetraplets_config())
+            .unwrap();
+
+        let hash = repo
+            .add_module_base64(base64.encode(module), tetraplets_config())
+            .unwrap();
+
+        assert_eq!(hash, chunked_hash);
+        assert!(repo.get_interface(&chunked_hash).is_ok());
+    }
+
+    #[test]
+    fn test_get_module_config() {
+        let (repo, _dirs) = test_repository(server_config::default_module_max_heap_size(), None);
+
+        let module = tetraplets_module();
+
+        let mut config = tetraplets_config();
+        config.config.max_heap_size = Some(ByteSize::from_str("5 Mb").unwrap());
+        config.config.logger_enabled = Some(true);
+        config.config.logging_mask = Some(4);
+        let hash = repo
+            .add_module_base64(base64.encode(module), config)
+            .unwrap();
+
+        let stored = repo.get_module_config(&hash).expect("get module config");
+        assert_eq!(stored.name, "tetra");
+        assert_eq!(
+            stored.config.max_heap_size,
+            Some(ByteSize::from_str("5 Mb").unwrap())
+        );
+        assert_eq!(stored.config.logger_enabled, Some(true));
+        assert_eq!(stored.config.logging_mask, Some(4));
+
+        let unknown = Hash::new(&[1, 2, 3]).to_hex();
+        assert!(matches!(
+            repo.get_module_config(unknown.as_ref()),
+            Err(NoModuleConfig { .. })
+        ));
+        assert!(matches!(
+            repo.get_module_config("not a hash"),
+            Err(InvalidModuleHash { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_module_config() {
+        let max_heap_size = ByteSize::from_str("10 Mb").unwrap();
+        let (repo, dirs) = test_repository(max_heap_size, None);
+
+        let binary = dirs.modules.path().join("binary");
+        std::fs::write(&binary, "").unwrap();
+        let dir = dirs.modules.path().display().to_string();
+        let binary = binary.display().to_string();
+        let missing = dirs.modules.path().join("missing").display().to_string();
+
+        let mut config = tetraplets_config();
+        config.config.max_heap_size = Some(ByteSize::from_str("5 Mb").unwrap());
+        config.config.wasi = Some(WASIConfig {
+            preopened_files: Some(vec![dir.clone().into()]),
+            envs: None,
+            mapped_dirs: Some(table(vec![("dir".into(), dir.clone())])),
+        });
+        config.config.mounted_binaries = Some(table(vec![("bin".into(), binary.clone())]));
+        repo.validate_module_config(&config).expect("valid config");
+
+        config.config.max_heap_size = Some(ByteSize::from_str("20 Mb").unwrap());
+        config.config.wasi = Some(WASIConfig {
+            preopened_files: Some(vec![missing.clone().into()]),
+            envs: None,
+            mapped_dirs: Some(table(vec![
+                ("dir".into(), dir),
+                ("missing".into(), missing.clone()),
+                // binary is not a directory
+                ("file".into(), binary),
+            ])),
+        });
+        config.config.mounted_binaries = Some(table(vec![("missing".into(), missing)]));
+
+        match repo.validate_module_config(&config) {
+            Err(InvalidModuleConfig { name, problems }) => {
+                assert_eq!(name, "tetra");
+                assert_eq!(problems.len(), 5, "{problems:?}");
+            }
+            other => panic!("expected InvalidModuleConfig, got {other:?}"),
+        }
     }
 }
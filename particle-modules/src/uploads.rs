@@ -0,0 +1,350 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use fluence_libp2p::PeerId;
+
+use crate::error::ModuleError::{
+    TooManyModuleUploads, TooManyPeerModuleUploads, UnexpectedChunkIndex, UploadNotFound,
+    UploadTooBig,
+};
+use crate::error::Result;
+
+/// Limits on modules uploaded chunk by chunk. Uploads are kept in memory,
+/// so at most `max_uploads * max_size` bytes are held at once
+#[derive(Debug, Clone, Copy)]
+pub struct UploadLimits {
+    /// Maximum size of a module assembled from chunks
+    pub max_size: usize,
+    /// Maximum number of uploads in progress
+    pub max_uploads: usize,
+    /// Maximum number of uploads in progress started by a single peer
+    pub max_uploads_per_peer: usize,
+    /// Uploads that didn't receive chunks for that long are dropped
+    pub timeout: Duration,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 32 * 1024 * 1024,
+            max_uploads: 8,
+            max_uploads_per_peer: 2,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Upload {
+    /// Peer that started the upload, only it can append chunks and finish the upload
+    owner: PeerId,
+    module: Vec<u8>,
+    /// Index of the chunk expected next
+    next_index: u32,
+    updated: Instant,
+}
+
+/// Modules being uploaded chunk by chunk
+#[derive(Debug, Default)]
+pub struct ModuleUploads {
+    limits: UploadLimits,
+    uploads: HashMap<String, Upload>,
+}
+
+impl ModuleUploads {
+    pub fn new(limits: UploadLimits) -> Self {
+        Self {
+            limits,
+            uploads: <_>::default(),
+        }
+    }
+
+    /// Starts a new upload on behalf of `owner`, returns its id
+    pub fn begin(&mut self, owner: PeerId) -> Result<String> {
+        self.begin_at(owner, Instant::now())
+    }
+
+    /// Appends chunk to the upload. Chunks must be sent in order, starting from 0
+    pub fn append(
+        &mut self,
+        owner: PeerId,
+        upload_id: &str,
+        index: u32,
+        chunk: &[u8],
+    ) -> Result<()> {
+        self.append_at(owner, upload_id, index, chunk, Instant::now())
+    }
+
+    /// Removes upload and returns assembled module bytes
+    pub fn finish(&mut self, owner: PeerId, upload_id: &str) -> Result<Vec<u8>> {
+        self.finish_at(owner, upload_id, Instant::now())
+    }
+
+    fn begin_at(&mut self, owner: PeerId, now: Instant) -> Result<String> {
+        self.remove_expired(now);
+
+        let max_uploads = self.limits.max_uploads;
+        if self.uploads.len() >= max_uploads {
+            return Err(TooManyModuleUploads { max_uploads });
+        }
+
+        let max_uploads = self.limits.max_uploads_per_peer;
+        let peer_uploads = self.uploads.values().filter(|u| u.owner == owner).count();
+        if peer_uploads >= max_uploads {
+            return Err(TooManyPeerModuleUploads {
+                peer_id: owner,
+                max_uploads,
+            });
+        }
+
+        let upload_id = uuid_utils::uuid();
+        let upload = Upload {
+            owner,
+            module: vec![],
+            next_index: 0,
+            updated: now,
+        };
+        self.uploads.insert(upload_id.clone(), upload);
+
+        Ok(upload_id)
+    }
+
+    fn append_at(
+        &mut self,
+        owner: PeerId,
+        upload_id: &str,
+        index: u32,
+        chunk: &[u8],
+        now: Instant,
+    ) -> Result<()> {
+        self.remove_expired(now);
+
+        let upload = self
+            .uploads
+            .get_mut(upload_id)
+            // uploads of other peers are indistinguishable from missing ones
+            .filter(|upload| upload.owner == owner)
+            .ok_or_else(|| UploadNotFound {
+                upload_id: upload_id.to_string(),
+            })?;
+
+        if index != upload.next_index {
+            return Err(UnexpectedChunkIndex {
+                upload_id: upload_id.to_string(),
+                expected: upload.next_index,
+                got: index,
+            });
+        }
+
+        let max_size = self.limits.max_size;
+        if upload.module.len() + chunk.len() > max_size {
+            // upload can't be finished anyway
+            self.uploads.remove(upload_id);
+            return Err(UploadTooBig { max_size });
+        }
+
+        upload.module.extend_from_slice(chunk);
+        upload.next_index += 1;
+        upload.updated = now;
+
+        Ok(())
+    }
+
+    fn finish_at(&mut self, owner: PeerId, upload_id: &str, now: Instant) -> Result<Vec<u8>> {
+        self.remove_expired(now);
+
+        let not_found = || UploadNotFound {
+            upload_id: upload_id.to_string(),
+        };
+        let upload = self.uploads.get(upload_id).ok_or_else(not_found)?;
+        if upload.owner != owner {
+            return Err(not_found());
+        }
+
+        let upload = self.uploads.remove(upload_id).ok_or_else(not_found)?;
+        Ok(upload.module)
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        let timeout = self.limits.timeout;
+        self.uploads
+            .retain(|_, upload| now.saturating_duration_since(upload.updated) < timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use fluence_libp2p::PeerId;
+
+    use crate::error::ModuleError::{
+        TooManyModuleUploads, TooManyPeerModuleUploads, UnexpectedChunkIndex, UploadNotFound,
+        UploadTooBig,
+    };
+    use crate::uploads::{ModuleUploads, UploadLimits};
+
+    fn limits(max_size: usize, max_uploads: usize, timeout: Duration) -> UploadLimits {
+        UploadLimits {
+            max_size,
+            max_uploads,
+            max_uploads_per_peer: max_uploads,
+            timeout,
+        }
+    }
+
+    #[test]
+    fn chunks_are_assembled() {
+        let mut uploads = ModuleUploads::default();
+        let peer = PeerId::random();
+        let id = uploads.begin(peer).unwrap();
+
+        uploads.append(peer, &id, 0, &[1, 2]).unwrap();
+        uploads.append(peer, &id, 1, &[3]).unwrap();
+        uploads.append(peer, &id, 2, &[]).unwrap();
+        uploads.append(peer, &id, 3, &[4, 5]).unwrap();
+
+        assert_eq!(uploads.finish(peer, &id).unwrap(), vec![1, 2, 3, 4, 5]);
+        // upload is removed on finish
+        assert!(matches!(
+            uploads.finish(peer, &id),
+            Err(UploadNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn chunks_must_be_ordered() {
+        let mut uploads = ModuleUploads::default();
+        let peer = PeerId::random();
+        let id = uploads.begin(peer).unwrap();
+
+        let result = uploads.append(peer, &id, 1, &[1]);
+        assert!(matches!(
+            result,
+            Err(UnexpectedChunkIndex {
+                expected: 0,
+                got: 1,
+                ..
+            })
+        ));
+
+        uploads.append(peer, &id, 0, &[1]).unwrap();
+        let result = uploads.append(peer, &id, 0, &[1]);
+        assert!(matches!(
+            result,
+            Err(UnexpectedChunkIndex {
+                expected: 1,
+                got: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn size_is_limited() {
+        let mut uploads = ModuleUploads::new(limits(4, 10, Duration::from_secs(1)));
+        let peer = PeerId::random();
+        let id = uploads.begin(peer).unwrap();
+
+        uploads.append(peer, &id, 0, &[1, 2, 3]).unwrap();
+        let result = uploads.append(peer, &id, 1, &[4, 5]);
+        assert!(matches!(result, Err(UploadTooBig { max_size: 4 })));
+        // oversized upload is dropped
+        assert!(matches!(
+            uploads.finish(peer, &id),
+            Err(UploadNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn number_of_uploads_is_limited() {
+        let mut uploads = ModuleUploads::new(limits(4, 2, Duration::from_secs(1)));
+        let now = Instant::now();
+
+        uploads.begin_at(PeerId::random(), now).unwrap();
+        uploads.begin_at(PeerId::random(), now).unwrap();
+        let result = uploads.begin_at(PeerId::random(), now);
+        assert!(matches!(
+            result,
+            Err(TooManyModuleUploads { max_uploads: 2 })
+        ));
+
+        // expired uploads free their slots
+        let later = now + Duration::from_secs(1);
+        assert!(uploads.begin_at(PeerId::random(), later).is_ok());
+    }
+
+    #[test]
+    fn number_of_uploads_per_peer_is_limited() {
+        let mut uploads = ModuleUploads::new(UploadLimits {
+            max_uploads_per_peer: 1,
+            ..limits(4, 10, Duration::from_secs(1))
+        });
+        let peer = PeerId::random();
+
+        let id = uploads.begin(peer).unwrap();
+        let result = uploads.begin(peer);
+        assert!(matches!(
+            result,
+            Err(TooManyPeerModuleUploads { max_uploads: 1, .. })
+        ));
+        // other peers aren't affected
+        assert!(uploads.begin(PeerId::random()).is_ok());
+
+        // finished upload frees the slot
+        uploads.finish(peer, &id).unwrap();
+        assert!(uploads.begin(peer).is_ok());
+    }
+
+    #[test]
+    fn uploads_are_private() {
+        let mut uploads = ModuleUploads::default();
+        let owner = PeerId::random();
+        let stranger = PeerId::random();
+        let id = uploads.begin(owner).unwrap();
+
+        let result = uploads.append(stranger, &id, 0, &[1]);
+        assert!(matches!(result, Err(UploadNotFound { .. })));
+        let result = uploads.finish(stranger, &id);
+        assert!(matches!(result, Err(UploadNotFound { .. })));
+
+        // upload is intact
+        uploads.append(owner, &id, 0, &[1]).unwrap();
+        assert_eq!(uploads.finish(owner, &id).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn abandoned_uploads_expire() {
+        let mut uploads = ModuleUploads::new(limits(100, 10, Duration::from_secs(60)));
+        let peer = PeerId::random();
+        let now = Instant::now();
+        let id = uploads.begin_at(peer, now).unwrap();
+
+        // each chunk prolongs the upload
+        uploads
+            .append_at(peer, &id, 0, &[1], now + Duration::from_secs(50))
+            .unwrap();
+        uploads
+            .append_at(peer, &id, 1, &[2], now + Duration::from_secs(100))
+            .unwrap();
+
+        let result = uploads.append_at(peer, &id, 2, &[3], now + Duration::from_secs(160));
+        assert!(matches!(result, Err(UploadNotFound { .. })));
+    }
+}
@@ -0,0 +1,68 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Build info is best effort: if something isn't available, it's reported as "unknown"
+const UNKNOWN: &str = "unknown";
+
+fn main() {
+    let git_commit = command_output("git", &["rev-parse", "HEAD"]);
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = command_output(&rustc, &["--version"]);
+
+    println!(
+        "cargo:rustc-env=BUILD_GIT_COMMIT={}",
+        git_commit.as_deref().unwrap_or(UNKNOWN)
+    );
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", build_timestamp());
+    println!(
+        "cargo:rustc-env=BUILD_RUSTC_VERSION={}",
+        rustc_version.as_deref().unwrap_or(UNKNOWN)
+    );
+
+    // rebuild when the checked out commit changes
+    for git_file in ["../.git/HEAD", "../.git/refs/heads"] {
+        if Path::new(git_file).exists() {
+            println!("cargo:rerun-if-changed={git_file}");
+        }
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?;
+    Some(output.trim().to_string()).filter(|o| !o.is_empty())
+}
+
+/// Respects SOURCE_DATE_EPOCH, so reproducible builds get the same timestamp
+fn build_timestamp() -> String {
+    let time = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => match epoch.parse() {
+            Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+            Err(_) => return UNKNOWN.to_string(),
+        },
+        Err(_) => SystemTime::now(),
+    };
+
+    humantime::format_rfc3339_seconds(time).to_string()
+}
@@ -46,6 +46,8 @@ impl FluenceNetworkBehaviour {
                 let supports_kademlia =
                     info.protocols.iter().any(|p| p.contains("/ipfs/kad/1.0.0"));
                 if supports_kademlia {
+                    // connection pool doesn't see Kademlia traffic, so it mustn't close these as idle
+                    self.connection_pool.protect_peer(peer_id);
                     self.kademlia.add_kad_node(peer_id, addresses);
                 }
             }
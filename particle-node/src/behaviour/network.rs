@@ -13,6 +13,10 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
 use libp2p::identify::IdentifyConfig;
 use libp2p::{
     identify::Identify,
@@ -50,13 +54,18 @@ impl FluenceNetworkBehaviour {
             kad_config: cfg.kademlia_config,
         };
 
-        let (kademlia, kademlia_api) = Kademlia::new(kad_config, cfg.libp2p_metrics);
-        let (connection_pool, particle_stream, connection_pool_api) = ConnectionPoolBehaviour::new(
+        let (kademlia, kademlia_api) =
+            Kademlia::new(kad_config, cfg.libp2p_metrics, cfg.kademlia_metrics);
+        let (mut connection_pool, particle_stream, connection_pool_api) = ConnectionPoolBehaviour::new(
             cfg.particle_queue_buffer,
             cfg.protocol_config,
             cfg.local_peer_id,
+            cfg.connection_idle_timeout,
             cfg.connection_pool_metrics,
         );
+        for addr in &cfg.bootstrap_nodes {
+            connection_pool.protect_address(addr.clone());
+        }
 
         let this = Self {
             kademlia,
@@ -65,12 +74,15 @@ impl FluenceNetworkBehaviour {
             ping,
         };
 
+        // nothing to bootstrap from
+        let bootstrapped = Arc::new(AtomicBool::new(cfg.bootstrap_nodes.is_empty()));
         let connectivity = Connectivity {
             peer_id: cfg.local_peer_id,
             kademlia: kademlia_api,
             connection_pool: connection_pool_api,
             bootstrap_nodes: cfg.bootstrap_nodes.into_iter().collect(),
             bootstrap_frequency: cfg.bootstrap_frequency,
+            bootstrapped,
             metrics: cfg.connectivity_metrics,
         };
 
@@ -0,0 +1,39 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use libp2p::ping::{PingEvent, PingSuccess};
+
+use super::FluenceNetworkBehaviour;
+
+/// Round-trip times are measured via Ping protocol.
+/// They are passed to connection pool, so `peer.ping` can report them
+impl FluenceNetworkBehaviour {
+    pub fn inject_ping_event(&mut self, event: PingEvent) {
+        let PingEvent { peer, result } = event;
+        match result {
+            Ok(PingSuccess::Ping { rtt }) => {
+                log::trace!("Ping to {} succeeded in {:?}", peer, rtt);
+                self.connection_pool.add_ping_result(peer, Some(rtt));
+            }
+            // We don't care about pings sent by remote peers
+            Ok(PingSuccess::Pong) => {}
+            Err(error) => {
+                log::debug!("Ping to {} failed: {}", peer, error);
+                self.connection_pool.add_ping_result(peer, None);
+            }
+        }
+    }
+}
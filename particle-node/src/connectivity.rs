@@ -16,6 +16,8 @@
 
 use std::cmp::min;
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_std::task::{sleep, spawn};
@@ -43,6 +45,8 @@ pub struct Connectivity {
     /// Bootstrap will be executed after [1, N, 2*N, 3*N, ...] bootstrap nodes connected
     /// This setting specify that N.
     pub bootstrap_frequency: usize,
+    /// Set after the first successful Kademlia bootstrap. Initially set if there are no bootstrap nodes
+    pub bootstrapped: Arc<AtomicBool>,
     pub metrics: Option<ConnectivityMetrics>,
 }
 
@@ -157,6 +161,7 @@ impl Connectivity {
         let pool = self.connection_pool;
         let bootstrap_nodes = self.bootstrap_nodes;
         let frequency = self.bootstrap_frequency;
+        let bootstrapped = self.bootstrapped;
 
         // Count connected (and reconnected) bootstrap nodes
         let connections = {
@@ -178,6 +183,7 @@ impl Connectivity {
         connections
             .for_each(move |(n, contact)| {
                 let kademlia = kademlia.clone();
+                let bootstrapped = bootstrapped.clone();
                 async move {
                     if n % frequency == 0 {
                         kademlia.add_contact(contact);
@@ -185,6 +191,7 @@ impl Connectivity {
                             log::warn!("Kademlia bootstrap failed: {}", err)
                         } else {
                             log::info!("Kademlia bootstrap finished");
+                            bootstrapped.store(true, Ordering::Relaxed);
                         }
                     }
                 }
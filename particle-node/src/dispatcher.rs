@@ -14,17 +14,26 @@
  * limitations under the License.
  */
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_std::task::spawn;
 use futures::{FutureExt, SinkExt, StreamExt};
+use parking_lot::Mutex;
 use prometheus_client::registry::Registry;
+use tracing::Instrument;
 
 use aquamarine::{AquamarineApi, AquamarineApiError, RoutingEffects};
 use fluence_libp2p::types::{BackPressuredInlet, Inlet, Outlet};
 use fluence_libp2p::PeerId;
 use particle_protocol::Particle;
-use peer_metrics::DispatcherMetrics;
+use peer_metrics::{DispatcherMetrics, ParticleStreamMetrics};
+use server_config::ParticleTtlLimit;
 
 use crate::effectors::Effectors;
+use crate::particle_dedup::ParticleDedup;
+use crate::peer_filter::PeerFilter;
+use crate::rate_limiter::RateLimiter;
 use crate::tasks::Tasks;
 
 type Effects = Result<RoutingEffects, AquamarineApiError>;
@@ -38,17 +47,35 @@ pub struct Dispatcher {
     aquamarine: AquamarineApi,
     particle_failures_sink: Outlet<String>,
     effectors: Effectors,
+    /// Whether to drop particles that aren't signed by their init peer id
+    verify_signatures: bool,
+    /// Decides which init peer ids particles are accepted from
+    peer_filter: PeerFilter,
+    /// Limits rate of particles accepted from a single init peer id
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    /// Particles with a larger TTL are counted as clamped or dropped
+    ttl_limit: Option<ParticleTtlLimit>,
+    /// Drops repeated deliveries of the same particle
+    dedup: Option<Arc<Mutex<ParticleDedup>>>,
     metrics: Option<DispatcherMetrics>,
+    particle_stream_metrics: Option<ParticleStreamMetrics>,
 }
 
 impl Dispatcher {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         peer_id: PeerId,
         aquamarine: AquamarineApi,
         effectors: Effectors,
         particle_failures_sink: Outlet<String>,
         particle_parallelism: Option<usize>,
+        verify_signatures: bool,
+        peer_filter: PeerFilter,
+        rate_limiter: Option<RateLimiter>,
+        ttl_limit: Option<ParticleTtlLimit>,
+        dedup: Option<ParticleDedup>,
         registry: Option<&mut Registry>,
+        particle_stream_metrics: Option<ParticleStreamMetrics>,
     ) -> Self {
         Self {
             peer_id,
@@ -56,7 +83,13 @@ impl Dispatcher {
             aquamarine,
             particle_failures_sink,
             particle_parallelism,
+            verify_signatures,
+            peer_filter,
+            rate_limiter: rate_limiter.map(|l| Arc::new(Mutex::new(l))),
+            ttl_limit,
+            dedup: dedup.map(|d| Arc::new(Mutex::new(d))),
             metrics: registry.map(|r| DispatcherMetrics::new(r, particle_parallelism)),
+            particle_stream_metrics,
         }
     }
 }
@@ -81,25 +114,139 @@ impl Dispatcher {
         let parallelism = self.particle_parallelism;
         let aquamarine = self.aquamarine;
         let metrics = self.metrics;
+        let verify_signatures = self.verify_signatures;
+        let peer_filter = self.peer_filter;
+        let rate_limiter = self.rate_limiter;
+        let ttl_limit = self.ttl_limit;
+        let dedup = self.dedup;
+        let stream_metrics = self.particle_stream_metrics;
         particle_stream
+            .inspect(move |_| {
+                // particle was taken from the stream, it doesn't count towards stream depth anymore
+                if let Some(m) = &stream_metrics {
+                    m.depth.dec();
+                }
+            })
             .for_each_concurrent(parallelism, move |particle| {
                 let aquamarine = aquamarine.clone();
                 let metrics = metrics.clone();
+                let span = particle.span();
+                let _guard = span.enter();
 
                 if particle.is_expired() {
                     metrics.map(|m| m.expired_particles.inc());
-                    log::info!("Particle {} expired", particle.id);
+                    log::debug!("Particle {} expired", particle.id);
                     return async {}.boxed();
                 }
 
-                async move {
+                if verify_signatures {
+                    if let Err(err) = particle.verify() {
+                        metrics.map(|m| m.invalid_signature_particles.inc());
+                        log::warn!("Dropping particle: {}", err);
+                        return async {}.boxed();
+                    }
+                }
+
+                // in clamp mode, particle is tracked by Aquamarine for no longer than `max_ttl`,
+                // but its TTL is kept, so the signature stays valid for the next peers
+                if let Some(limit) = ttl_limit {
+                    let max_ttl = limit.max_ttl.as_millis().try_into().unwrap_or(u32::MAX);
+                    if particle.ttl > max_ttl {
+                        if limit.reject {
+                            if let Some(m) = &metrics {
+                                m.ttl_rejected_particles.inc();
+                            }
+                            log::warn!(
+                                "Particle {} dropped: ttl {} ms exceeds maximum of {} ms",
+                                particle.id,
+                                particle.ttl,
+                                max_ttl
+                            );
+                            return async {}.boxed();
+                        }
+
+                        if let Some(m) = &metrics {
+                            m.ttl_clamped_particles.inc();
+                        }
+                        log::debug!(
+                            "Particle {} ttl {} ms exceeds maximum, it's tracked for {} ms",
+                            particle.id,
+                            particle.ttl,
+                            max_ttl
+                        );
+                    }
+                }
+
+                if let Err(reason) = peer_filter.check(&particle.init_peer_id) {
+                    log::warn!(
+                        "Particle {} from {} dropped: {}",
+                        particle.id,
+                        particle.init_peer_id,
+                        reason
+                    );
+                    return async {}.boxed();
+                }
+
+                // a particle over the rate limit waits for its turn,
+                // unless it would expire before that
+                let mut delay = Duration::ZERO;
+                if let Some(rate_limiter) = &rate_limiter {
+                    let acquired = rate_limiter
+                        .lock()
+                        .acquire(particle.init_peer_id, particle.time_to_live());
+                    match acquired {
+                        None => {
+                            metrics.map(|m| m.rate_limited_particles.inc());
+                            log::warn!(
+                                "Particle {} dropped: rate limit exceeded by {}, it would expire before being processed",
+                                particle.id,
+                                particle.init_peer_id
+                            );
+                            return async {}.boxed();
+                        }
+                        Some(d) if !d.is_zero() => {
+                            metrics.map(|m| m.rate_limit_delayed_particles.inc());
+                            log::debug!(
+                                "Particle {} delayed for {} ms: rate limit exceeded by {}",
+                                particle.id,
+                                d.as_millis(),
+                                particle.init_peer_id
+                            );
+                            delay = d;
+                        }
+                        Some(_) => {}
+                    }
+                }
+
+                // checked last, so a particle dropped for another reason isn't remembered,
+                // and its retry isn't mistaken for a duplicate
+                if let Some(dedup) = &dedup {
+                    if dedup.lock().is_duplicate(&particle) {
+                        metrics.map(|m| m.duplicate_particles.inc());
+                        log::debug!("Particle {} dropped: duplicate", particle.id);
+                        return async {}.boxed();
+                    }
+                }
+
+                let execute = async move {
                     aquamarine
-                        .execute(particle, None)
+                        .execute_in_span(particle, None, span.clone())
                         // do not log errors: Aquamarine will log them fine
                         .map(|_| ())
                         .await
                 }
-                .boxed()
+                .instrument(span.clone());
+
+                if delay.is_zero() {
+                    execute.boxed()
+                } else {
+                    // wait in a separate task, so a delayed particle doesn't hold a parallelism slot
+                    spawn(async move {
+                        async_std::task::sleep(delay).await;
+                        execute.await
+                    });
+                    async {}.boxed()
+                }
             })
             .await;
 
@@ -122,7 +269,11 @@ impl Dispatcher {
                     match effects {
                         Ok(effects) => {
                             // perform effects as instructed by aquamarine
-                            effectors.execute(effects, particle_failures.clone()).await;
+                            let span = effects.span.clone();
+                            effectors
+                                .execute(effects, particle_failures.clone())
+                                .instrument(span)
+                                .await;
                         }
                         Err(err) => {
                             // particles are sent in fire and forget fashion, so
@@ -141,3 +292,304 @@ impl Dispatcher {
         log::error!("Effects stream has ended");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use parking_lot::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::{Layer, Registry};
+
+    use aquamarine::{AquamarineApi, Command};
+    use connection_pool::ConnectionPoolApi;
+    use fluence_keypair::KeyPair;
+    use fluence_libp2p::RandomPeerId;
+    use kademlia::KademliaApi;
+    use now_millis::now_ms;
+    use particle_protocol::Particle;
+    use server_config::{ParticleDedupConfig, ParticleRateLimit, ParticleTtlLimit};
+
+    use crate::dispatcher::Dispatcher;
+    use crate::effectors::Effectors;
+    use crate::particle_dedup::ParticleDedup;
+    use crate::peer_filter::PeerFilter;
+    use crate::rate_limiter::RateLimiter;
+    use crate::Connectivity;
+
+    /// Passes `particles` through Dispatcher, returns how many of them reached Aquamarine
+    fn dispatch(verify_signatures: bool, particles: Vec<Particle>) -> usize {
+        dispatch_with(verify_signatures, None, None, particles).len()
+    }
+
+    /// Passes `particles` through Dispatcher, returns particles that reached Aquamarine
+    fn dispatch_with(
+        verify_signatures: bool,
+        ttl_limit: Option<ParticleTtlLimit>,
+        dedup: Option<ParticleDedup>,
+        particles: Vec<Particle>,
+    ) -> Vec<Particle> {
+        let (sender, particles_stream) = mpsc::unbounded();
+        for particle in particles {
+            sender.unbounded_send(particle).unwrap();
+        }
+        drop(sender);
+
+        dispatch_stream(verify_signatures, ttl_limit, dedup, None, particles_stream)
+    }
+
+    /// Passes particles through Dispatcher until the stream ends, returns particles that reached Aquamarine
+    fn dispatch_stream(
+        verify_signatures: bool,
+        ttl_limit: Option<ParticleTtlLimit>,
+        dedup: Option<ParticleDedup>,
+        rate_limiter: Option<RateLimiter>,
+        particles: mpsc::UnboundedReceiver<Particle>,
+    ) -> Vec<Particle> {
+        let (outlet, inlet) = mpsc::channel(16);
+        let aquamarine = AquamarineApi::new(outlet, Duration::from_secs(1));
+        let connectivity = Connectivity {
+            peer_id: RandomPeerId::random(),
+            kademlia: KademliaApi {
+                outlet: mpsc::unbounded().0,
+            },
+            connection_pool: ConnectionPoolApi {
+                outlet: mpsc::unbounded().0,
+                send_timeout: Duration::from_secs(1),
+            },
+            bootstrap_nodes: HashSet::new(),
+            bootstrap_frequency: 1,
+            bootstrapped: <_>::default(),
+            metrics: None,
+        };
+        let dispatcher = Dispatcher::new(
+            RandomPeerId::random(),
+            aquamarine,
+            Effectors::new(connectivity),
+            mpsc::unbounded().0,
+            None,
+            verify_signatures,
+            PeerFilter::default(),
+            rate_limiter,
+            ttl_limit,
+            dedup,
+            None,
+            None,
+        );
+
+        async_std::task::block_on(async move {
+            dispatcher.process_particles(particles).await;
+            // dispatcher is dropped by now, so the stream ends
+            inlet
+                .filter_map(|cmd| async move {
+                    match cmd {
+                        Command::Ingest { particle, .. } => Some(particle),
+                        _ => None,
+                    }
+                })
+                .collect()
+                .await
+        })
+    }
+
+    fn signed_particle() -> Particle {
+        let keypair = KeyPair::generate_ed25519();
+        let mut particle = Particle {
+            id: "particle".to_string(),
+            init_peer_id: keypair.get_peer_id(),
+            timestamp: now_ms() as u64,
+            ttl: 60_000,
+            script: "(null)".to_string(),
+            ..<_>::default()
+        };
+        particle.sign(&keypair).expect("sign particle");
+        particle
+    }
+
+    #[test]
+    fn valid_signature() {
+        assert_eq!(dispatch(true, vec![signed_particle()]), 1);
+    }
+
+    #[test]
+    fn forged_signature() {
+        // script was changed after signing
+        let mut forged = signed_particle();
+        forged.script = "(seq (null) (null))".to_string();
+        assert_eq!(dispatch(true, vec![forged.clone()]), 0);
+
+        // signature belongs to another peer
+        let mut impostor = signed_particle();
+        impostor.init_peer_id = KeyPair::generate_ed25519().get_peer_id();
+        assert_eq!(dispatch(true, vec![impostor]), 0);
+
+        // unsigned
+        let mut unsigned = signed_particle();
+        unsigned.signature = vec![];
+        assert_eq!(dispatch(true, vec![unsigned.clone()]), 0);
+
+        // signatures aren't checked unless enabled
+        assert_eq!(dispatch(false, vec![forged, unsigned]), 2);
+    }
+
+    #[test]
+    fn expired_particle() {
+        let mut expired = signed_particle();
+        expired.timestamp = now_ms() as u64 - 120_000;
+        assert_eq!(dispatch(false, vec![expired]), 0);
+
+        // timestamp + ttl overflow is considered expired
+        let mut overflow = signed_particle();
+        overflow.timestamp = u64::MAX;
+        assert_eq!(dispatch(false, vec![overflow]), 0);
+    }
+
+    #[test]
+    fn ttl_clamped() {
+        let limit = ParticleTtlLimit {
+            max_ttl: Duration::from_secs(10),
+            reject: false,
+        };
+        // TTL is capped by Aquamarine, particle itself is passed unchanged
+        let dispatched = dispatch_with(true, Some(limit), None, vec![signed_particle()]);
+        assert_eq!(dispatched.len(), 1);
+        assert_eq!(dispatched[0].ttl, 60_000);
+        // so it can be forwarded to peers verifying signatures
+        dispatched[0]
+            .verify()
+            .expect("clamped particle is still valid");
+
+        let mut short = signed_particle();
+        short.ttl = 5_000;
+        let dispatched = dispatch_with(false, Some(limit), None, vec![short]);
+        assert_eq!(dispatched.len(), 1);
+        assert_eq!(dispatched[0].ttl, 5_000);
+    }
+
+    #[test]
+    fn ttl_rejected() {
+        let limit = ParticleTtlLimit {
+            max_ttl: Duration::from_secs(10),
+            reject: true,
+        };
+        let mut short = signed_particle();
+        short.ttl = 10_000;
+        short.id = "short".to_string();
+
+        let dispatched = dispatch_with(false, Some(limit), None, vec![signed_particle(), short]);
+        let ids: Vec<_> = dispatched.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["short"]);
+    }
+
+    #[test]
+    fn duplicate_particle() {
+        let dedup = ParticleDedup::new(ParticleDedupConfig::default());
+        let particle = signed_particle();
+        let mut next_hop = particle.clone();
+        next_hop.data = b"new data".to_vec();
+
+        let particles = vec![particle.clone(), particle, next_hop];
+        let dispatched = dispatch_with(true, None, Some(dedup), particles);
+        assert_eq!(dispatched.len(), 2);
+    }
+
+    /// Passes `particles` through Dispatcher with a rate limit shared by all init peers
+    fn dispatch_rate_limited(limit: ParticleRateLimit, particles: Vec<Particle>) -> Vec<Particle> {
+        let dedup = ParticleDedup::new(ParticleDedupConfig::default());
+        let rate_limiter = RateLimiter::new(limit, RandomPeerId::random());
+
+        let (sender, particles_stream) = mpsc::unbounded();
+        for particle in particles {
+            sender.unbounded_send(particle).unwrap();
+        }
+        drop(sender);
+
+        dispatch_stream(
+            false,
+            None,
+            Some(dedup),
+            Some(rate_limiter),
+            particles_stream,
+        )
+    }
+
+    #[test]
+    fn rate_limited_particle_delayed() {
+        let limit = ParticleRateLimit {
+            particles_per_sec: 10.0,
+            burst: Some(1),
+        };
+        let particle = signed_particle();
+        let mut other = particle.clone();
+        other.id = "other".to_string();
+
+        let start = std::time::Instant::now();
+        // `other` takes the only token, `particle` waits for the next one,
+        // and its retry is dropped as a duplicate
+        let dispatched = dispatch_rate_limited(limit, vec![other, particle.clone(), particle]);
+
+        let ids: Vec<_> = dispatched.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["other", "particle"]);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn rate_limited_particle_dropped() {
+        let limit = ParticleRateLimit {
+            particles_per_sec: 1.0,
+            burst: Some(1),
+        };
+        let mut particle = signed_particle();
+        let mut other = particle.clone();
+        other.id = "other".to_string();
+        // would expire before the next token
+        particle.ttl = 500;
+
+        let dispatched = dispatch_rate_limited(limit, vec![other, particle]);
+
+        let ids: Vec<_> = dispatched.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["other"]);
+    }
+
+    /// Records `particle_id` field of every created span
+    #[derive(Clone, Default)]
+    struct ParticleSpans(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for ParticleSpans {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "particle_id" {
+                self.0.lock().push(format!("{value:?}"));
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for ParticleSpans {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            if attrs.metadata().name() == "particle" {
+                attrs.record(&mut self.clone());
+            }
+        }
+    }
+
+    #[test]
+    fn particle_span() {
+        let spans = ParticleSpans::default();
+        let subscriber = Registry::default().with(spans.clone());
+
+        let dispatched = tracing::subscriber::with_default(subscriber, || {
+            dispatch(false, vec![signed_particle()])
+        });
+        assert_eq!(dispatched, 1);
+
+        // span is created once and passed along with the particle
+        let particle_ids = spans.0.lock();
+        assert_eq!(*particle_ids, vec!["particle".to_string()]);
+    }
+}
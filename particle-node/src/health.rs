@@ -0,0 +1,176 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_std::future::timeout;
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use aquamarine::{AquamarineApi, VmPoolStatus};
+use fluence_libp2p::types::{BackPressuredOutlet, OneshotOutlet};
+
+/// Components that didn't respond within that time are considered unresponsive
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+/// How many health checks may wait for the event loop.
+/// When the queue is full, event loop is reported unresponsive right away
+pub const HEALTH_CHECK_QUEUE_SIZE: usize = 16;
+
+#[derive(Serialize, Debug)]
+pub struct VmPoolHealth {
+    pub size: usize,
+    pub created: usize,
+    pub free: usize,
+}
+
+impl From<VmPoolStatus> for VmPoolHealth {
+    fn from(status: VmPoolStatus) -> Self {
+        Self {
+            size: status.pool_size,
+            created: status.created,
+            free: status.free,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    pub event_loop_responsive: bool,
+    /// Absent if the pool didn't respond in time
+    pub vm_pool: Option<VmPoolHealth>,
+    /// Number of established connections, absent if the event loop didn't respond in time
+    pub connections: Option<u32>,
+}
+
+/// Checks that the node event loop and the AquaVM pool are alive without executing particles.
+/// Node is healthy if both respond in time and the pool has at least one created VM.
+#[derive(Clone)]
+pub struct HealthCheck {
+    aquamarine_api: AquamarineApi,
+    /// Node event loop replies with the number of established connections.
+    /// Shared between clones, as each clone of a sender would get an extra slot in the queue
+    event_loop: Arc<Mutex<BackPressuredOutlet<OneshotOutlet<u32>>>>,
+}
+
+impl HealthCheck {
+    pub fn new(
+        aquamarine_api: AquamarineApi,
+        event_loop: BackPressuredOutlet<OneshotOutlet<u32>>,
+    ) -> Self {
+        Self {
+            aquamarine_api,
+            event_loop: Arc::new(Mutex::new(event_loop)),
+        }
+    }
+
+    pub async fn check(&self) -> HealthStatus {
+        let connections = async {
+            let (outlet, inlet) = oneshot::channel();
+            self.event_loop.lock().try_send(outlet).ok()?;
+            timeout(HEALTH_CHECK_TIMEOUT, inlet).await.ok()?.ok()
+        };
+        let vm_pool = async {
+            let status = self.aquamarine_api.clone().vm_pool_status();
+            timeout(HEALTH_CHECK_TIMEOUT, status).await.ok()?.ok()
+        };
+        let (connections, vm_pool) = futures::join!(connections, vm_pool);
+
+        let event_loop_responsive = connections.is_some();
+        let has_vms = vm_pool.map_or(false, |status| status.created > 0);
+        HealthStatus {
+            healthy: event_loop_responsive && has_vms,
+            event_loop_responsive,
+            vm_pool: vm_pool.map(Into::into),
+            connections,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReadinessStatus {
+    pub ready: bool,
+    pub builtins_deployed: bool,
+    pub bootstrapped: bool,
+}
+
+/// Node is ready once builtins are deployed and Kademlia bootstrap has succeeded
+#[derive(Clone, Debug)]
+pub struct Readiness {
+    builtins_deployed: Arc<AtomicBool>,
+    /// Shared with `Connectivity`, which sets it after the first successful bootstrap
+    bootstrapped: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    pub fn new(bootstrapped: Arc<AtomicBool>) -> Self {
+        Self {
+            builtins_deployed: <_>::default(),
+            bootstrapped,
+        }
+    }
+
+    pub fn set_builtins_deployed(&self) {
+        self.builtins_deployed.store(true, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> ReadinessStatus {
+        let builtins_deployed = self.builtins_deployed.load(Ordering::Relaxed);
+        let bootstrapped = self.bootstrapped.load(Ordering::Relaxed);
+        ReadinessStatus {
+            ready: builtins_deployed && bootstrapped,
+            builtins_deployed,
+            bootstrapped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::channel::mpsc;
+    use futures::future::join_all;
+
+    use aquamarine::AquamarineApi;
+
+    use crate::health::{HealthCheck, HEALTH_CHECK_QUEUE_SIZE};
+
+    #[test]
+    fn stuck_event_loop_queue_is_bounded() {
+        let (aquamarine_outlet, _aquamarine_inlet) = mpsc::channel(1);
+        let aquamarine_api = AquamarineApi::new(aquamarine_outlet, Duration::from_secs(1));
+        let (event_loop, mut event_loop_inlet) = mpsc::channel(HEALTH_CHECK_QUEUE_SIZE);
+        let health = HealthCheck::new(aquamarine_api, event_loop);
+
+        // event loop never replies
+        let checks = (0..HEALTH_CHECK_QUEUE_SIZE * 2).map(|_| health.check());
+        let statuses = async_std::task::block_on(join_all(checks));
+        assert!(statuses
+            .iter()
+            .all(|s| !s.healthy && !s.event_loop_responsive));
+
+        let mut queued = 0;
+        while let Ok(Some(_)) = event_loop_inlet.try_next() {
+            queued += 1;
+        }
+        // a single sender is allowed one message on top of the buffer
+        assert_eq!(queued, HEALTH_CHECK_QUEUE_SIZE + 1);
+    }
+}
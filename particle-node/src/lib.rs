@@ -31,13 +31,18 @@
 mod connectivity;
 mod dispatcher;
 mod effectors;
+mod health;
 mod metrics;
 mod node;
+mod particle_dedup;
+mod peer_filter;
+mod rate_limiter;
 mod tasks;
 
 mod behaviour {
     mod identify;
     mod network;
+    mod ping;
 
     pub use network::{FluenceNetworkBehaviour, FluenceNetworkBehaviourEvent};
 }
@@ -26,39 +26,43 @@
     unreachable_patterns
 )]
 
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_std::future::timeout;
+use async_std::task::{self, JoinHandle};
+
 use base64::{engine::general_purpose::STANDARD as base64, Engine};
 use clap::App;
-use env_logger::Env;
-use eyre::WrapErr;
+use eyre::{eyre, WrapErr};
 use futures::channel::oneshot;
-use log::LevelFilter;
+use humantime_serde::re::humantime::{format_duration as pretty, format_rfc3339_micros};
+use tracing_subscriber::fmt::format::{Format, Json, JsonFields, Writer};
+use tracing_subscriber::fmt::time::FormatTime;
+use tracing_subscriber::fmt::{MakeWriter, SubscriberBuilder};
+use tracing_subscriber::EnvFilter;
 
-use air_interpreter_fs::write_default_air_interpreter;
+use air_interpreter_fs::{check_air_interpreter_sha256, write_default_air_interpreter};
 use aquamarine::{VmConfig, AVM};
 use config_utils::to_peer_id;
 use ctrlc_adapter::block_until_ctrlc;
 use fs_utils::to_abs_path;
 use particle_node::Node;
 use server_config::args::create_args;
+use server_config::config_keys::{DRY_RUN_CONFIG, LOG_FORMAT};
 use server_config::{load_config, ResolvedConfig};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
 const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
+/// Node stops its subsystems within the shutdown grace period, this is added on top of it
+const SHUTDOWN_MARGIN: Duration = Duration::from_secs(5);
 
 trait Stoppable {
-    fn stop(self);
+    fn stop(self) -> eyre::Result<()>;
 }
 
 fn main() -> eyre::Result<()> {
-    // TODO: maybe set log level via flag?
-    env_logger::Builder::from_env(Env::default().default_filter_or("INFO"))
-        .format_timestamp_micros()
-        // Disable most spamming modules
-        .filter_module("cranelift_codegen", LevelFilter::Off)
-        .filter_module("wasmer_wasi_fl", LevelFilter::Off)
-        .init();
-
     let version = format!("{}; AIR version {}", VERSION, air_interpreter_wasm::VERSION);
     let authors = format!("by {AUTHORS}");
     let arg_matches = App::new("Fluence node")
@@ -69,6 +73,12 @@ fn main() -> eyre::Result<()> {
         .args(create_args().as_slice())
         .get_matches();
 
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    let reload_log_filter = init_logger(
+        arg_matches.value_of(LOG_FORMAT),
+        log_filter(&<_>::default(), rust_log.as_deref())?,
+    );
+
     log::info!(
         r#"
 +-------------------------------------------------+
@@ -83,22 +93,119 @@ fn main() -> eyre::Result<()> {
     "#
     );
 
+    let dry_run = arg_matches.is_present(DRY_RUN_CONFIG);
     let config = load_config(arg_matches)?;
+    // per-module levels are known only after config is loaded
+    reload_log_filter(log_filter(&config.log_levels, rust_log.as_deref())?)?;
+    if dry_run {
+        // config is valid and directories are writable, don't start the node
+        println!("{}", config.summary());
+        return Ok(());
+    }
 
     let interpreter_path = to_abs_path(config.dir_config.air_interpreter_path.clone());
     write_default_air_interpreter(&interpreter_path)?;
+    if let Some(expected_sha256) = &config.node_config.air_interpreter_sha256 {
+        check_air_interpreter_sha256(&interpreter_path, expected_sha256)?;
+    }
     log::info!("AIR interpreter: {:?}", interpreter_path);
 
     let fluence = start_fluence(config)?;
     log::info!("Fluence has been successfully started.");
 
-    log::info!("Waiting for Ctrl-C to exit...");
+    log::info!("Waiting for Ctrl-C or SIGTERM to exit...");
     block_until_ctrlc();
 
     log::info!("Shutting down...");
-    fluence.stop();
+    fluence.stop()
+}
 
-    Ok(())
+/// Replaces filter of the installed logger
+type ReloadLogFilter = Box<dyn Fn(EnvFilter) -> eyre::Result<()>>;
+
+fn init_logger(log_format: Option<&str>, filter: EnvFilter) -> ReloadLogFilter {
+    // `init` also installs a bridge, so events from `log` macros are recorded within particle spans
+    let reload: ReloadLogFilter = match log_format {
+        Some("json") => {
+            let logger = json_logger(filter, std::io::stdout).with_filter_reloading();
+            let handle = logger.reload_handle();
+            logger.init();
+            Box::new(move |filter| Ok(handle.reload(filter)?))
+        }
+        _ => {
+            let logger = tracing_subscriber::fmt()
+                .with_timer(MicrosTimer)
+                .with_env_filter(filter)
+                .with_filter_reloading();
+            let handle = logger.reload_handle();
+            logger.init();
+            Box::new(move |filter| Ok(handle.reload(filter)?))
+        }
+    };
+
+    // the bridge drops `log` records above the max level of the initial filter.
+    // Let them all through, so that levels raised on reload take effect
+    log::set_max_level(log::LevelFilter::Trace);
+
+    reload
+}
+
+/// Writes UTC timestamps with microseconds, e.g. `2023-01-01T00:00:00.123456Z`
+struct MicrosTimer;
+
+impl FormatTime for MicrosTimer {
+    fn format_time(&self, w: &mut Writer<'_>) -> std::fmt::Result {
+        write!(w, "{}", format_rfc3339_micros(std::time::SystemTime::now()))
+    }
+}
+
+/// Per-module `log_levels` from config are applied on top of the default level,
+/// `rust_log` directives are applied last, so they win.
+fn log_filter(
+    log_levels: &HashMap<String, log::LevelFilter>,
+    rust_log: Option<&str>,
+) -> eyre::Result<EnvFilter> {
+    let mut filter = EnvFilter::new("info")
+        // Disable most spamming modules
+        .add_directive("cranelift_codegen=off".parse()?)
+        .add_directive("wasmer_wasi_fl=off".parse()?);
+
+    for (target, level) in log_levels {
+        let directive = format!("{target}={level}");
+        filter = filter.add_directive(
+            directive
+                .parse()
+                .wrap_err_with(|| format!("invalid log level directive '{directive}'"))?,
+        );
+    }
+
+    for directive in rust_log.into_iter().flat_map(|l| l.split(',')) {
+        // invalid RUST_LOG directives were always ignored, don't fail the node on them
+        if let Ok(directive) = directive.trim().parse() {
+            filter = filter.add_directive(directive);
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Writes an object per line with timestamp, level, target and message.
+/// Events within a particle span also carry its `particle_id` under the `span` key.
+fn json_logger<W>(
+    filter: EnvFilter,
+    writer: W,
+) -> SubscriberBuilder<JsonFields, Format<Json, MicrosTimer>, EnvFilter, W>
+where
+    W: for<'w> MakeWriter<'w> + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_timer(MicrosTimer)
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_env_filter(filter)
+        .with_writer(writer)
 }
 
 // NOTE: to stop Fluence just call Stoppable::stop()
@@ -112,30 +219,46 @@ fn start_fluence(config: ResolvedConfig) -> eyre::Result<impl Stoppable> {
 
     let listen_addrs = config.listen_multiaddrs();
     let vm_config = vm_config(&config);
+    let shutdown_timeout = config.shutdown_grace_period + SHUTDOWN_MARGIN;
 
     let mut node: Box<Node<AVM<_>>> =
         Node::new(config, vm_config, VERSION).wrap_err("error create node instance")?;
     node.listen(listen_addrs).wrap_err("error on listen")?;
 
-    let node_exit_outlet = node.start().wrap_err("node failed to start")?;
+    let (node_exit_outlet, node_task) =
+        node.start_with_handle().wrap_err("node failed to start")?;
 
-    struct Fluence {
-        node_exit_outlet: oneshot::Sender<()>,
-    }
+    Ok(Fluence {
+        node_exit_outlet,
+        node_task,
+        shutdown_timeout,
+    })
+}
 
-    impl Stoppable for Fluence {
-        fn stop(self) {
-            self.node_exit_outlet
-                .send(())
-                .expect("failed to stop node through exit outlet");
+struct Fluence {
+    node_exit_outlet: oneshot::Sender<()>,
+    /// Completes once the node has stopped
+    node_task: JoinHandle<()>,
+    shutdown_timeout: Duration,
+}
+
+impl Stoppable for Fluence {
+    fn stop(self) -> eyre::Result<()> {
+        // receiver is gone if the node task has already died, don't panic on that
+        if self.node_exit_outlet.send(()).is_err() {
+            log::warn!("Unable to send stop signal to node: exit inlet is dropped");
+            return Err(eyre!("failed to stop node through exit outlet"));
         }
-    }
 
-    Ok(Fluence { node_exit_outlet })
+        // let the node cancel its components before the process exits
+        let shutdown_timeout = self.shutdown_timeout;
+        task::block_on(timeout(shutdown_timeout, self.node_task))
+            .map_err(|_| eyre!("node didn't stop in {}", pretty(shutdown_timeout)))
+    }
 }
 
 fn vm_config(config: &ResolvedConfig) -> VmConfig {
-    VmConfig::new(
+    let mut vm_config = VmConfig::new(
         to_peer_id(&config.root_key_pair.clone().into()),
         config.dir_config.avm_base_dir.clone(),
         config.dir_config.air_interpreter_path.clone(),
@@ -143,5 +266,171 @@ fn vm_config(config: &ResolvedConfig) -> VmConfig {
             .node_config
             .aquavm_max_heap_size
             .map(|byte_size| byte_size.as_u64()),
-    )
+    );
+    vm_config.keep_particle_vaults = config.node_config.keep_particle_vaults;
+
+    vm_config
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_std::task;
+    use futures::channel::oneshot;
+    use parking_lot::Mutex;
+    use serde_json::Value;
+    use tracing_subscriber::EnvFilter;
+
+    use crate::{json_logger, log_filter, Fluence, Stoppable};
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stop_with_dropped_receiver() {
+        let (node_exit_outlet, node_exit_inlet) = oneshot::channel();
+        drop(node_exit_inlet);
+
+        let node_task = task::spawn(async {});
+
+        let result = Fluence {
+            node_exit_outlet,
+            node_task,
+            shutdown_timeout: Duration::from_secs(1),
+        }
+        .stop();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn stop_waits_for_node() {
+        let (node_exit_outlet, node_exit_inlet) = oneshot::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let node_task = task::spawn({
+            let stopped = stopped.clone();
+            async move {
+                node_exit_inlet.await.unwrap();
+                // node is draining
+                task::sleep(Duration::from_millis(100)).await;
+                stopped.store(true, Ordering::SeqCst);
+            }
+        });
+
+        Fluence {
+            node_exit_outlet,
+            node_task,
+            shutdown_timeout: Duration::from_secs(1),
+        }
+        .stop()
+        .unwrap();
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn json_logs() {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = json_logger(EnvFilter::new("info"), move || writer.clone()).finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside of particle");
+            let span = tracing::info_span!("particle", particle_id = "some_particle");
+            let _guard = span.enter();
+            tracing::warn!("within particle");
+            tracing::debug!("filtered out");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().clone()).unwrap();
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("log line must be json"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+
+        for line in &lines {
+            let timestamp = line["timestamp"].as_str().expect("timestamp");
+            assert_eq!(timestamp.len(), "2023-01-01T00:00:00.000000Z".len());
+            assert!(line["target"].is_string());
+        }
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "outside of particle");
+        assert!(lines[0].get("span").is_none());
+
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["message"], "within particle");
+        assert_eq!(lines[1]["span"]["particle_id"], "some_particle");
+    }
+
+    /// Returns messages logged by `f` under filter made of `log_levels` and `rust_log`
+    fn filtered_messages(
+        log_levels: HashMap<String, log::LevelFilter>,
+        rust_log: Option<&str>,
+        f: impl FnOnce(),
+    ) -> Vec<String> {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let filter = log_filter(&log_levels, rust_log).unwrap();
+        let subscriber = json_logger(filter, move || writer.clone()).finish();
+        tracing::subscriber::with_default(subscriber, f);
+
+        let output = String::from_utf8(buffer.0.lock().clone()).unwrap();
+        output
+            .lines()
+            .map(|line| {
+                let line: Value = serde_json::from_str(line).unwrap();
+                line["message"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn module_log_levels() {
+        let log_levels = HashMap::from([
+            ("aquamarine".to_string(), log::LevelFilter::Debug),
+            ("connection_pool".to_string(), log::LevelFilter::Warn),
+        ]);
+        let log = || {
+            tracing::debug!(target: "aquamarine", "aquamarine debug");
+            tracing::trace!(target: "aquamarine::actor", "aquamarine trace");
+            tracing::info!(target: "connection_pool", "connection_pool info");
+            tracing::warn!(target: "connection_pool", "connection_pool warn");
+            tracing::info!(target: "sorcerer", "sorcerer info");
+            tracing::debug!(target: "sorcerer", "sorcerer debug");
+        };
+
+        let messages = filtered_messages(log_levels.clone(), None, log);
+        assert_eq!(
+            messages,
+            vec!["aquamarine debug", "connection_pool warn", "sorcerer info"]
+        );
+
+        // RUST_LOG overrides config
+        let rust_log = Some("aquamarine=trace,connection_pool=info,sorcerer=off");
+        let messages = filtered_messages(log_levels, rust_log, log);
+        assert_eq!(
+            messages,
+            vec![
+                "aquamarine debug",
+                "aquamarine trace",
+                "connection_pool info",
+                "connection_pool warn"
+            ]
+        );
+    }
 }
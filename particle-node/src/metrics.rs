@@ -23,19 +23,36 @@ use futures::FutureExt;
 use parking_lot::Mutex;
 use prometheus_client::registry::Registry;
 
+use crate::health::{HealthCheck, Readiness};
+
+#[derive(Clone)]
+struct EndpointState {
+    registry: Arc<Mutex<Registry>>,
+    health: HealthCheck,
+    readiness: Readiness,
+}
+
+/// Serves `/metrics`, `/health` and `/ready`.
+/// The latter two return 200 if the node is healthy (ready) and 503 otherwise
 pub fn start_metrics_endpoint(
     registry: Registry,
+    health: HealthCheck,
+    readiness: Readiness,
     listen_addr: SocketAddr,
 ) -> BoxFuture<'static, io::Result<()>> {
     use prometheus_client::encoding::text::encode;
     use tide::{Error, StatusCode::InternalServerError};
 
-    let registry = Arc::new(Mutex::new(registry));
-    let mut app = tide::with_state(registry);
+    let state = EndpointState {
+        registry: Arc::new(Mutex::new(registry)),
+        health,
+        readiness,
+    };
+    let mut app = tide::with_state(state);
     app.at("/metrics")
-        .get(|req: tide::Request<Arc<Mutex<Registry>>>| async move {
+        .get(|req: tide::Request<EndpointState>| async move {
             let mut encoded = Vec::new();
-            encode(&mut encoded, &req.state().lock()).map_err(|e| {
+            encode(&mut encoded, &req.state().registry.lock()).map_err(|e| {
                 let msg = format!("Error while text-encoding metrics: {e}");
                 log::warn!("{}", msg);
                 Error::from_str(InternalServerError, msg)
@@ -46,6 +63,25 @@ pub fn start_metrics_endpoint(
                 .build();
             Ok(response)
         });
+    app.at("/health")
+        .get(|req: tide::Request<EndpointState>| async move {
+            let status = req.state().health.check().await;
+            let code = if status.healthy { 200 } else { 503 };
+            let response = tide::Response::builder(code)
+                .body(tide::Body::from_json(&status)?)
+                .build();
+            Ok(response)
+        });
+
+    app.at("/ready")
+        .get(|req: tide::Request<EndpointState>| async move {
+            let status = req.state().readiness.status();
+            let code = if status.ready { 200 } else { 503 };
+            let response = tide::Response::builder(code)
+                .body(tide::Body::from_json(&status)?)
+                .build();
+            Ok(response)
+        });
 
     app.listen(listen_addr).boxed()
 }
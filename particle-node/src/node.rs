@@ -15,17 +15,22 @@
  */
 
 use std::sync::Arc;
+use std::time::Duration;
 use std::{io, net::SocketAddr};
 
-use async_std::task;
+use async_std::task::{self, JoinHandle};
 use eyre::WrapErr;
 use fluence_keypair::KeyPair;
 use futures::{
-    channel::{mpsc::unbounded, oneshot},
+    channel::{
+        mpsc::{channel, unbounded},
+        oneshot,
+    },
     select,
     stream::StreamExt,
     FutureExt,
 };
+use humantime_serde::re::humantime::format_duration as pretty;
 use libp2p::swarm::SwarmEvent;
 use libp2p::{
     core::{muxing::StreamMuxerBox, transport::Boxed, Multiaddr},
@@ -45,12 +50,12 @@ use connection_pool::{ConnectionPoolApi, ConnectionPoolT};
 use fluence_libp2p::types::{BackPressuredInlet, Inlet};
 use fluence_libp2p::{build_transport, types::OneshotOutlet};
 use key_manager::KeyManager;
-use particle_builtins::{Builtins, NodeInfo};
+use particle_builtins::{Builtins, ExternalAddressCommand, ExternalAddressesApi, NodeInfo};
 use particle_execution::ParticleFunctionStatic;
 use particle_protocol::Particle;
 use peer_metrics::{
-    ConnectionPoolMetrics, ConnectivityMetrics, ParticleExecutorMetrics, ServicesMetrics,
-    ServicesMetricsBackend, VmPoolMetrics,
+    ConnectionPoolMetrics, ConnectivityMetrics, KademliaMetrics, ParticleExecutorMetrics,
+    ServicesMetrics, ServicesMetricsBackend, VmPoolMetrics,
 };
 use script_storage::{ScriptStorageApi, ScriptStorageBackend, ScriptStorageConfig};
 use server_config::{NetworkConfig, ResolvedConfig, ServicesConfig};
@@ -60,7 +65,12 @@ use spell_event_bus::bus::SpellEventBus;
 
 use crate::dispatcher::Dispatcher;
 use crate::effectors::Effectors;
+use crate::health::{HealthCheck, Readiness, HEALTH_CHECK_QUEUE_SIZE};
 use crate::metrics::start_metrics_endpoint;
+use crate::particle_dedup::ParticleDedup;
+use crate::peer_filter::PeerFilter;
+use crate::rate_limiter::RateLimiter;
+use crate::tasks::cancel_within;
 use crate::Connectivity;
 
 use super::behaviour::FluenceNetworkBehaviour;
@@ -81,15 +91,19 @@ pub struct Node<RT: AquaRuntime> {
     spell_event_bus: SpellEventBus,
     spell_events_stream: Inlet<TriggerEvent>,
     sorcerer: Sorcerer,
+    external_addresses_stream: Inlet<ExternalAddressCommand>,
 
     registry: Option<Registry>,
     services_metrics_backend: ServicesMetricsBackend,
 
     metrics_listen_addr: SocketAddr,
+    shutdown_grace_period: Duration,
 
     pub builtins_management_peer_id: PeerId,
 
     pub key_manager: KeyManager,
+
+    pub readiness: Readiness,
 }
 
 impl<RT: AquaRuntime> Node<RT> {
@@ -116,12 +130,16 @@ impl<RT: AquaRuntime> Node<RT> {
         let services_config = ServicesConfig::new(
             key_manager.get_host_peer_id(),
             config.dir_config.services_base_dir.clone(),
-            config_utils::particles_vault_dir(&config.dir_config.avm_base_dir),
+            config.dir_config.particles_vault_dir.clone(),
             config.services_envs.clone(),
             config.management_peer_id,
             builtins_peer_id,
             config.node_config.module_max_heap_size,
             config.node_config.module_default_heap_size,
+            config.node_config.max_services_per_owner,
+            config.node_config.max_call_depth,
+            config.node_config.max_script_size,
+            config.node_config.module_uploads,
         )
         .expect("create services config");
 
@@ -133,6 +151,10 @@ impl<RT: AquaRuntime> Node<RT> {
         let libp2p_metrics = metrics_registry.as_mut().map(Metrics::new);
         let connectivity_metrics = metrics_registry.as_mut().map(ConnectivityMetrics::new);
         let connection_pool_metrics = metrics_registry.as_mut().map(ConnectionPoolMetrics::new);
+        let kademlia_metrics = metrics_registry.as_mut().map(KademliaMetrics::new);
+        let particle_stream_metrics = connection_pool_metrics
+            .as_ref()
+            .map(|m| m.particle_stream.clone());
         let plumber_metrics = metrics_registry.as_mut().map(ParticleExecutorMetrics::new);
         let vm_pool_metrics = metrics_registry.as_mut().map(VmPoolMetrics::new);
 
@@ -140,17 +162,20 @@ impl<RT: AquaRuntime> Node<RT> {
             libp2p_metrics,
             connectivity_metrics,
             connection_pool_metrics,
+            kademlia_metrics,
             key_pair,
             &config,
             node_version,
         );
 
+        let external_addresses = config.external_addresses();
         let (swarm, connectivity, particle_stream) = Self::swarm(
             key_manager.get_host_peer_id(),
             network_config,
             transport,
-            config.external_addresses(),
+            external_addresses.clone(),
         );
+        let readiness = Readiness::new(connectivity.bootstrapped.clone());
 
         let (particle_failures_out, particle_failures_in) = unbounded();
 
@@ -179,19 +204,27 @@ impl<RT: AquaRuntime> Node<RT> {
                 )
             };
 
+        let (external_addresses_out, external_addresses_stream) = unbounded();
+        let external_addresses_api =
+            ExternalAddressesApi::new(external_addresses_out, config.allow_local_addresses);
+
         let builtins = Arc::new(Self::builtins(
             connectivity.clone(),
-            config.external_addresses(),
+            external_addresses,
+            external_addresses_api,
             services_config,
             script_storage_api,
             services_metrics,
             config.node_config.root_key_pair.clone(),
+            key_manager.clone(),
         ));
 
         let (effects_out, effects_in) = unbounded();
 
-        let pool_config =
+        let mut pool_config =
             VmPoolConfig::new(config.aquavm_pool_size, config.particle_execution_timeout);
+        pool_config.log_failed_traces = config.log_failed_particle_traces;
+        pool_config.max_particle_ttl = config.max_particle_ttl.map(|limit| limit.max_ttl);
         let (aquavm_pool, aquamarine_api) = AquamarineBackend::new(
             pool_config,
             vm_config,
@@ -205,13 +238,29 @@ impl<RT: AquaRuntime> Node<RT> {
         let dispatcher = {
             let failures = particle_failures_out;
             let parallelism = config.particle_processor_parallelism;
+            let peer_filter = PeerFilter::new(
+                config.allowed_peer_ids.clone(),
+                config.denied_peer_ids.clone(),
+                [key_manager.get_host_peer_id(), config.management_peer_id],
+            );
+            let rate_limiter = config
+                .particle_rate_limit
+                .map(|limit| RateLimiter::new(limit, config.management_peer_id));
+            let dedup = (config.particle_dedup.capacity > 0)
+                .then(|| ParticleDedup::new(config.particle_dedup));
             Dispatcher::new(
                 key_manager.get_host_peer_id(),
                 aquamarine_api.clone(),
                 effectors,
                 failures,
                 parallelism,
+                config.verify_particle_signatures,
+                peer_filter,
+                rate_limiter,
+                config.max_particle_ttl,
+                dedup,
                 metrics_registry.as_mut(),
+                particle_stream_metrics,
             )
         };
 
@@ -261,11 +310,14 @@ impl<RT: AquaRuntime> Node<RT> {
             spell_event_bus,
             spell_events_stream,
             sorcerer,
+            external_addresses_stream,
             metrics_registry,
             services_metrics_backend,
             config.metrics_listen_addr(),
+            config.shutdown_grace_period,
             builtins_peer_id,
             key_manager,
+            readiness,
         ))
     }
 
@@ -294,24 +346,31 @@ impl<RT: AquaRuntime> Node<RT> {
     pub fn builtins(
         connectivity: Connectivity,
         external_addresses: Vec<Multiaddr>,
+        external_addresses_api: ExternalAddressesApi,
         services_config: ServicesConfig,
         script_storage_api: ScriptStorageApi,
         services_metrics: ServicesMetrics,
         root_keypair: KeyPair,
+        key_manager: KeyManager,
     ) -> Builtins<Connectivity> {
         let node_info = NodeInfo {
             external_addresses,
             node_version: env!("CARGO_PKG_VERSION"),
             air_version: air_interpreter_wasm::VERSION,
+            git_commit: env!("BUILD_GIT_COMMIT"),
+            build_timestamp: env!("BUILD_TIMESTAMP"),
+            rustc_version: env!("BUILD_RUSTC_VERSION"),
         };
 
         Builtins::new(
             connectivity,
             script_storage_api,
             node_info,
+            external_addresses_api,
             services_config,
             services_metrics,
             root_keypair,
+            key_manager,
         )
     }
 }
@@ -332,13 +391,16 @@ impl<RT: AquaRuntime> Node<RT> {
         spell_event_bus: SpellEventBus,
         spell_events_stream: Inlet<TriggerEvent>,
         sorcerer: Sorcerer,
+        external_addresses_stream: Inlet<ExternalAddressCommand>,
 
         registry: Option<Registry>,
         services_metrics_backend: ServicesMetricsBackend,
         metrics_listen_addr: SocketAddr,
+        shutdown_grace_period: Duration,
 
         builtins_management_peer_id: PeerId,
         key_manager: KeyManager,
+        readiness: Readiness,
     ) -> Box<Self> {
         let node_service = Self {
             particle_stream,
@@ -354,21 +416,30 @@ impl<RT: AquaRuntime> Node<RT> {
             spell_event_bus,
             spell_events_stream,
             sorcerer,
+            external_addresses_stream,
 
             registry,
             services_metrics_backend,
             metrics_listen_addr,
+            shutdown_grace_period,
 
             builtins_management_peer_id,
             key_manager,
+            readiness,
         };
 
         Box::new(node_service)
     }
 
     /// Starts node service
-    #[allow(clippy::boxed_local)] // Mike said it should be boxed
     pub fn start(self: Box<Self>) -> eyre::Result<OneshotOutlet<()>> {
+        self.start_with_handle().map(|(exit_outlet, _)| exit_outlet)
+    }
+
+    /// Starts node service. Returned handle completes once the node has stopped
+    /// after the exit signal and all of its components are cancelled
+    #[allow(clippy::boxed_local)] // Mike said it should be boxed
+    pub fn start_with_handle(self: Box<Self>) -> eyre::Result<(OneshotOutlet<()>, JoinHandle<()>)> {
         let (exit_outlet, exit_inlet) = oneshot::channel();
         let mut exit_inlet = exit_inlet.into_stream().fuse();
 
@@ -382,14 +453,26 @@ impl<RT: AquaRuntime> Node<RT> {
         let spell_event_bus = self.spell_event_bus;
         let spell_events_stream = self.spell_events_stream;
         let sorcerer = self.sorcerer;
+        let mut external_addresses_stream = self.external_addresses_stream.fuse();
         let registry = self.registry;
         let services_metrics_backend = self.services_metrics_backend;
         let metrics_listen_addr = self.metrics_listen_addr;
-
-        task::spawn(async move {
+        let shutdown_grace_period = self.shutdown_grace_period;
+        let (health_outlet, health_inlet) = channel(HEALTH_CHECK_QUEUE_SIZE);
+        let health = HealthCheck::new(self.aquamarine_api, health_outlet);
+        let readiness = self.readiness;
+        let endpoint_readiness = readiness.clone();
+        let mut health_inlet = health_inlet.fuse();
+
+        let node_task = task::spawn(async move {
             let (metrics_fut, libp2p_metrics) = if let Some(mut registry) = registry {
                 let libp2p_metrics = Metrics::new(&mut registry);
-                let fut = start_metrics_endpoint(registry, metrics_listen_addr);
+                let fut = start_metrics_endpoint(
+                    registry,
+                    health,
+                    endpoint_readiness,
+                    metrics_listen_addr,
+                );
                 (fut, Some(libp2p_metrics))
             } else {
                 (futures::future::ready(Ok(())).boxed(), None)
@@ -408,8 +491,14 @@ impl<RT: AquaRuntime> Node<RT> {
                 select!(
                     e = swarm.select_next_some() => {
                         if let Some(m) = libp2p_metrics.as_ref() { m.record(&e) }
-                        if let SwarmEvent::Behaviour(FluenceNetworkBehaviourEvent::Identify(i)) = e {
-                            swarm.behaviour_mut().inject_identify_event(i, true)
+                        match e {
+                            SwarmEvent::Behaviour(FluenceNetworkBehaviourEvent::Identify(i)) => {
+                                swarm.behaviour_mut().inject_identify_event(i, true)
+                            }
+                            SwarmEvent::Behaviour(FluenceNetworkBehaviourEvent::Ping(p)) => {
+                                swarm.behaviour_mut().inject_ping_event(p)
+                            }
+                            _ => {}
                         }
                     },
                     e = metrics_fut => {
@@ -417,6 +506,22 @@ impl<RT: AquaRuntime> Node<RT> {
                             log::warn!("Metrics returned error: {}", err)
                         }
                     },
+                    command = external_addresses_stream.select_next_some() => {
+                        match command {
+                            ExternalAddressCommand::Add(addr) => {
+                                log::info!("Adding external address {}", addr);
+                                swarm.add_external_address(addr, AddressScore::Finite(1));
+                            }
+                            ExternalAddressCommand::Remove(addr) => {
+                                log::info!("Removing external address {}", addr);
+                                swarm.remove_external_address(&addr);
+                            }
+                        }
+                    },
+                    reply = health_inlet.select_next_some() => {
+                        let info = swarm.network_info();
+                        reply.send(info.connection_counters().num_established()).ok();
+                    },
                     _ = connectivity => {},
                     _ = dispatcher => {},
                     event = exit_inlet.next() => {
@@ -429,21 +534,39 @@ impl<RT: AquaRuntime> Node<RT> {
             }
 
             log::info!("Stopping node");
-            services_metrics_backend.cancel().await;
-            script_storage.cancel().await;
-            spell_event_bus.cancel().await;
-            sorcerer.cancel().await;
-            dispatcher.cancel().await;
-            connectivity.cancel().await;
-            pool.cancel().await;
+            let subsystems = vec![
+                (
+                    "services metrics",
+                    services_metrics_backend.cancel().map(drop).boxed(),
+                ),
+                ("script storage", script_storage.cancel().map(drop).boxed()),
+                (
+                    "spell event bus",
+                    spell_event_bus.cancel().map(drop).boxed(),
+                ),
+                ("sorcerer", sorcerer.cancel().map(drop).boxed()),
+                ("dispatcher", dispatcher.cancel().boxed()),
+                ("connectivity", connectivity.cancel().boxed()),
+                ("aquavm pool", pool.cancel().map(drop).boxed()),
+            ];
+            let stuck = cancel_within(shutdown_grace_period, subsystems).await;
+            if !stuck.is_empty() {
+                log::warn!(
+                    "Subsystems didn't stop within {}, abandoning them: {}",
+                    pretty(shutdown_grace_period),
+                    stuck.join(", ")
+                );
+            }
+            log::info!("Node stopped");
         });
 
         let mut builtins_deployer = self.builtins_deployer;
         builtins_deployer
             .deploy_builtin_services()
             .wrap_err("builtins deploy failed")?;
+        readiness.set_builtins_deployed();
 
-        Ok(exit_outlet)
+        Ok((exit_outlet, node_task))
     }
 
     /// Starts node service listener.
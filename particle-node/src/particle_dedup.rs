@@ -0,0 +1,147 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use particle_protocol::Particle;
+use server_config::ParticleDedupConfig;
+
+/// Particle id and hash of its data. The same particle legitimately comes back to a peer
+/// with new data as it moves through the network, only identical deliveries are duplicates
+type DedupKey = (String, u64);
+
+/// Remembers up to `capacity` particles received within `window`
+#[derive(Debug)]
+pub struct ParticleDedup {
+    capacity: usize,
+    window: Duration,
+    seen: HashMap<DedupKey, Instant>,
+    /// Keys in the order they were received, oldest first
+    order: VecDeque<(DedupKey, Instant)>,
+}
+
+impl ParticleDedup {
+    pub fn new(config: ParticleDedupConfig) -> Self {
+        Self {
+            capacity: config.capacity,
+            window: config.window,
+            seen: <_>::default(),
+            order: <_>::default(),
+        }
+    }
+
+    /// Returns true if the same particle was already received within the window
+    pub fn is_duplicate(&mut self, particle: &Particle) -> bool {
+        self.is_duplicate_at(particle, Instant::now())
+    }
+
+    fn is_duplicate_at(&mut self, particle: &Particle, now: Instant) -> bool {
+        self.remove_expired(now);
+
+        let key = dedup_key(particle);
+        if self.seen.contains_key(&key) {
+            return true;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some((oldest, _)) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.seen.insert(key.clone(), now);
+        self.order.push_back((key, now));
+
+        false
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        while let Some((key, received)) = self.order.front() {
+            if now.saturating_duration_since(*received) < self.window {
+                break;
+            }
+            self.seen.remove(key);
+            self.order.pop_front();
+        }
+    }
+}
+
+fn dedup_key(particle: &Particle) -> DedupKey {
+    let mut hasher = DefaultHasher::new();
+    particle.data.hash(&mut hasher);
+    (particle.id.clone(), hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use particle_protocol::Particle;
+    use server_config::ParticleDedupConfig;
+
+    use crate::particle_dedup::ParticleDedup;
+
+    fn particle(id: &str, data: &[u8]) -> Particle {
+        Particle {
+            id: id.to_string(),
+            data: data.to_vec(),
+            ..<_>::default()
+        }
+    }
+
+    fn dedup(capacity: usize, window: Duration) -> ParticleDedup {
+        ParticleDedup::new(ParticleDedupConfig { capacity, window })
+    }
+
+    #[test]
+    fn same_particle_is_duplicate() {
+        let mut dedup = dedup(10, Duration::from_secs(60));
+
+        assert!(!dedup.is_duplicate(&particle("a", b"data")));
+        assert!(dedup.is_duplicate(&particle("a", b"data")));
+        // the same particle with new data is another hop, not a duplicate
+        assert!(!dedup.is_duplicate(&particle("a", b"more data")));
+        assert!(!dedup.is_duplicate(&particle("b", b"data")));
+    }
+
+    #[test]
+    fn particles_are_forgotten_after_window() {
+        let mut dedup = dedup(10, Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(!dedup.is_duplicate_at(&particle("a", b""), now));
+        let later = now + Duration::from_secs(30);
+        assert!(dedup.is_duplicate_at(&particle("a", b""), later));
+        // id reused after a long gap belongs to a distinct particle
+        let much_later = now + Duration::from_secs(60);
+        assert!(!dedup.is_duplicate_at(&particle("a", b""), much_later));
+    }
+
+    #[test]
+    fn capacity_is_bounded() {
+        let mut dedup = dedup(2, Duration::from_secs(60));
+
+        assert!(!dedup.is_duplicate(&particle("a", b"")));
+        assert!(!dedup.is_duplicate(&particle("b", b"")));
+        assert!(!dedup.is_duplicate(&particle("c", b"")));
+        assert_eq!(dedup.seen.len(), 2);
+        // the oldest particle was evicted
+        assert!(!dedup.is_duplicate(&particle("a", b"")));
+        assert!(dedup.is_duplicate(&particle("c", b"")));
+    }
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashSet;
+
+use fluence_libp2p::PeerId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PeerRejected {
+    #[error("peer is in the denylist")]
+    Denied,
+    #[error("peer is not in the allowlist")]
+    NotAllowed,
+}
+
+/// Decides whether particles from an init peer id are accepted
+#[derive(Debug, Clone, Default)]
+pub struct PeerFilter {
+    /// If not empty, only these peers are accepted
+    allowed: HashSet<PeerId>,
+    /// These peers are never accepted, even if they're in `allowed`
+    denied: HashSet<PeerId>,
+}
+
+impl PeerFilter {
+    /// `always_allowed` peers bypass the allowlist, but not the denylist
+    pub fn new(
+        allowed: HashSet<PeerId>,
+        denied: HashSet<PeerId>,
+        always_allowed: impl IntoIterator<Item = PeerId>,
+    ) -> Self {
+        let allowed = if allowed.is_empty() {
+            // empty allowlist means "allow all"
+            allowed
+        } else {
+            allowed.into_iter().chain(always_allowed).collect()
+        };
+
+        Self { allowed, denied }
+    }
+
+    pub fn check(&self, peer_id: &PeerId) -> Result<(), PeerRejected> {
+        if self.denied.contains(peer_id) {
+            return Err(PeerRejected::Denied);
+        }
+
+        if !self.allowed.is_empty() && !self.allowed.contains(peer_id) {
+            return Err(PeerRejected::NotAllowed);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use fluence_libp2p::RandomPeerId;
+
+    use crate::peer_filter::{PeerFilter, PeerRejected};
+
+    #[test]
+    fn empty_lists_allow_all() {
+        let filter = PeerFilter::new(<_>::default(), <_>::default(), None);
+        assert_eq!(filter.check(&RandomPeerId::random()), Ok(()));
+    }
+
+    #[test]
+    fn allowlist() {
+        let allowed = RandomPeerId::random();
+        let always_allowed = RandomPeerId::random();
+        let filter = PeerFilter::new(
+            HashSet::from([allowed]),
+            <_>::default(),
+            Some(always_allowed),
+        );
+
+        assert_eq!(filter.check(&allowed), Ok(()));
+        assert_eq!(filter.check(&always_allowed), Ok(()));
+        assert_eq!(
+            filter.check(&RandomPeerId::random()),
+            Err(PeerRejected::NotAllowed)
+        );
+    }
+
+    #[test]
+    fn denylist() {
+        let denied = RandomPeerId::random();
+        let filter = PeerFilter::new(<_>::default(), HashSet::from([denied]), None);
+
+        assert_eq!(filter.check(&denied), Err(PeerRejected::Denied));
+        assert_eq!(filter.check(&RandomPeerId::random()), Ok(()));
+    }
+
+    #[test]
+    fn denylist_takes_precedence() {
+        let peer = RandomPeerId::random();
+        let always_allowed = RandomPeerId::random();
+        let filter = PeerFilter::new(
+            HashSet::from([peer]),
+            HashSet::from([peer, always_allowed]),
+            Some(always_allowed),
+        );
+
+        assert_eq!(filter.check(&peer), Err(PeerRejected::Denied));
+        assert_eq!(filter.check(&always_allowed), Err(PeerRejected::Denied));
+    }
+}
@@ -0,0 +1,234 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use fluence_libp2p::PeerId;
+use server_config::ParticleRateLimit;
+
+/// Buckets are pruned once there are more of them than that
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// Negative if tokens were taken in advance by delayed particles
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter keyed by particle's init peer id
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Tokens added to a bucket per second
+    rate: f64,
+    /// Maximum number of tokens in a bucket
+    burst: f64,
+    /// This peer is never limited
+    exempt: PeerId,
+    buckets: HashMap<PeerId, Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: ParticleRateLimit, exempt: PeerId) -> Self {
+        let rate = config.particles_per_sec.max(0.0);
+        let burst = config
+            .burst
+            .map_or(rate, |b| b as f64)
+            // always allow at least a single particle
+            .max(1.0);
+
+        Self {
+            rate,
+            burst,
+            exempt,
+            buckets: <_>::default(),
+        }
+    }
+
+    /// Takes a token from `peer_id`'s bucket, in advance if the bucket is empty.
+    /// Returns how long to wait until the token is available, or None if that's
+    /// longer than `max_delay`, in which case no token is taken
+    pub fn acquire(&mut self, peer_id: PeerId, max_delay: Duration) -> Option<Duration> {
+        self.acquire_at(peer_id, max_delay, Instant::now())
+    }
+
+    fn acquire_at(
+        &mut self,
+        peer_id: PeerId,
+        max_delay: Duration,
+        now: Instant,
+    ) -> Option<Duration> {
+        if peer_id == self.exempt {
+            return Some(Duration::ZERO);
+        }
+
+        if self.buckets.len() > PRUNE_THRESHOLD {
+            self.prune(now);
+        }
+
+        let bucket = self.buckets.entry(peer_id).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+
+        let delay = if bucket.tokens >= 1.0 {
+            Duration::ZERO
+        } else if self.rate > 0.0 {
+            Duration::try_from_secs_f64((1.0 - bucket.tokens) / self.rate).ok()?
+        } else {
+            return None;
+        };
+
+        if delay > max_delay {
+            return None;
+        }
+        bucket.tokens -= 1.0;
+        Some(delay)
+    }
+
+    /// Removes buckets that are full by now, they're indistinguishable from absent ones
+    fn prune(&mut self, now: Instant) {
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * rate < burst
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use fluence_libp2p::{PeerId, RandomPeerId};
+    use server_config::ParticleRateLimit;
+
+    use crate::rate_limiter::RateLimiter;
+
+    /// Acquires a token only if it's available right away
+    fn try_acquire_at(limiter: &mut RateLimiter, peer: PeerId, now: Instant) -> bool {
+        limiter.acquire_at(peer, Duration::ZERO, now).is_some()
+    }
+
+    fn limiter(particles_per_sec: f64, burst: Option<u32>) -> RateLimiter {
+        let config = ParticleRateLimit {
+            particles_per_sec,
+            burst,
+        };
+        RateLimiter::new(config, RandomPeerId::random())
+    }
+
+    #[test]
+    fn burst_is_throttled() {
+        let mut limiter = limiter(10.0, Some(5));
+        let peer = RandomPeerId::random();
+        let now = Instant::now();
+
+        let accepted = (0..20)
+            .filter(|_| try_acquire_at(&mut limiter, peer, now))
+            .count();
+        assert_eq!(accepted, 5);
+
+        // other peers have their own buckets
+        assert!(try_acquire_at(&mut limiter, RandomPeerId::random(), now));
+    }
+
+    #[test]
+    fn tokens_are_refilled() {
+        let mut limiter = limiter(10.0, None);
+        let peer = RandomPeerId::random();
+        let now = Instant::now();
+
+        for _ in 0..10 {
+            assert!(try_acquire_at(&mut limiter, peer, now));
+        }
+        assert!(!try_acquire_at(&mut limiter, peer, now));
+
+        // 10 particles per second = 1 particle per 100ms
+        let later = now + Duration::from_millis(100);
+        assert!(try_acquire_at(&mut limiter, peer, later));
+        assert!(!try_acquire_at(&mut limiter, peer, later));
+
+        // bucket never holds more than burst
+        let much_later = now + Duration::from_secs(60);
+        let accepted = (0..20)
+            .filter(|_| try_acquire_at(&mut limiter, peer, much_later))
+            .count();
+        assert_eq!(accepted, 10);
+    }
+
+    #[test]
+    fn exempt_peer_is_not_limited() {
+        let exempt = RandomPeerId::random();
+        let config = ParticleRateLimit {
+            particles_per_sec: 1.0,
+            burst: Some(1),
+        };
+        let mut limiter = RateLimiter::new(config, exempt);
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert!(try_acquire_at(&mut limiter, exempt, now));
+        }
+    }
+
+    #[test]
+    fn particles_are_delayed() {
+        let mut limiter = limiter(4.0, Some(1));
+        let peer = RandomPeerId::random();
+        let now = Instant::now();
+        let max_delay = Duration::from_secs(1);
+
+        assert_eq!(
+            limiter.acquire_at(peer, max_delay, now),
+            Some(Duration::ZERO)
+        );
+        // tokens are taken in advance, so each next particle waits longer
+        assert_eq!(
+            limiter.acquire_at(peer, max_delay, now),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            limiter.acquire_at(peer, max_delay, now),
+            Some(Duration::from_millis(500))
+        );
+
+        // too long to wait, no token is taken
+        assert_eq!(
+            limiter.acquire_at(peer, Duration::from_millis(600), now),
+            None
+        );
+        assert_eq!(
+            limiter.acquire_at(peer, max_delay, now),
+            Some(Duration::from_millis(750))
+        );
+    }
+
+    #[test]
+    fn zero_rate_never_refills() {
+        let mut limiter = limiter(0.0, None);
+        let peer = RandomPeerId::random();
+        let now = Instant::now();
+
+        assert!(try_acquire_at(&mut limiter, peer, now));
+        assert_eq!(limiter.acquire_at(peer, Duration::MAX, now), None);
+    }
+}
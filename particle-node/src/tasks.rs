@@ -17,9 +17,11 @@
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
+use async_std::future::timeout;
 use async_std::task::JoinHandle;
-use futures::future::FusedFuture;
+use futures::future::{BoxFuture, FusedFuture};
 use futures::FutureExt;
 
 /// Holds handles to spawned tasks
@@ -62,3 +64,50 @@ impl FusedFuture for Tasks {
         self.tasks.is_empty()
     }
 }
+
+/// Awaits cancellation of `subsystems` in order, giving all of them `grace_period` in total.
+/// Subsystems that didn't stop in time are abandoned, their names are returned.
+pub async fn cancel_within(
+    grace_period: Duration,
+    subsystems: Vec<(&'static str, BoxFuture<'static, ()>)>,
+) -> Vec<&'static str> {
+    let deadline = Instant::now() + grace_period;
+    let mut stuck = vec![];
+    for (name, cancel) in subsystems {
+        // once the deadline has passed, the rest are still polled once and may stop immediately
+        let left = deadline.saturating_duration_since(Instant::now());
+        if timeout(left, cancel).await.is_err() {
+            stuck.push(name);
+        }
+    }
+
+    stuck
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use async_std::task;
+    use futures::FutureExt;
+
+    use crate::tasks::cancel_within;
+
+    #[test]
+    fn slow_subsystem_is_abandoned() {
+        let subsystems = vec![
+            ("fast", async {}.boxed()),
+            ("slow", task::sleep(Duration::from_secs(60)).boxed()),
+            ("quick", task::sleep(Duration::from_millis(10)).boxed()),
+            ("after_slow", async {}.boxed()),
+        ];
+
+        let start = Instant::now();
+        let stuck = task::block_on(cancel_within(Duration::from_millis(300), subsystems));
+
+        assert_eq!(stuck, vec!["slow"]);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_secs(5), "took {elapsed:?}");
+    }
+}
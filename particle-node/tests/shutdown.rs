@@ -0,0 +1,126 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine};
+use fluence_keypair::KeyPair;
+use fs_utils::make_tmp_dir;
+
+fn free_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind to a free port");
+    listener.local_addr().unwrap().port()
+}
+
+fn is_ready(metrics_port: u16) -> bool {
+    let Ok(mut stream) = TcpStream::connect(("127.0.0.1", metrics_port)) else {
+        return false;
+    };
+    let request = "GET /ready HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n";
+    let mut response = String::new();
+    stream.write_all(request.as_bytes()).is_ok()
+        && stream.read_to_string(&mut response).is_ok()
+        && response.starts_with("HTTP/1.1 200")
+}
+
+fn wait_for_exit(child: &mut Child, timeout: Duration) -> Option<ExitStatus> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Some(status) = child.try_wait().expect("wait for node process") {
+            return Some(status);
+        }
+        sleep(Duration::from_millis(100));
+    }
+
+    None
+}
+
+#[test]
+fn sigterm_stops_node() {
+    let base_dir = make_tmp_dir();
+    let key = |kp: KeyPair| base64.encode(kp.to_vec());
+    let config = format!(
+        r#"
+        base_dir = "{}"
+
+        [root_key_pair]
+        format = "ed25519"
+        value = "{}"
+
+        [builtins_key_pair]
+        format = "ed25519"
+        value = "{}"
+        "#,
+        base_dir.display(),
+        key(KeyPair::generate_ed25519()),
+        key(KeyPair::generate_ed25519()),
+    );
+    let config_path = base_dir.join("Config.toml");
+    std::fs::write(&config_path, config).unwrap();
+
+    let metrics_port = free_port();
+    let mut node = Command::new(env!("CARGO_BIN_EXE_particle-node"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--tcp-port", &free_port().to_string()])
+        .args(["--ws-port", &free_port().to_string()])
+        .args(["--metrics-port", &metrics_port.to_string()])
+        .args(["--listen-ip", "127.0.0.1"])
+        .arg("--local")
+        .env("RUST_LOG", "info")
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("start node");
+    // drain stdout, so that the node never blocks on logging
+    let mut stdout = node.stdout.take().unwrap();
+    let logs = std::thread::spawn(move || {
+        let mut logs = String::new();
+        stdout.read_to_string(&mut logs).ok();
+        logs
+    });
+
+    let start = Instant::now();
+    while !is_ready(metrics_port) {
+        if start.elapsed() > Duration::from_secs(120) {
+            node.kill().ok();
+            panic!("node didn't become ready");
+        }
+        sleep(Duration::from_millis(200));
+    }
+
+    let killed = Command::new("kill")
+        .args(["-TERM", &node.id().to_string()])
+        .status()
+        .expect("send SIGTERM");
+    assert!(killed.success());
+
+    let status = wait_for_exit(&mut node, Duration::from_secs(60));
+    if status.is_none() {
+        node.kill().ok();
+    }
+    let logs = logs.join().unwrap();
+    let status = status.unwrap_or_else(|| panic!("node didn't stop on SIGTERM, logs: {logs}"));
+
+    assert!(status.success(), "node exited with {status}, logs: {logs}");
+    assert!(
+        logs.contains("Node stopped"),
+        "node wasn't drained, logs: {logs}"
+    );
+}
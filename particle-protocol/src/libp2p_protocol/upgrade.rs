@@ -44,6 +44,13 @@ pub struct ProtocolConfig {
     /// Timeout for outbound substream upgrades.
     #[serde(with = "humantime_serde")]
     pub outbound_substream_timeout: Duration,
+    /// Maximum size of an inbound message in bytes. Larger messages are rejected before allocation.
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
+}
+
+fn default_max_message_size() -> usize {
+    MAX_BUF_SIZE
 }
 
 impl Default for ProtocolConfig {
@@ -52,6 +59,7 @@ impl Default for ProtocolConfig {
             upgrade_timeout: Duration::from_secs(10),
             keep_alive_timeout: Duration::from_secs(10),
             outbound_substream_timeout: Duration::from_secs(10),
+            max_message_size: MAX_BUF_SIZE,
         }
     }
 }
@@ -66,8 +74,14 @@ impl ProtocolConfig {
             upgrade_timeout,
             keep_alive_timeout,
             outbound_substream_timeout,
+            max_message_size: MAX_BUF_SIZE,
         }
     }
+
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
 }
 
 impl<OutProto: libp2p::swarm::handler::OutboundUpgradeSend, OutEvent> From<ProtocolConfig>
@@ -116,8 +130,9 @@ where
     type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
 
     fn upgrade_inbound(self, mut socket: Socket, _: Self::Info) -> Self::Future {
+        let max_size = self.max_message_size;
         async move {
-            let decoded = upgrade::read_length_prefixed(&mut socket, MAX_BUF_SIZE).await?;
+            let decoded = upgrade::read_length_prefixed(&mut socket, max_size).await?;
             let msg: ProtocolMessage = serde_json::from_slice(&decoded)
                 .wrap_err_with(|| format!("unable to deserialize: '{decoded:?}'"))?;
 
@@ -265,6 +280,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn oversized_message_rejected() {
+        let mem_addr = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let mut transport = MemoryTransport::new().boxed();
+        transport.listen_on(mem_addr).unwrap();
+
+        let listener_addr = match transport.select_next_some().now_or_never() {
+            Some(TransportEvent::NewAddress { listen_addr, .. }) => listen_addr,
+            p => panic!("MemoryTransport not listening on an address!: {:?}", p),
+        };
+
+        let inbound = async_std::task::spawn(async move {
+            let (listener_upgrade, _) = transport.select_next_some().await.into_incoming().unwrap();
+            let conn = listener_upgrade.await.unwrap();
+            // BYTES is larger than 100 bytes, so it must be rejected
+            let config = ProtocolConfig::default().with_max_message_size(100);
+            upgrade::apply_inbound(conn, config).await
+        });
+
+        async_std::task::block_on(async move {
+            let msg: ProtocolMessage = serde_json::from_slice(&BYTES).unwrap();
+            let particle = match msg {
+                ProtocolMessage::Particle(p) => p,
+                _ => unreachable!("must be particle"),
+            };
+            let msg = HandlerMessage::OutParticle(particle, <_>::default());
+            let mut transport = MemoryTransport::new();
+            let c = transport.dial(listener_addr).unwrap().await.unwrap();
+            // outbound side may or may not notice that the message was dropped
+            upgrade::apply_outbound(c, msg, upgrade::Version::V1)
+                .await
+                .ok();
+        });
+
+        let received = futures::executor::block_on(inbound);
+        assert!(received.is_err(), "oversized message must be rejected");
+    }
+
     #[test]
     fn deserialize() {
         let str = r#"{"action":"Particle","id":"2","init_peer_id":"12D3KooWAcn1f5iZ7wbo9QrYPFgq6o7DGkh7VwC8Zucn6DgWZQDo","timestamp":1617733422130,"ttl":65525,"script":"!","signature":[],"data":"MTJEM0tvb1dDM3dhcjhqcTJzaGFVQ2hSZWttYjNNN0RGRGl4ZkdVTm5ydGY0VlRGQVlVdywxMkQzS29vV0o2bVZLYXpKQzdyd2dtd0JpZm5LZ0JoR2NSTWtaOXdRTjY4dmJ1UGdIUjlO"}"#;
@@ -79,6 +79,15 @@ impl Particle {
         self.timestamp.checked_add(self.ttl as u64)
     }
 
+    /// Span carrying particle id and init peer id, so that all events of a particle can be grouped
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "particle",
+            particle_id = %self.id,
+            init_peer_id = %self.init_peer_id
+        )
+    }
+
     pub fn time_to_live(&self) -> Duration {
         if let Some(ttl) = self.deadline().and_then(|d| d.checked_sub(now_ms() as u64)) {
             Duration::from_millis(ttl)
@@ -22,7 +22,7 @@ use fluence_app_service::{
     AppService, AppServiceConfig, MarineConfig, MarineWASIConfig, ModuleDescriptor,
 };
 use fluence_libp2p::PeerId;
-use particle_modules::ModuleRepository;
+use particle_modules::{ModuleError, ModuleRepository};
 use peer_metrics::ServicesMetrics;
 use server_config::ServicesConfig;
 
@@ -38,10 +38,7 @@ pub fn create_app_service(
     metrics: Option<&ServicesMetrics>,
 ) -> Result<AppService> {
     try {
-        let mut modules_config = modules.resolve_blueprint(&blueprint_id)?;
-        modules_config
-            .iter_mut()
-            .for_each(|module| inject_vault(&config.particles_vault_dir, module));
+        let modules_config = resolve_modules_config(&config, modules, &blueprint_id)?;
 
         if let Some(metrics) = metrics.as_ref() {
             metrics.observe_service_config(config.max_heap_size.as_u64(), &modules_config);
@@ -69,6 +66,24 @@ pub fn create_app_service(
     }
 }
 
+/// Resolve blueprint to module descriptors, checking that all of its modules are present on disk
+pub fn resolve_modules_config(
+    config: &ServicesConfig,
+    modules: &ModuleRepository,
+    blueprint_id: &str,
+) -> Result<Vec<ModuleDescriptor>> {
+    let mut modules_config = modules.resolve_blueprint(blueprint_id)?;
+    for module in modules_config.iter_mut() {
+        let path = config.modules_dir.join(&module.file_name);
+        if let Err(err) = std::fs::metadata(&path) {
+            return Err(ModuleError::ModuleNotFound { path, err }.into());
+        }
+        inject_vault(&config.particles_vault_dir, module);
+    }
+
+    Ok(modules_config)
+}
+
 /// Map `vault_dir` to `/tmp/vault` inside the service.
 /// Particle File Vaults will be available as `/tmp/vault/$particle_id`
 fn inject_vault(vault_dir: &Path, module: &mut ModuleDescriptor) {
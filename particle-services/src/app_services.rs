@@ -15,7 +15,10 @@
  */
 use std::ops::Deref;
 use std::time::{Duration, Instant};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 
 use derivative::Derivative;
 use fluence_app_service::{
@@ -37,15 +40,20 @@ use peer_metrics::{
 use server_config::ServicesConfig;
 use uuid_utils::uuid;
 
-use crate::app_service::create_app_service;
+use crate::app_service::{create_app_service, resolve_modules_config};
 use crate::error::ServiceError;
 use crate::error::ServiceError::{AliasAsServiceId, Forbidden, NoSuchAlias};
 use crate::persistence::{
     load_persisted_services, persist_service, remove_persisted_service, PersistedService,
 };
 
+/// How many recent call errors are kept for each service
+const MAX_SERVICE_CALL_ERRORS: usize = 10;
+
 type Services = Arc<RwLock<HashMap<String, Service>>>;
 type Aliases = Arc<RwLock<HashMap<String, String>>>;
+/// Number of services being created by each owner
+type CreatingServices = Arc<Mutex<HashMap<PeerId, usize>>>;
 
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -55,6 +63,8 @@ pub struct Service {
     pub blueprint_id: String,
     pub owner_id: PeerId,
     pub aliases: Vec<String>,
+    /// Most recent call errors, oldest first
+    pub call_errors: Mutex<VecDeque<ServiceCallError>>,
 }
 
 impl Service {
@@ -65,6 +75,22 @@ impl Service {
     pub fn add_alias(&mut self, alias: String) {
         self.aliases.push(alias);
     }
+
+    fn record_call_error(&self, error: ServiceCallError) {
+        let mut errors = self.call_errors.lock();
+        if errors.len() >= MAX_SERVICE_CALL_ERRORS {
+            errors.pop_front();
+        }
+        errors.push_back(error);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceCallError {
+    pub function_name: String,
+    pub message: String,
+    /// Unix timestamp of the failure, in milliseconds
+    pub timestamp: u64,
 }
 
 impl Deref for Service {
@@ -98,11 +124,30 @@ pub struct ParticleAppServices {
     services: Services,
     modules: ModuleRepository,
     aliases: Aliases,
+    creating_services: CreatingServices,
     management_peer_id: PeerId,
     builtins_management_peer_id: PeerId,
     pub metrics: Option<ServicesMetrics>,
 }
 
+/// Place reserved for a service under creation, released on drop
+struct ServiceSlot {
+    creating_services: CreatingServices,
+    owner_id: PeerId,
+}
+
+impl Drop for ServiceSlot {
+    fn drop(&mut self) {
+        let mut creating = self.creating_services.lock();
+        if let Some(count) = creating.get_mut(&self.owner_id) {
+            *count -= 1;
+            if *count == 0 {
+                creating.remove(&self.owner_id);
+            }
+        }
+    }
+}
+
 pub fn get_service<'l>(
     services: &'l HashMap<String, Service>,
     aliases: &HashMap<String, String>,
@@ -138,6 +183,7 @@ impl ParticleAppServices {
             services: <_>::default(),
             modules,
             aliases: <_>::default(),
+            creating_services: <_>::default(),
             management_peer_id,
             builtins_management_peer_id,
             metrics,
@@ -153,11 +199,54 @@ impl ParticleAppServices {
         blueprint_id: String,
         init_peer_id: PeerId,
     ) -> Result<String, ServiceError> {
+        // kept until the service is created, so concurrent creations can't exceed the limit
+        let _slot = self.reserve_service_slot(init_peer_id)?;
         let service_id = uuid::Uuid::new_v4().to_string();
         self.create_service_inner(blueprint_id, init_peer_id, service_id.clone(), vec![])?;
         Ok(service_id)
     }
 
+    /// Counts services owned by `owner_id`, including the ones being created,
+    /// and reserves a place for one more if the limit allows it
+    fn reserve_service_slot(&self, owner_id: PeerId) -> Result<Option<ServiceSlot>, ServiceError> {
+        let limit = match self.config.max_services_per_owner {
+            Some(limit) => limit,
+            None => return Ok(None),
+        };
+        if owner_id == self.management_peer_id || owner_id == self.builtins_management_peer_id {
+            return Ok(None);
+        }
+
+        // services are counted under this lock, so no other creation can be reserved meanwhile
+        let mut creating = self.creating_services.lock();
+        let owned = self
+            .services
+            .read()
+            .values()
+            .filter(|service| service.owner_id == owner_id)
+            .count();
+        let pending = creating.entry(owner_id).or_default();
+        if owned + *pending >= limit {
+            return Err(ServiceError::ServicesLimitExceeded {
+                owner: owner_id,
+                limit,
+            });
+        }
+        *pending += 1;
+
+        Ok(Some(ServiceSlot {
+            creating_services: self.creating_services.clone(),
+            owner_id,
+        }))
+    }
+
+    /// Runs the same checks as `create_service` without creating anything:
+    /// blueprint is resolved and all of its modules are checked to be present
+    pub fn create_service_dry_run(&self, blueprint_id: &str) -> Result<(), ServiceError> {
+        resolve_modules_config(&self.config, &self.modules, blueprint_id)?;
+        Ok(())
+    }
+
     pub fn remove_service(
         &self,
         service_id_or_alias: String,
@@ -233,6 +322,70 @@ impl ParticleAppServices {
         Ok(())
     }
 
+    /// Recreates module instances of the service from its blueprint,
+    /// keeping its id, owner and aliases.
+    ///
+    /// Services are write-locked until the new instance is in place, so the service
+    /// can't be removed or get new aliases while it's being restarted
+    pub fn restart_service(
+        &self,
+        service_id_or_alias: String,
+        init_peer_id: PeerId,
+    ) -> Result<(), ServiceError> {
+        if init_peer_id != self.management_peer_id
+            && init_peer_id != self.builtins_management_peer_id
+        {
+            return Err(Forbidden {
+                user: init_peer_id,
+                function: "restart_service",
+                reason: "only management peer id can restart services",
+            });
+        };
+
+        let restart_start_time = Instant::now();
+        let mut services = self.services.write();
+        let (_, service_id) = get_service(&services, &self.aliases.read(), service_id_or_alias)
+            .map_err(ServiceError::NoSuchService)?;
+        let service = services
+            .get_mut(&service_id)
+            .ok_or_else(|| ServiceError::NoSuchService(service_id.clone()))?;
+
+        let app_service = create_app_service(
+            self.config.clone(),
+            &self.modules,
+            service.blueprint_id.clone(),
+            service_id.clone(),
+            service.aliases.clone(),
+            service.owner_id,
+            self.metrics.as_ref(),
+        )?;
+        let stats = app_service.module_memory_stats();
+        let stats = ServiceMemoryStat::new(&stats);
+        let service_type = ServiceType::Service(service.aliases.first().cloned());
+
+        // previous instance is replaced and dropped
+        service.service = Mutex::new(app_service);
+        drop(services);
+
+        let restart_end_time = restart_start_time.elapsed().as_secs();
+        if let Some(m) = self.metrics.as_ref() {
+            m.observe_restarted(
+                service_id.clone(),
+                service_type,
+                stats,
+                restart_end_time as f64,
+            );
+        }
+
+        log::info!(
+            "Service {} restarted in {}",
+            service_id,
+            pretty(restart_start_time.elapsed())
+        );
+
+        Ok(())
+    }
+
     pub fn call_service(
         &self,
         mut function_args: Args,
@@ -285,11 +438,11 @@ impl ParticleAppServices {
         };
         let function_name = function_args.function_name;
 
-        let mut service = service.lock();
-        let old_memory = service.module_memory_stats();
+        let mut app_service = service.lock();
+        let old_memory = app_service.module_memory_stats();
         let old_mem_usage = ServicesMetricsBuiltin::get_used_memory(&old_memory);
         // TODO: set execution timeout https://github.com/fluencelabs/fluence/issues/1212
-        let result = service
+        let result = app_service
             .call(
                 function_name.clone(),
                 JValue::Array(function_args.function_args),
@@ -312,11 +465,16 @@ impl ParticleAppServices {
                         stats,
                     );
                 }
+                service.record_call_error(ServiceCallError {
+                    function_name: function_name.clone(),
+                    message: e.to_string(),
+                    timestamp: now_ms() as u64,
+                });
                 ServiceError::Engine(e)
             })?;
 
         let call_time_sec = call_time_start.elapsed().as_secs_f64();
-        let new_memory = service.module_memory_stats();
+        let new_memory = app_service.module_memory_stats();
         let new_memory_usage = ServicesMetricsBuiltin::get_used_memory(&new_memory);
 
         let memory_delta_bytes = new_memory_usage - old_mem_usage;
@@ -362,6 +520,7 @@ impl ParticleAppServices {
             ttl: particle_ttl.as_millis() as u32,
             script: "".to_string(),
             signature: vec![],
+            call_depth: 0,
         };
 
         self.call_service(args, particle)
@@ -424,6 +583,86 @@ impl ParticleAppServices {
         Ok(())
     }
 
+    pub fn list_aliases(
+        &self,
+        service_id: String,
+        init_peer_id: PeerId,
+    ) -> Result<Vec<String>, ServiceError> {
+        if init_peer_id != self.management_peer_id
+            && init_peer_id != self.builtins_management_peer_id
+        {
+            return Err(Forbidden {
+                user: init_peer_id,
+                function: "list_aliases",
+                reason: "only management peer id can list aliases",
+            });
+        };
+
+        let services = self.services.read();
+        let service = services
+            .get(&service_id)
+            .ok_or(ServiceError::NoSuchService(service_id))?;
+
+        Ok(service.aliases.clone())
+    }
+
+    /// Moves alias `old` to `new`, keeping it pointed to the same service
+    pub fn rename_alias(
+        &self,
+        old: String,
+        new: String,
+        init_peer_id: PeerId,
+    ) -> Result<(), ServiceError> {
+        if init_peer_id != self.management_peer_id
+            && init_peer_id != self.builtins_management_peer_id
+        {
+            return Err(Forbidden {
+                user: init_peer_id,
+                function: "rename_alias",
+                reason: "only management peer id can rename aliases",
+            });
+        };
+
+        let mut services = self.services.write();
+        if services.contains_key(&new) {
+            return Err(AliasAsServiceId(new));
+        }
+
+        let mut aliases = self.aliases.write();
+        let service_id = aliases.get(&old).cloned().ok_or(NoSuchAlias(old.clone()))?;
+        if old == new {
+            return Ok(());
+        }
+
+        let mut updated = vec![];
+        // If `new` alias already belongs to another service, take it away
+        let previous_owner: Option<_> = try {
+            let previous_owner_id = aliases.get(&new)?.clone();
+            let previous_owner_service = services.get_mut(&previous_owner_id)?;
+            previous_owner_service.remove_alias(&new);
+            PersistedService::from_service(previous_owner_id, previous_owner_service)
+        };
+        updated.extend(previous_owner);
+
+        let service = services
+            .get_mut(&service_id)
+            .ok_or_else(|| ServiceError::NoSuchService(service_id.clone()))?;
+        service.remove_alias(&old);
+        service.add_alias(new.clone());
+        updated.push(PersistedService::from_service(service_id.clone(), service));
+
+        aliases.remove(&old);
+        aliases.insert(new, service_id);
+
+        drop(aliases);
+        drop(services);
+        for persisted in updated {
+            persist_service(&self.config.services_dir, persisted)?;
+        }
+
+        Ok(())
+    }
+
     pub fn resolve_alias(&self, alias: String) -> Result<String, ServiceError> {
         let aliases = self.aliases.read();
         let service_id = aliases.get(&alias);
@@ -479,6 +718,19 @@ impl ParticleAppServices {
         services
     }
 
+    /// Returns the most recent call error of the service, if any
+    pub fn last_error(
+        &self,
+        service_id_or_alias: String,
+    ) -> Result<Option<ServiceCallError>, ServiceError> {
+        let services = self.services.read();
+        let (service, _) = get_service(&services, &self.aliases.read(), service_id_or_alias)
+            .map_err(ServiceError::NoSuchService)?;
+
+        let last_error = service.call_errors.lock().back().cloned();
+        Ok(last_error)
+    }
+
     // TODO: move JSON serialization to builtins
     pub fn get_service_mem_stats(&self, service_id: String) -> Result<Vec<JValue>, JError> {
         let services = self.services.read();
@@ -586,6 +838,7 @@ impl ParticleAppServices {
             blueprint_id,
             owner_id,
             aliases,
+            call_errors: <_>::default(),
         };
 
         let replaced = self.services.write().insert(service_id.clone(), service);
@@ -641,6 +894,15 @@ mod tests {
         local_pid: PeerId,
         management_pid: PeerId,
         base_dir: PathBuf,
+    ) -> ParticleAppServices {
+        create_pas_with_limit(local_pid, management_pid, base_dir, None)
+    }
+
+    fn create_pas_with_limit(
+        local_pid: PeerId,
+        management_pid: PeerId,
+        base_dir: PathBuf,
+        max_services_per_owner: Option<usize>,
     ) -> ParticleAppServices {
         let startup_kp = Keypair::generate_ed25519();
         let vault_dir = base_dir.join("..").join("vault");
@@ -654,6 +916,10 @@ mod tests {
             to_peer_id(&startup_kp),
             max_heap_size,
             None,
+            max_services_per_owner,
+            server_config::default_max_call_depth(),
+            server_config::default_max_script_size(),
+            <_>::default(),
         )
         .unwrap();
 
@@ -881,6 +1147,89 @@ mod tests {
         assert_eq!(persisted_service_2.aliases, vec![alias.to_string()]);
     }
 
+    #[test]
+    fn test_list_aliases() {
+        let base_dir = TempDir::new("test4").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas(local_pid, management_pid, base_dir.into_path());
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let service_id = create_service(&pas, module_name, &hash).unwrap();
+
+        pas.add_alias("alias1".to_string(), service_id.clone(), management_pid)
+            .unwrap();
+        pas.add_alias("alias2".to_string(), service_id.clone(), management_pid)
+            .unwrap();
+
+        let aliases = pas
+            .list_aliases(service_id.clone(), management_pid)
+            .unwrap();
+        assert_eq!(aliases, vec!["alias1".to_string(), "alias2".to_string()]);
+
+        let result = pas.list_aliases(service_id, create_pid());
+        assert!(matches!(result, Err(ServiceError::Forbidden { .. })));
+    }
+
+    #[test]
+    fn test_rename_alias() {
+        let base_dir = TempDir::new("test4").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas(local_pid, management_pid, base_dir.into_path());
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let service_id = create_service(&pas, module_name, &hash).unwrap();
+
+        pas.add_alias("old".to_string(), service_id.clone(), management_pid)
+            .unwrap();
+        pas.rename_alias("old".to_string(), "new".to_string(), management_pid)
+            .unwrap();
+
+        assert_eq!(pas.resolve_alias("new".to_string()).unwrap(), service_id);
+        assert!(matches!(
+            pas.resolve_alias("old".to_string()),
+            Err(ServiceError::NoSuchAlias(..))
+        ));
+        let aliases = pas
+            .list_aliases(service_id.clone(), management_pid)
+            .unwrap();
+        assert_eq!(aliases, vec!["new".to_string()]);
+
+        let persisted_services: Vec<_> = load_persisted_services(&pas.config.services_dir)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let persisted_service = persisted_services
+            .iter()
+            .find(|s| s.service_id == service_id)
+            .unwrap();
+        assert_eq!(persisted_service.aliases, vec!["new".to_string()]);
+    }
+
+    #[test]
+    fn test_rename_alias_no_such_alias() {
+        let base_dir = TempDir::new("test4").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas(local_pid, management_pid, base_dir.into_path());
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let service_id = create_service(&pas, module_name, &hash).unwrap();
+
+        let result = pas.rename_alias("old".to_string(), "new".to_string(), management_pid);
+        assert!(matches!(result, Err(ServiceError::NoSuchAlias(..))));
+        assert!(pas.resolve_alias("new".to_string()).is_err());
+        let aliases = pas.list_aliases(service_id, management_pid).unwrap();
+        assert!(aliases.is_empty());
+
+        let result = pas.rename_alias("old".to_string(), "new".to_string(), create_pid());
+        assert!(matches!(result, Err(ServiceError::Forbidden { .. })));
+    }
+
     #[test]
     fn test_persisted_service() {
         let base_dir = TempDir::new("test4").unwrap();
@@ -906,6 +1255,158 @@ mod tests {
         assert_eq!(service_1.owner_id, persisted_service_1.owner_id);
     }
 
+    #[test]
+    fn test_create_service_dry_run() {
+        let base_dir = TempDir::new("test5").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas(local_pid, management_pid, base_dir.into_path());
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let dep = Dependency::Hash(Hash::from_hex(&hash).unwrap());
+        let bp = pas
+            .modules
+            .add_blueprint(AddBlueprint::new(module_name, vec![dep]))
+            .unwrap();
+
+        pas.create_service_dry_run(&bp).expect("dry run");
+        assert!(pas.list_services().is_empty());
+
+        let missing = Dependency::Hash(Hash::new(&[1, 2, 3]));
+        let bp = pas
+            .modules
+            .add_blueprint(AddBlueprint::new("missing".to_string(), vec![missing]))
+            .unwrap();
+
+        let result = pas.create_service_dry_run(&bp);
+        assert!(matches!(result, Err(ServiceError::ModuleError(..))));
+        assert!(pas.list_services().is_empty());
+        let persisted = load_persisted_services(&pas.config.services_dir);
+        assert!(persisted.is_empty());
+    }
+
+    #[test]
+    fn test_services_limit() {
+        let base_dir = TempDir::new("test6").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas_with_limit(local_pid, management_pid, base_dir.into_path(), Some(2));
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let dep = Dependency::Hash(Hash::from_hex(&hash).unwrap());
+        let bp = pas
+            .modules
+            .add_blueprint(AddBlueprint::new(module_name, vec![dep]))
+            .unwrap();
+
+        let owner = create_pid();
+        pas.create_service(bp.clone(), owner).unwrap();
+        pas.create_service(bp.clone(), owner).unwrap();
+
+        let result = pas.create_service(bp.clone(), owner);
+        assert!(matches!(
+            result,
+            Err(ServiceError::ServicesLimitExceeded { limit: 2, .. })
+        ));
+        assert!(result.unwrap_err().to_string().contains('2'));
+
+        // other peers and management peer are not affected
+        pas.create_service(bp.clone(), create_pid()).unwrap();
+        for _ in 0..3 {
+            pas.create_service(bp.clone(), management_pid).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_services_limit_concurrent() {
+        let base_dir = TempDir::new("test6").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas_with_limit(local_pid, management_pid, base_dir.into_path(), Some(2));
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let dep = Dependency::Hash(Hash::from_hex(&hash).unwrap());
+        let bp = pas
+            .modules
+            .add_blueprint(AddBlueprint::new(module_name, vec![dep]))
+            .unwrap();
+
+        let owner = create_pid();
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let creations: Vec<_> = (0..5)
+                .map(|_| scope.spawn(|| pas.create_service(bp.clone(), owner)))
+                .collect();
+            creations.into_iter().map(|c| c.join().unwrap()).collect()
+        });
+
+        let created = results.iter().filter(|r| r.is_ok()).count();
+        assert_eq!(created, 2);
+        assert!(results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .all(|e| matches!(e, ServiceError::ServicesLimitExceeded { limit: 2, .. })));
+        let owned = pas
+            .services
+            .read()
+            .values()
+            .filter(|s| s.owner_id == owner)
+            .count();
+        assert_eq!(owned, 2);
+
+        // failed creations don't hold their places
+        assert!(pas.creating_services.lock().is_empty());
+    }
+
+    #[test]
+    fn test_aliases_survive_reload() {
+        let base_dir = TempDir::new("test7").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas(local_pid, management_pid, base_dir.path().to_path_buf());
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let service_id = create_service(&pas, module_name, &hash).unwrap();
+        pas.add_alias("alias".to_string(), service_id.clone(), management_pid)
+            .unwrap();
+        drop(pas);
+
+        // services and their aliases are loaded from disk on creation
+        let pas = create_pas(local_pid, management_pid, base_dir.path().to_path_buf());
+        let resolved = pas.resolve_alias("alias".to_string()).unwrap();
+        assert_eq!(resolved, service_id);
+        let aliases = pas.list_aliases(service_id, management_pid).unwrap();
+        assert_eq!(aliases, vec!["alias".to_string()]);
+    }
+
+    #[test]
+    fn test_reload_skips_service_without_modules() {
+        let base_dir = TempDir::new("test8").unwrap();
+        let local_pid = create_pid();
+        let management_pid = create_pid();
+        let pas = create_pas(local_pid, management_pid, base_dir.path().to_path_buf());
+
+        let module_name = "tetra".to_string();
+        let hash = upload_tetra_service(&pas, module_name.clone());
+        let service_id = create_service(&pas, module_name, &hash).unwrap();
+        pas.add_alias("alias".to_string(), service_id, management_pid)
+            .unwrap();
+        let modules_dir = pas.config.modules_dir.clone();
+        drop(pas);
+
+        std::fs::remove_dir_all(&modules_dir).unwrap();
+        std::fs::create_dir_all(&modules_dir).unwrap();
+
+        // the service can't be created anymore, so it's skipped along with its alias
+        let pas = create_pas(local_pid, management_pid, base_dir.path().to_path_buf());
+        assert!(pas.list_services().is_empty());
+        let result = pas.resolve_alias("alias".to_string());
+        assert!(matches!(result, Err(ServiceError::NoSuchAlias(..))));
+    }
+
     // TODO: add more tests
     //       - add alias success & fail with service collision & test on rewriting alias
     //       - create_service success & fail
@@ -34,12 +34,20 @@ pub enum ServiceError {
     NoSuchServiceWithFunction { service: String, function: String },
     #[error("Service with alias '{0}' not found")]
     NoSuchAlias(String),
+    #[error("Call to {service}.{function} didn't complete in {timeout_ms} ms")]
+    CallTimedOut {
+        service: String,
+        function: String,
+        timeout_ms: u64,
+    },
     #[error("Forbidden. User id '{user}' cannot call function '{function}': {reason}")]
     Forbidden {
         user: PeerId,
         function: &'static str,
         reason: &'static str,
     },
+    #[error("Peer '{owner}' can't create more than {limit} services")]
+    ServicesLimitExceeded { owner: PeerId, limit: usize },
     #[error("Cannot add alias '{0}' because there is a service with that id")]
     AliasAsServiceId(String),
     #[error(transparent)]
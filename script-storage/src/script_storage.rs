@@ -73,6 +73,8 @@ pub struct Script {
     pub executions: u32,
     /// How many times to execute the script. None - till the end of this world.
     pub times: Option<u32>,
+    /// Script is removed after that many failures. None - use `ScriptStorageConfig::max_failures`
+    pub max_failures: Option<u8>,
 }
 
 impl Script {
@@ -82,6 +84,7 @@ impl Script {
         delay: Duration,
         creator: PeerId,
         times: Option<u32>,
+        max_failures: Option<u8>,
     ) -> Self {
         Self {
             src,
@@ -93,6 +96,7 @@ impl Script {
             creator,
             executions: 0,
             times,
+            max_failures,
         }
     }
 
@@ -100,6 +104,28 @@ impl Script {
     pub fn ready(&self, now: Instant) -> bool {
         self.next_execution <= now
     }
+
+    /// Changes schedule of the script, keeping its id, creator and failure history
+    pub fn reschedule(
+        &mut self,
+        interval: Option<Duration>,
+        delay: Option<Duration>,
+        now: Instant,
+    ) {
+        if let Some(interval) = interval {
+            self.interval = Some(interval);
+            // script with interval is executed until removed
+            self.times = None;
+            if let Some(executed_at) = self.executed_at {
+                self.next_execution = executed_at + interval;
+            }
+        }
+
+        if let Some(delay) = delay {
+            self.delay = delay;
+            self.next_execution = now + delay;
+        }
+    }
 }
 
 type ParticleId = String;
@@ -117,6 +143,7 @@ pub enum Command {
         interval: Option<Duration>,
         delay: Duration,
         creator: PeerId,
+        max_failures: Option<u8>,
     },
     RemoveScript {
         uuid: String,
@@ -124,6 +151,14 @@ pub enum Command {
         actor: PeerId,
         by_admin: bool,
     },
+    UpdateScript {
+        uuid: String,
+        interval: Option<Duration>,
+        delay: Option<Duration>,
+        outlet: OneshotOutlet<Result<bool, ScriptStorageError>>,
+        actor: PeerId,
+        by_admin: bool,
+    },
     ListScripts {
         outlet: OneshotOutlet<HashMap<ScriptId, Script>>,
     },
@@ -261,11 +296,12 @@ async fn execute_command(command: Command, scripts: &Mutex<HashMap<ScriptId, Scr
             interval,
             delay,
             creator,
+            max_failures,
         } => {
             let uuid = ScriptId(Arc::new(uuid));
             // If interval isn't set, script should be executed only once
             let times = if interval.is_none() { Some(1) } else { None };
-            let script = Script::new(script, interval, delay, creator, times);
+            let script = Script::new(script, interval, delay, creator, times, max_failures);
             unlock(scripts, |scripts| scripts.insert(uuid, script)).await;
         }
         Command::RemoveScript {
@@ -286,6 +322,26 @@ async fn execute_command(command: Command, scripts: &Mutex<HashMap<ScriptId, Scr
             .await;
             outlet.send(removed).ok();
         }
+        Command::UpdateScript {
+            uuid,
+            interval,
+            delay,
+            outlet,
+            actor,
+            by_admin,
+        } => {
+            let uuid = ScriptId(Arc::new(uuid));
+            let updated = unlock(scripts, |scripts| match scripts.get_mut(&uuid) {
+                None => Ok(false),
+                Some(script) if by_admin || script.creator == actor => {
+                    script.reschedule(interval, delay, Instant::now());
+                    Ok(true)
+                }
+                Some(_) => Err(ScriptStorageError::PermissionDenied),
+            })
+            .await;
+            outlet.send(updated).ok();
+        }
         Command::ListScripts { outlet } => {
             let scripts = unlock(scripts, |scripts| scripts.clone()).await;
             outlet.send(scripts).ok();
@@ -304,6 +360,7 @@ async fn remove_failed_scripts(
         unlock(scripts, |scripts| {
             if let Entry::Occupied(entry) = scripts.entry(script_id) {
                 let failures = entry.get().failures + 1;
+                let max_failures = entry.get().max_failures.unwrap_or(max_failures);
                 let id: &String = (*entry.key()).borrow();
                 log::debug!("Script {} failures {} max {}", id, failures, max_failures);
                 if failures < max_failures {
@@ -341,7 +398,9 @@ pub enum ScriptStorageError {
     OutletError,
     #[error("ScriptStorageError::InletError: can't receive response from script storage")]
     InletError,
-    #[error("ScriptStorageError::PermissionDenied: only the creator of a script can remove it")]
+    #[error(
+        "ScriptStorageError::PermissionDenied: only the creator of a script can remove or update it"
+    )]
     PermissionDenied,
 }
 
@@ -358,6 +417,7 @@ impl ScriptStorageApi {
         interval: Option<Duration>,
         delay: Duration,
         creator: PeerId,
+        max_failures: Option<u8>,
     ) -> Result<String, ScriptStorageError> {
         let uuid = uuid::Uuid::new_v4().to_string();
 
@@ -367,6 +427,7 @@ impl ScriptStorageApi {
             interval,
             delay,
             creator,
+            max_failures,
         })?;
 
         Ok(uuid)
@@ -395,6 +456,34 @@ impl ScriptStorageApi {
             .boxed()
     }
 
+    /// Changes interval and/or delay of the script, returns false if there's no such script
+    pub fn update_script(
+        &self,
+        uuid: String,
+        interval: Option<Duration>,
+        delay: Option<Duration>,
+        actor: PeerId,
+        by_admin: bool,
+    ) -> BoxFuture<'static, Result<bool, ScriptStorageError>> {
+        use ScriptStorageError::InletError;
+
+        let (outlet, inlet) = oneshot::channel();
+        let command = Command::UpdateScript {
+            uuid,
+            interval,
+            delay,
+            outlet,
+            actor,
+            by_admin,
+        };
+        if let Err(err) = self.send(command) {
+            return futures::future::err(err).boxed();
+        }
+        inlet
+            .map(|r| r.map_err(|_| InletError).and_then(identity))
+            .boxed()
+    }
+
     pub fn list_scripts(
         &self,
     ) -> BoxFuture<'static, Result<HashMap<ScriptId, Script>, ScriptStorageError>> {
@@ -23,6 +23,7 @@ use futures::{FutureExt, StreamExt};
 
 use aquamarine::AquamarineApi;
 use fluence_libp2p::types::Inlet;
+use fluence_libp2p::PeerId;
 use key_manager::KeyManager;
 use particle_args::JError;
 use particle_builtins::{wrap, wrap_unit};
@@ -34,8 +35,8 @@ use spell_event_bus::api::{from_user_config, SpellEventBusApi, TriggerEvent};
 use spell_storage::SpellStorage;
 
 use crate::spells::{
-    get_spell_arg, get_spell_id, scope_get_peer_id, spell_install, spell_list, spell_remove,
-    spell_update_config, store_error, store_response,
+    get_spell_arg, get_spell_cron, get_spell_id, scope_get_peer_id, spell_install, spell_list,
+    spell_list_detailed, spell_remove, spell_update_config, store_error, store_response,
 };
 use crate::utils::process_func_outcome;
 
@@ -47,6 +48,7 @@ pub struct Sorcerer {
     pub spell_event_bus_api: SpellEventBusApi,
     pub spell_script_particle_ttl: Duration,
     pub key_manager: KeyManager,
+    pub management_peer_id: PeerId,
 }
 
 pub struct SpellBuiltin {
@@ -94,6 +96,7 @@ impl Sorcerer {
             spell_event_bus_api,
             spell_script_particle_ttl: config.max_spell_particle_ttl,
             key_manager,
+            management_peer_id: config.management_peer_id,
         };
 
         let spell_service_functions = sorcerer.make_spell_builtins();
@@ -118,7 +121,18 @@ impl Sorcerer {
                     &spell_id,
                     "get_trigger_config",
                 )?;
-                let config = from_user_config(result.config)?;
+                let cron = get_spell_cron(
+                    &self.services,
+                    &spell_id,
+                    spell_owner,
+                    self.spell_script_particle_ttl,
+                )
+                .unwrap_or_else(|e| {
+                    // spells installed before cron support don't have the key at all
+                    log::warn!("Failed to get cron expression of spell {}: {}", spell_id, e);
+                    None
+                });
+                let config = from_user_config(result.config, cron.as_deref())?;
                 if let Some(config) = config {
                     self.spell_event_bus_api
                         .subscribe(spell_id.clone(), config.clone())
@@ -156,6 +170,7 @@ impl Sorcerer {
         spell_service.append("install", self.make_spell_install_closure());
         spell_service.append("remove", self.make_spell_remove_closure());
         spell_service.append("list", self.make_spell_list_closure());
+        spell_service.append("list_detailed", self.make_spell_list_detailed_closure());
         spell_service.append(
             "update_trigger_config",
             self.make_spell_update_config_closure(),
@@ -235,6 +250,28 @@ impl Sorcerer {
         })
     }
 
+    fn make_spell_list_detailed_closure(&self) -> ServiceFunction {
+        let storage = self.spell_storage.clone();
+        let services = self.services.clone();
+        let key_manager = self.key_manager.clone();
+        let management_peer_id = self.management_peer_id;
+        Box::new(move |_, params| {
+            let storage = storage.clone();
+            let services = services.clone();
+            let key_manager = key_manager.clone();
+            async move {
+                wrap(spell_list_detailed(
+                    params,
+                    storage,
+                    services,
+                    key_manager,
+                    management_peer_id,
+                ))
+            }
+            .boxed()
+        })
+    }
+
     fn make_spell_update_config_closure(&self) -> ServiceFunction {
         let api = self.spell_event_bus_api.clone();
         let services = self.services.clone();
@@ -13,11 +13,12 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
-use fluence_spell_dtos::value::{StringValue, UnitValue};
+use fluence_spell_dtos::value::{ScriptValue, StringValue, UnitValue};
 use serde_json::{json, Value as JValue, Value::Array};
 
 use crate::utils::{parse_spell_id_from, process_func_outcome};
-use fluence_spell_dtos::trigger_config::TriggerConfig;
+use fluence_libp2p::PeerId;
+use fluence_spell_dtos::trigger_config::{TriggerConfig, TriggerConfigValue};
 use key_manager::KeyManager;
 use particle_args::{Args, JError};
 use particle_execution::ParticleParams;
@@ -27,6 +28,9 @@ use spell_event_bus::{api, api::SpellEventBusApi};
 use spell_storage::SpellStorage;
 use std::time::Duration;
 
+/// Key in the spell's KV that holds the spell's cron expression, if any
+const CRON_KEY: &str = "trigger_cron";
+
 pub(crate) async fn spell_install(
     sargs: Args,
     params: ParticleParams,
@@ -39,7 +43,8 @@ pub(crate) async fn spell_install(
     let script: String = Args::next("script", &mut args)?;
     let init_data: String = Args::next("data", &mut args)?;
     let user_config: TriggerConfig = Args::next("config", &mut args)?;
-    let config = api::from_user_config(user_config.clone())?;
+    let cron: Option<String> = Args::next_opt("cron", &mut args)?;
+    let config = api::from_user_config(user_config.clone(), cron.as_deref())?;
 
     let spell_peer_id = key_manager.get_scope_peer_id(params.init_peer_id)?;
 
@@ -89,6 +94,15 @@ pub(crate) async fn spell_install(
         "set_trigger_config",
     )?;
 
+    // Save cron expression, so the spell is rescheduled with it after restart
+    store_cron(
+        &services,
+        &spell_id,
+        cron.as_deref().unwrap_or_default(),
+        spell_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    )?;
+
     if let Some(config) = config {
         // Scheduling the spell
         if let Err(err) = spell_event_bus_api
@@ -125,6 +139,64 @@ pub(crate) fn spell_list(spell_storage: SpellStorage) -> Result<JValue, JError>
     ))
 }
 
+/// Lists spells with their scripts and trigger configs.
+/// Management peer sees all spells, other peers only see spells they've created.
+pub(crate) fn spell_list_detailed(
+    params: ParticleParams,
+    spell_storage: SpellStorage,
+    services: ParticleAppServices,
+    key_manager: KeyManager,
+    management_peer_id: PeerId,
+) -> Result<JValue, JError> {
+    let init_peer_id = params.init_peer_id;
+    let is_management = init_peer_id == management_peer_id;
+    // don't generate a scope keypair just to list spells: peers without one have no spells
+    let scope_peer_id =
+        if key_manager.is_scope_peer_id(init_peer_id) || key_manager.has_keypair(init_peer_id) {
+            Some(key_manager.get_scope_peer_id(init_peer_id)?)
+        } else {
+            None
+        };
+    let ttl = Duration::from_millis(params.ttl as u64);
+
+    let mut spell_ids: Vec<_> = spell_storage.get_registered_spells().into_iter().collect();
+    spell_ids.sort();
+
+    let mut spells = vec![];
+    for spell_id in spell_ids {
+        let owner = services.get_service_owner(spell_id.clone())?;
+        if !is_management && Some(owner) != scope_peer_id {
+            continue;
+        }
+
+        let script = process_func_outcome::<ScriptValue>(
+            services.call_function(
+                &spell_id,
+                "get_script_source_from_file",
+                vec![],
+                None,
+                owner,
+                ttl,
+            ),
+            &spell_id,
+            "get_script_source_from_file",
+        )?;
+        let config = process_func_outcome::<TriggerConfigValue>(
+            services.call_function(&spell_id, "get_trigger_config", vec![], None, owner, ttl),
+            &spell_id,
+            "get_trigger_config",
+        )?;
+
+        spells.push(json!({
+            "spell_id": spell_id,
+            "script": script.source_code,
+            "trigger_config": config.config,
+        }));
+    }
+
+    Ok(Array(spells))
+}
+
 pub(crate) async fn spell_remove(
     args: Args,
     params: ParticleParams,
@@ -163,7 +235,8 @@ pub(crate) async fn spell_update_config(
     let spell_id: String = Args::next("spell_id", &mut args)?;
     let spell_peer_id = key_manager.get_scope_peer_id(params.init_peer_id)?;
     let user_config: TriggerConfig = Args::next("config", &mut args)?;
-    let config = api::from_user_config(user_config.clone())?;
+    let cron: Option<String> = Args::next_opt("cron", &mut args)?;
+    let config = api::from_user_config(user_config.clone(), cron.as_deref())?;
 
     process_func_outcome::<UnitValue>(
         services.call_function(
@@ -178,6 +251,15 @@ pub(crate) async fn spell_update_config(
         "set_trigger_config",
     )?;
 
+    // New config replaces the old one, so an absent cron expression removes the cron trigger
+    store_cron(
+        &services,
+        &spell_id,
+        cron.as_deref().unwrap_or_default(),
+        spell_peer_id,
+        Duration::from_millis(params.ttl as u64),
+    )?;
+
     let result: Result<(), EventBusError> = try {
         // we unsubscribe the spell from the current config anyway
         spell_event_bus_api.unsubscribe(spell_id.clone()).await?;
@@ -199,6 +281,50 @@ pub(crate) async fn spell_update_config(
     Ok(())
 }
 
+fn store_cron(
+    services: &ParticleAppServices,
+    spell_id: &str,
+    cron: &str,
+    spell_peer_id: PeerId,
+    ttl: Duration,
+) -> Result<(), JError> {
+    process_func_outcome::<UnitValue>(
+        services.call_function(
+            spell_id,
+            "set_string",
+            vec![json!(CRON_KEY), json!(cron)],
+            None,
+            spell_peer_id,
+            ttl,
+        ),
+        spell_id,
+        "set_string",
+    )?;
+    Ok(())
+}
+
+/// Returns the cron expression the spell was installed with, empty value means there's none
+pub(crate) fn get_spell_cron(
+    services: &ParticleAppServices,
+    spell_id: &str,
+    spell_peer_id: PeerId,
+    ttl: Duration,
+) -> Result<Option<String>, JError> {
+    let value = process_func_outcome::<StringValue>(
+        services.call_function(
+            spell_id,
+            "get_string",
+            vec![json!(CRON_KEY)],
+            None,
+            spell_peer_id,
+            ttl,
+        ),
+        spell_id,
+        "get_string",
+    )?;
+    Ok(Some(value.str).filter(|cron| !cron.is_empty()))
+}
+
 pub(crate) fn get_spell_id(params: ParticleParams) -> Result<JValue, JError> {
     Ok(json!(parse_spell_id_from(&params.id)?))
 }